@@ -1,3 +1,11 @@
+use std::path::Path;
+
 fn main() {
     embuild::espidf::sysenv::output();
+
+    // Keeps the calculator side C library in step with the message types
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    middlesp_codegen::generate(&root.join("proto/src"), &root.join("c"))
+        .expect("Failed to generate the C library");
+    println!("cargo:rerun-if-changed=proto/src");
 }
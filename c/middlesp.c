@@ -0,0 +1,3800 @@
+/* Generated by middlesp-codegen from proto/src, do not edit.
+ *
+ * Builders and parsers for the payloads inside the uart link's frames (see
+ * proto/src/frame.rs). A request payload is the id its response is sent back
+ * with followed by a CalcRequest, and a response payload is that id followed
+ * by a CalcResponse. */
+
+#include <string.h>
+
+#include "middlesp.h"
+
+void mesp_writer_init(mesp_writer_t *w, uint8_t *buf, size_t cap)
+{
+    w->buf = buf;
+    w->cap = cap;
+    w->len = 0;
+    w->error = false;
+}
+
+void mesp_reader_init(mesp_reader_t *r, const uint8_t *buf, size_t len)
+{
+    r->buf = buf;
+    r->len = len;
+    r->pos = 0;
+}
+
+mesp_reader_t mesp_list_reader(const mesp_list_t *list)
+{
+    mesp_reader_t r;
+    mesp_reader_init(&r, list->raw, list->raw_len);
+    return r;
+}
+
+mesp_str_t mesp_str(const char *s)
+{
+    mesp_str_t str;
+    str.ptr = s;
+    str.len = (uint32_t)strlen(s);
+    return str;
+}
+
+mesp_list_t mesp_list(const void *items, uint32_t count)
+{
+    mesp_list_t list;
+    list.count = count;
+    list.items = items;
+    list.raw = NULL;
+    list.raw_len = 0;
+    return list;
+}
+
+void mesp_write_array(mesp_writer_t *w, const uint8_t *v, uint32_t len)
+{
+    if (w->error || len > w->cap - w->len) {
+        w->error = true;
+        return;
+    }
+
+    memcpy(w->buf + w->len, v, len);
+    w->len += len;
+}
+
+void mesp_write_u8(mesp_writer_t *w, uint8_t v)
+{
+    mesp_write_array(w, &v, 1);
+}
+
+void mesp_write_u16(mesp_writer_t *w, uint16_t v)
+{
+    uint8_t b[2];
+    b[0] = (uint8_t)(v >> 8);
+    b[1] = (uint8_t)v;
+    mesp_write_array(w, b, 2);
+}
+
+void mesp_write_u32(mesp_writer_t *w, uint32_t v)
+{
+    uint8_t b[4];
+    b[0] = (uint8_t)(v >> 24);
+    b[1] = (uint8_t)(v >> 16);
+    b[2] = (uint8_t)(v >> 8);
+    b[3] = (uint8_t)v;
+    mesp_write_array(w, b, 4);
+}
+
+void mesp_write_i8(mesp_writer_t *w, int8_t v)
+{
+    mesp_write_u8(w, (uint8_t)v);
+}
+
+void mesp_write_i32(mesp_writer_t *w, int32_t v)
+{
+    mesp_write_u32(w, (uint32_t)v);
+}
+
+void mesp_write_bool(mesp_writer_t *w, bool v)
+{
+    mesp_write_u8(w, v ? 1 : 0);
+}
+
+void mesp_write_varint(mesp_writer_t *w, uint32_t v)
+{
+    while (v >= 0x80) {
+        mesp_write_u8(w, (uint8_t)(v | 0x80));
+        v >>= 7;
+    }
+    mesp_write_u8(w, (uint8_t)v);
+}
+
+void mesp_write_str(mesp_writer_t *w, mesp_str_t v)
+{
+    mesp_write_varint(w, v.len);
+    mesp_write_array(w, (const uint8_t *)v.ptr, v.len);
+}
+
+void mesp_write_bytes(mesp_writer_t *w, mesp_bytes_t v)
+{
+    mesp_write_varint(w, v.len);
+    mesp_write_array(w, v.ptr, v.len);
+}
+
+/* Points `out` at the next `len` bytes */
+static bool mesp_take(mesp_reader_t *r, const uint8_t **out, uint32_t len)
+{
+    if (len > r->len - r->pos) {
+        return false;
+    }
+
+    *out = r->buf + r->pos;
+    r->pos += len;
+    return true;
+}
+
+bool mesp_read_array(mesp_reader_t *r, uint8_t *out, uint32_t len)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, len)) {
+        return false;
+    }
+
+    memcpy(out, b, len);
+    return true;
+}
+
+bool mesp_read_u8(mesp_reader_t *r, uint8_t *out)
+{
+    return mesp_read_array(r, out, 1);
+}
+
+bool mesp_read_u16(mesp_reader_t *r, uint16_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, 2)) {
+        return false;
+    }
+
+    *out = (uint16_t)((uint16_t)b[0] << 8 | b[1]);
+    return true;
+}
+
+bool mesp_read_u32(mesp_reader_t *r, uint32_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, 4)) {
+        return false;
+    }
+
+    *out = (uint32_t)b[0] << 24 | (uint32_t)b[1] << 16 | (uint32_t)b[2] << 8 | b[3];
+    return true;
+}
+
+bool mesp_read_i8(mesp_reader_t *r, int8_t *out)
+{
+    return mesp_read_u8(r, (uint8_t *)out);
+}
+
+bool mesp_read_i32(mesp_reader_t *r, int32_t *out)
+{
+    return mesp_read_u32(r, (uint32_t *)out);
+}
+
+bool mesp_read_bool(mesp_reader_t *r, bool *out)
+{
+    uint8_t b;
+    if (!mesp_read_u8(r, &b) || b > 1) {
+        return false;
+    }
+
+    *out = b == 1;
+    return true;
+}
+
+bool mesp_read_varint(mesp_reader_t *r, uint32_t *out)
+{
+    uint32_t n = 0;
+    uint8_t i, b;
+
+    for (i = 0; i < 5; i++) {
+        if (!mesp_read_u8(r, &b)) {
+            return false;
+        }
+        /* Only the bottom 4 bits of the 5th byte are left for a u32 */
+        if (i == 4 && b > 0x0F) {
+            return false;
+        }
+
+        n |= (uint32_t)(b & 0x7F) << (7 * i);
+        if (!(b & 0x80)) {
+            *out = n;
+            return true;
+        }
+    }
+
+    return false;
+}
+
+bool mesp_read_str(mesp_reader_t *r, mesp_str_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_read_varint(r, &out->len) || !mesp_take(r, &b, out->len)) {
+        return false;
+    }
+
+    out->ptr = (const char *)b;
+    return true;
+}
+
+bool mesp_read_bytes(mesp_reader_t *r, mesp_bytes_t *out)
+{
+    return mesp_read_varint(r, &out->len) && mesp_take(r, &out->ptr, out->len);
+}
+
+bool mesp_read_list(mesp_reader_t *r, mesp_list_t *out, bool (*skip)(mesp_reader_t *))
+{
+    uint32_t i;
+    size_t start;
+
+    if (!mesp_read_varint(r, &out->count)) {
+        return false;
+    }
+
+    start = r->pos;
+    for (i = 0; i < out->count; i++) {
+        if (!skip(r)) {
+            return false;
+        }
+    }
+
+    out->items = NULL;
+    out->raw = r->buf + start;
+    out->raw_len = r->pos - start;
+    return true;
+}
+
+static bool mesp_skip_str_str(mesp_reader_t *r)
+{
+    mesp_str_str_t v;
+    return mesp_read_str_str(r, &v);
+}
+
+static void mesp_write_str_str_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_str_str_t *items = (const mesp_str_str_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_str_str(w, &items[i]);
+    }
+}
+
+static bool mesp_read_str_str_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_str_str);
+}
+
+static bool mesp_skip_str(mesp_reader_t *r)
+{
+    mesp_str_t v;
+    return mesp_read_str(r, &v);
+}
+
+static void mesp_write_str_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_str_t *items = (const mesp_str_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_str(w, items[i]);
+    }
+}
+
+static bool mesp_read_str_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_str);
+}
+
+static bool mesp_skip_number(mesp_reader_t *r)
+{
+    mesp_number_t v;
+    return mesp_read_number(r, &v);
+}
+
+static void mesp_write_number_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_number_t *items = (const mesp_number_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_number(w, &items[i]);
+    }
+}
+
+static bool mesp_read_number_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_number);
+}
+
+static bool mesp_skip_json_value(mesp_reader_t *r)
+{
+    mesp_json_value_t v;
+    return mesp_read_json_value(r, &v);
+}
+
+static void mesp_write_json_value_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_json_value_t *items = (const mesp_json_value_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_json_value(w, &items[i]);
+    }
+}
+
+static bool mesp_read_json_value_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_json_value);
+}
+
+static bool mesp_skip_access_point(mesp_reader_t *r)
+{
+    mesp_access_point_t v;
+    return mesp_read_access_point(r, &v);
+}
+
+static void mesp_write_access_point_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_access_point_t *items = (const mesp_access_point_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_access_point(w, &items[i]);
+    }
+}
+
+static bool mesp_read_access_point_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_access_point);
+}
+
+static bool mesp_skip_service_instance(mesp_reader_t *r)
+{
+    mesp_service_instance_t v;
+    return mesp_read_service_instance(r, &v);
+}
+
+static void mesp_write_service_instance_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_service_instance_t *items = (const mesp_service_instance_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_service_instance(w, &items[i]);
+    }
+}
+
+static bool mesp_read_service_instance_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_service_instance);
+}
+
+static bool mesp_skip_trace_record(mesp_reader_t *r)
+{
+    mesp_trace_record_t v;
+    return mesp_read_trace_record(r, &v);
+}
+
+static void mesp_write_trace_record_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_trace_record_t *items = (const mesp_trace_record_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_trace_record(w, &items[i]);
+    }
+}
+
+static bool mesp_read_trace_record_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_trace_record);
+}
+
+static bool mesp_skip_line(mesp_reader_t *r)
+{
+    mesp_line_t v;
+    return mesp_read_line(r, &v);
+}
+
+static void mesp_write_line_list(mesp_writer_t *w, const mesp_list_t *v)
+{
+    const mesp_line_t *items = (const mesp_line_t *)v->items;
+    uint32_t i;
+
+    mesp_write_varint(w, v->count);
+    for (i = 0; i < v->count; i++) {
+        mesp_write_line(w, &items[i]);
+    }
+}
+
+static bool mesp_read_line_list(mesp_reader_t *r, mesp_list_t *out)
+{
+    return mesp_read_list(r, out, mesp_skip_line);
+}
+
+void mesp_write_auth_method(mesp_writer_t *w, const mesp_auth_method_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_AUTH_METHOD_NONE:
+        break;
+    case MESP_AUTH_METHOD_WEP:
+        break;
+    case MESP_AUTH_METHOD_WPA:
+        break;
+    case MESP_AUTH_METHOD_WPA2_PERSONAL:
+        break;
+    case MESP_AUTH_METHOD_WPAWPA2_PERSONAL:
+        break;
+    case MESP_AUTH_METHOD_WPA2_ENTERPRISE:
+        break;
+    case MESP_AUTH_METHOD_WPA3_PERSONAL:
+        break;
+    case MESP_AUTH_METHOD_WPA2_WPA3_PERSONAL:
+        break;
+    case MESP_AUTH_METHOD_WAPI_PERSONAL:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_auth_method(mesp_reader_t *r, mesp_auth_method_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_AUTH_METHOD_NONE:
+        return true;
+    case MESP_AUTH_METHOD_WEP:
+        return true;
+    case MESP_AUTH_METHOD_WPA:
+        return true;
+    case MESP_AUTH_METHOD_WPA2_PERSONAL:
+        return true;
+    case MESP_AUTH_METHOD_WPAWPA2_PERSONAL:
+        return true;
+    case MESP_AUTH_METHOD_WPA2_ENTERPRISE:
+        return true;
+    case MESP_AUTH_METHOD_WPA3_PERSONAL:
+        return true;
+    case MESP_AUTH_METHOD_WPA2_WPA3_PERSONAL:
+        return true;
+    case MESP_AUTH_METHOD_WAPI_PERSONAL:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_auth_method_t mesp_auth_method_none(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_NONE;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wep(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WEP;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpa(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPA;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpa2_personal(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPA2_PERSONAL;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpawpa2_personal(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPAWPA2_PERSONAL;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpa2_enterprise(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPA2_ENTERPRISE;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpa3_personal(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPA3_PERSONAL;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wpa2_wpa3_personal(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WPA2_WPA3_PERSONAL;
+    return v;
+}
+
+mesp_auth_method_t mesp_auth_method_wapi_personal(void)
+{
+    mesp_auth_method_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_AUTH_METHOD_WAPI_PERSONAL;
+    return v;
+}
+
+void mesp_write_wifi_config(mesp_writer_t *w, const mesp_wifi_config_t *v)
+{
+    mesp_write_str(w, v->ssid);
+    mesp_write_str(w, v->password);
+    mesp_write_auth_method(w, &v->auth_method);
+}
+
+bool mesp_read_wifi_config(mesp_reader_t *r, mesp_wifi_config_t *out)
+{
+    return mesp_read_str(r, &out->ssid)
+        && mesp_read_str(r, &out->password)
+        && mesp_read_auth_method(r, &out->auth_method);
+}
+
+void mesp_write_wifi_actions(mesp_writer_t *w, const mesp_wifi_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_WIFI_ACTIONS_IS_STARTED:
+        break;
+    case MESP_WIFI_ACTIONS_IS_CONNECTED:
+        break;
+    case MESP_WIFI_ACTIONS_GET_CAPABILITIES:
+        break;
+    case MESP_WIFI_ACTIONS_START:
+        break;
+    case MESP_WIFI_ACTIONS_STOP:
+        break;
+    case MESP_WIFI_ACTIONS_SCAN:
+        break;
+    case MESP_WIFI_ACTIONS_CONNECT:
+        break;
+    case MESP_WIFI_ACTIONS_DISCONNECT:
+        break;
+    case MESP_WIFI_ACTIONS_SET_CONFIG:
+        mesp_write_wifi_config(w, &v->u.set_config);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_wifi_actions(mesp_reader_t *r, mesp_wifi_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_WIFI_ACTIONS_IS_STARTED:
+        return true;
+    case MESP_WIFI_ACTIONS_IS_CONNECTED:
+        return true;
+    case MESP_WIFI_ACTIONS_GET_CAPABILITIES:
+        return true;
+    case MESP_WIFI_ACTIONS_START:
+        return true;
+    case MESP_WIFI_ACTIONS_STOP:
+        return true;
+    case MESP_WIFI_ACTIONS_SCAN:
+        return true;
+    case MESP_WIFI_ACTIONS_CONNECT:
+        return true;
+    case MESP_WIFI_ACTIONS_DISCONNECT:
+        return true;
+    case MESP_WIFI_ACTIONS_SET_CONFIG:
+        return mesp_read_wifi_config(r, &out->u.set_config);
+    default:
+        return false;
+    }
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_is_started(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_IS_STARTED;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_is_connected(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_IS_CONNECTED;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_get_capabilities(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_GET_CAPABILITIES;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_start(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_START;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_stop(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_STOP;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_scan(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_SCAN;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_connect(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_CONNECT;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_disconnect(void)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_DISCONNECT;
+    return v;
+}
+
+mesp_wifi_actions_t mesp_wifi_actions_set_config(mesp_wifi_config_t value)
+{
+    mesp_wifi_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_ACTIONS_SET_CONFIG;
+    v.u.set_config = value;
+    return v;
+}
+
+void mesp_write_str_str(mesp_writer_t *w, const mesp_str_str_t *v)
+{
+    mesp_write_str(w, v->f0);
+    mesp_write_str(w, v->f1);
+}
+
+bool mesp_read_str_str(mesp_reader_t *r, mesp_str_str_t *out)
+{
+    return mesp_read_str(r, &out->f0)
+        && mesp_read_str(r, &out->f1);
+}
+
+void mesp_write_method_with_args(mesp_writer_t *w, const mesp_method_with_args_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_METHOD_WITH_ARGS_DELETE:
+        break;
+    case MESP_METHOD_WITH_ARGS_GET:
+        break;
+    case MESP_METHOD_WITH_ARGS_HEAD:
+        mesp_write_str_str_list(w, &v->u.head);
+        break;
+    case MESP_METHOD_WITH_ARGS_POST:
+        mesp_write_str_str_list(w, &v->u.post.f0);
+        mesp_write_str(w, v->u.post.f1);
+        break;
+    case MESP_METHOD_WITH_ARGS_PUT:
+        mesp_write_str_str_list(w, &v->u.put);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_method_with_args(mesp_reader_t *r, mesp_method_with_args_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_METHOD_WITH_ARGS_DELETE:
+        return true;
+    case MESP_METHOD_WITH_ARGS_GET:
+        return true;
+    case MESP_METHOD_WITH_ARGS_HEAD:
+        return mesp_read_str_str_list(r, &out->u.head);
+    case MESP_METHOD_WITH_ARGS_POST:
+        return mesp_read_str_str_list(r, &out->u.post.f0)
+            && mesp_read_str(r, &out->u.post.f1);
+    case MESP_METHOD_WITH_ARGS_PUT:
+        return mesp_read_str_str_list(r, &out->u.put);
+    default:
+        return false;
+    }
+}
+
+mesp_method_with_args_t mesp_method_with_args_delete(void)
+{
+    mesp_method_with_args_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_METHOD_WITH_ARGS_DELETE;
+    return v;
+}
+
+mesp_method_with_args_t mesp_method_with_args_get(void)
+{
+    mesp_method_with_args_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_METHOD_WITH_ARGS_GET;
+    return v;
+}
+
+mesp_method_with_args_t mesp_method_with_args_head(mesp_list_t value)
+{
+    mesp_method_with_args_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_METHOD_WITH_ARGS_HEAD;
+    v.u.head = value;
+    return v;
+}
+
+mesp_method_with_args_t mesp_method_with_args_post(mesp_list_t f0, mesp_str_t f1)
+{
+    mesp_method_with_args_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_METHOD_WITH_ARGS_POST;
+    v.u.post.f0 = f0;
+    v.u.post.f1 = f1;
+    return v;
+}
+
+mesp_method_with_args_t mesp_method_with_args_put(mesp_list_t value)
+{
+    mesp_method_with_args_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_METHOD_WITH_ARGS_PUT;
+    v.u.put = value;
+    return v;
+}
+
+void mesp_write_convert(mesp_writer_t *w, const mesp_convert_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_CONVERT_RAW:
+        break;
+    case MESP_CONVERT_TEXT:
+        break;
+    case MESP_CONVERT_JSON:
+        mesp_write_str_list(w, &v->u.json);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_convert(mesp_reader_t *r, mesp_convert_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_CONVERT_RAW:
+        return true;
+    case MESP_CONVERT_TEXT:
+        return true;
+    case MESP_CONVERT_JSON:
+        return mesp_read_str_list(r, &out->u.json);
+    default:
+        return false;
+    }
+}
+
+mesp_convert_t mesp_convert_raw(void)
+{
+    mesp_convert_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CONVERT_RAW;
+    return v;
+}
+
+mesp_convert_t mesp_convert_text(void)
+{
+    mesp_convert_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CONVERT_TEXT;
+    return v;
+}
+
+mesp_convert_t mesp_convert_json(mesp_list_t value)
+{
+    mesp_convert_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CONVERT_JSON;
+    v.u.json = value;
+    return v;
+}
+
+void mesp_write_http_req(mesp_writer_t *w, const mesp_http_req_t *v)
+{
+    mesp_write_str(w, v->url);
+    mesp_write_bool(w, v->close);
+    mesp_write_method_with_args(w, &v->extra);
+    mesp_write_convert(w, &v->convert);
+}
+
+bool mesp_read_http_req(mesp_reader_t *r, mesp_http_req_t *out)
+{
+    return mesp_read_str(r, &out->url)
+        && mesp_read_bool(r, &out->close)
+        && mesp_read_method_with_args(r, &out->extra)
+        && mesp_read_convert(r, &out->convert);
+}
+
+void mesp_write_mdns_actions(mesp_writer_t *w, const mesp_mdns_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_MDNS_ACTIONS_SET_HOSTNAME:
+        mesp_write_str(w, v->u.set_hostname);
+        break;
+    case MESP_MDNS_ACTIONS_BROWSE:
+        mesp_write_str(w, v->u.browse);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_mdns_actions(mesp_reader_t *r, mesp_mdns_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_MDNS_ACTIONS_SET_HOSTNAME:
+        return mesp_read_str(r, &out->u.set_hostname);
+    case MESP_MDNS_ACTIONS_BROWSE:
+        return mesp_read_str(r, &out->u.browse);
+    default:
+        return false;
+    }
+}
+
+mesp_mdns_actions_t mesp_mdns_actions_set_hostname(mesp_str_t value)
+{
+    mesp_mdns_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MDNS_ACTIONS_SET_HOSTNAME;
+    v.u.set_hostname = value;
+    return v;
+}
+
+mesp_mdns_actions_t mesp_mdns_actions_browse(mesp_str_t value)
+{
+    mesp_mdns_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MDNS_ACTIONS_BROWSE;
+    v.u.browse = value;
+    return v;
+}
+
+void mesp_write_uart_settings(mesp_writer_t *w, const mesp_uart_settings_t *v)
+{
+    mesp_write_u32(w, v->baud);
+    mesp_write_u8(w, v->parity);
+    mesp_write_u8(w, v->stop_bits);
+    mesp_write_u8(w, v->tx);
+    mesp_write_u8(w, v->rx);
+    mesp_write_u8(w, v->rts);
+    mesp_write_u8(w, v->cts);
+}
+
+bool mesp_read_uart_settings(mesp_reader_t *r, mesp_uart_settings_t *out)
+{
+    return mesp_read_u32(r, &out->baud)
+        && mesp_read_u8(r, &out->parity)
+        && mesp_read_u8(r, &out->stop_bits)
+        && mesp_read_u8(r, &out->tx)
+        && mesp_read_u8(r, &out->rx)
+        && mesp_read_u8(r, &out->rts)
+        && mesp_read_u8(r, &out->cts);
+}
+
+void mesp_write_mode(mesp_writer_t *w, const mesp_mode_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_MODE_BINARY:
+        break;
+    case MESP_MODE_TEXT:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_mode(mesp_reader_t *r, mesp_mode_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_MODE_BINARY:
+        return true;
+    case MESP_MODE_TEXT:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_mode_t mesp_mode_binary(void)
+{
+    mesp_mode_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MODE_BINARY;
+    return v;
+}
+
+mesp_mode_t mesp_mode_text(void)
+{
+    mesp_mode_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MODE_TEXT;
+    return v;
+}
+
+void mesp_write_trace_actions(mesp_writer_t *w, const mesp_trace_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_TRACE_ACTIONS_START:
+        break;
+    case MESP_TRACE_ACTIONS_STOP:
+        break;
+    case MESP_TRACE_ACTIONS_TAKE:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_trace_actions(mesp_reader_t *r, mesp_trace_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_TRACE_ACTIONS_START:
+        return true;
+    case MESP_TRACE_ACTIONS_STOP:
+        return true;
+    case MESP_TRACE_ACTIONS_TAKE:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_trace_actions_t mesp_trace_actions_start(void)
+{
+    mesp_trace_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_ACTIONS_START;
+    return v;
+}
+
+mesp_trace_actions_t mesp_trace_actions_stop(void)
+{
+    mesp_trace_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_ACTIONS_STOP;
+    return v;
+}
+
+mesp_trace_actions_t mesp_trace_actions_take(void)
+{
+    mesp_trace_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_ACTIONS_TAKE;
+    return v;
+}
+
+void mesp_write_tcp_actions(mesp_writer_t *w, const mesp_tcp_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_TCP_ACTIONS_OPEN:
+        mesp_write_str(w, v->u.open.host);
+        mesp_write_u16(w, v->u.open.port);
+        mesp_write_bool(w, v->u.open.tls);
+        break;
+    case MESP_TCP_ACTIONS_WRITE:
+        mesp_write_u8(w, v->u.write.f0);
+        mesp_write_bytes(w, v->u.write.f1);
+        break;
+    case MESP_TCP_ACTIONS_READ:
+        mesp_write_u8(w, v->u.read.f0);
+        mesp_write_u16(w, v->u.read.f1);
+        break;
+    case MESP_TCP_ACTIONS_CLOSE:
+        mesp_write_u8(w, v->u.close);
+        break;
+    case MESP_TCP_ACTIONS_LISTEN:
+        mesp_write_u16(w, v->u.listen);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_tcp_actions(mesp_reader_t *r, mesp_tcp_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_TCP_ACTIONS_OPEN:
+        return mesp_read_str(r, &out->u.open.host)
+            && mesp_read_u16(r, &out->u.open.port)
+            && mesp_read_bool(r, &out->u.open.tls);
+    case MESP_TCP_ACTIONS_WRITE:
+        return mesp_read_u8(r, &out->u.write.f0)
+            && mesp_read_bytes(r, &out->u.write.f1);
+    case MESP_TCP_ACTIONS_READ:
+        return mesp_read_u8(r, &out->u.read.f0)
+            && mesp_read_u16(r, &out->u.read.f1);
+    case MESP_TCP_ACTIONS_CLOSE:
+        return mesp_read_u8(r, &out->u.close);
+    case MESP_TCP_ACTIONS_LISTEN:
+        return mesp_read_u16(r, &out->u.listen);
+    default:
+        return false;
+    }
+}
+
+mesp_tcp_actions_t mesp_tcp_actions_open(mesp_str_t host, uint16_t port, bool tls)
+{
+    mesp_tcp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_ACTIONS_OPEN;
+    v.u.open.host = host;
+    v.u.open.port = port;
+    v.u.open.tls = tls;
+    return v;
+}
+
+mesp_tcp_actions_t mesp_tcp_actions_write(uint8_t f0, mesp_bytes_t f1)
+{
+    mesp_tcp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_ACTIONS_WRITE;
+    v.u.write.f0 = f0;
+    v.u.write.f1 = f1;
+    return v;
+}
+
+mesp_tcp_actions_t mesp_tcp_actions_read(uint8_t f0, uint16_t f1)
+{
+    mesp_tcp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_ACTIONS_READ;
+    v.u.read.f0 = f0;
+    v.u.read.f1 = f1;
+    return v;
+}
+
+mesp_tcp_actions_t mesp_tcp_actions_close(uint8_t value)
+{
+    mesp_tcp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_ACTIONS_CLOSE;
+    v.u.close = value;
+    return v;
+}
+
+mesp_tcp_actions_t mesp_tcp_actions_listen(uint16_t value)
+{
+    mesp_tcp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_ACTIONS_LISTEN;
+    v.u.listen = value;
+    return v;
+}
+
+void mesp_write_udp_actions(mesp_writer_t *w, const mesp_udp_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_UDP_ACTIONS_BIND:
+        mesp_write_u16(w, v->u.bind);
+        break;
+    case MESP_UDP_ACTIONS_SEND:
+        mesp_write_u8(w, v->u.send.handle);
+        mesp_write_str(w, v->u.send.host);
+        mesp_write_u16(w, v->u.send.port);
+        mesp_write_bytes(w, v->u.send.data);
+        break;
+    case MESP_UDP_ACTIONS_RECV:
+        mesp_write_u8(w, v->u.recv);
+        break;
+    case MESP_UDP_ACTIONS_CLOSE:
+        mesp_write_u8(w, v->u.close);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_udp_actions(mesp_reader_t *r, mesp_udp_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_UDP_ACTIONS_BIND:
+        return mesp_read_u16(r, &out->u.bind);
+    case MESP_UDP_ACTIONS_SEND:
+        return mesp_read_u8(r, &out->u.send.handle)
+            && mesp_read_str(r, &out->u.send.host)
+            && mesp_read_u16(r, &out->u.send.port)
+            && mesp_read_bytes(r, &out->u.send.data);
+    case MESP_UDP_ACTIONS_RECV:
+        return mesp_read_u8(r, &out->u.recv);
+    case MESP_UDP_ACTIONS_CLOSE:
+        return mesp_read_u8(r, &out->u.close);
+    default:
+        return false;
+    }
+}
+
+mesp_udp_actions_t mesp_udp_actions_bind(uint16_t value)
+{
+    mesp_udp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_ACTIONS_BIND;
+    v.u.bind = value;
+    return v;
+}
+
+mesp_udp_actions_t mesp_udp_actions_send(uint8_t handle, mesp_str_t host, uint16_t port, mesp_bytes_t data)
+{
+    mesp_udp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_ACTIONS_SEND;
+    v.u.send.handle = handle;
+    v.u.send.host = host;
+    v.u.send.port = port;
+    v.u.send.data = data;
+    return v;
+}
+
+mesp_udp_actions_t mesp_udp_actions_recv(uint8_t value)
+{
+    mesp_udp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_ACTIONS_RECV;
+    v.u.recv = value;
+    return v;
+}
+
+mesp_udp_actions_t mesp_udp_actions_close(uint8_t value)
+{
+    mesp_udp_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_ACTIONS_CLOSE;
+    v.u.close = value;
+    return v;
+}
+
+void mesp_write_ws_message(mesp_writer_t *w, const mesp_ws_message_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_WS_MESSAGE_TEXT:
+        mesp_write_str(w, v->u.text);
+        break;
+    case MESP_WS_MESSAGE_BINARY:
+        mesp_write_bytes(w, v->u.binary);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_ws_message(mesp_reader_t *r, mesp_ws_message_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_WS_MESSAGE_TEXT:
+        return mesp_read_str(r, &out->u.text);
+    case MESP_WS_MESSAGE_BINARY:
+        return mesp_read_bytes(r, &out->u.binary);
+    default:
+        return false;
+    }
+}
+
+mesp_ws_message_t mesp_ws_message_text(mesp_str_t value)
+{
+    mesp_ws_message_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_MESSAGE_TEXT;
+    v.u.text = value;
+    return v;
+}
+
+mesp_ws_message_t mesp_ws_message_binary(mesp_bytes_t value)
+{
+    mesp_ws_message_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_MESSAGE_BINARY;
+    v.u.binary = value;
+    return v;
+}
+
+void mesp_write_ws_actions(mesp_writer_t *w, const mesp_ws_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_WS_ACTIONS_CONNECT:
+        mesp_write_str(w, v->u.connect.url);
+        mesp_write_str_str_list(w, &v->u.connect.headers);
+        break;
+    case MESP_WS_ACTIONS_SEND:
+        mesp_write_u8(w, v->u.send.f0);
+        mesp_write_ws_message(w, &v->u.send.f1);
+        break;
+    case MESP_WS_ACTIONS_RECV:
+        mesp_write_u8(w, v->u.recv);
+        break;
+    case MESP_WS_ACTIONS_CLOSE:
+        mesp_write_u8(w, v->u.close);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_ws_actions(mesp_reader_t *r, mesp_ws_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_WS_ACTIONS_CONNECT:
+        return mesp_read_str(r, &out->u.connect.url)
+            && mesp_read_str_str_list(r, &out->u.connect.headers);
+    case MESP_WS_ACTIONS_SEND:
+        return mesp_read_u8(r, &out->u.send.f0)
+            && mesp_read_ws_message(r, &out->u.send.f1);
+    case MESP_WS_ACTIONS_RECV:
+        return mesp_read_u8(r, &out->u.recv);
+    case MESP_WS_ACTIONS_CLOSE:
+        return mesp_read_u8(r, &out->u.close);
+    default:
+        return false;
+    }
+}
+
+mesp_ws_actions_t mesp_ws_actions_connect(mesp_str_t url, mesp_list_t headers)
+{
+    mesp_ws_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_ACTIONS_CONNECT;
+    v.u.connect.url = url;
+    v.u.connect.headers = headers;
+    return v;
+}
+
+mesp_ws_actions_t mesp_ws_actions_send(uint8_t f0, mesp_ws_message_t f1)
+{
+    mesp_ws_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_ACTIONS_SEND;
+    v.u.send.f0 = f0;
+    v.u.send.f1 = f1;
+    return v;
+}
+
+mesp_ws_actions_t mesp_ws_actions_recv(uint8_t value)
+{
+    mesp_ws_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_ACTIONS_RECV;
+    v.u.recv = value;
+    return v;
+}
+
+mesp_ws_actions_t mesp_ws_actions_close(uint8_t value)
+{
+    mesp_ws_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_ACTIONS_CLOSE;
+    v.u.close = value;
+    return v;
+}
+
+void mesp_write_mqtt_config(mesp_writer_t *w, const mesp_mqtt_config_t *v)
+{
+    mesp_write_str(w, v->url);
+    mesp_write_str(w, v->client_id);
+    mesp_write_str(w, v->username);
+    mesp_write_str(w, v->password);
+}
+
+bool mesp_read_mqtt_config(mesp_reader_t *r, mesp_mqtt_config_t *out)
+{
+    return mesp_read_str(r, &out->url)
+        && mesp_read_str(r, &out->client_id)
+        && mesp_read_str(r, &out->username)
+        && mesp_read_str(r, &out->password);
+}
+
+void mesp_write_qos(mesp_writer_t *w, const mesp_qos_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_QOS_AT_MOST_ONCE:
+        break;
+    case MESP_QOS_AT_LEAST_ONCE:
+        break;
+    case MESP_QOS_EXACTLY_ONCE:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_qos(mesp_reader_t *r, mesp_qos_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_QOS_AT_MOST_ONCE:
+        return true;
+    case MESP_QOS_AT_LEAST_ONCE:
+        return true;
+    case MESP_QOS_EXACTLY_ONCE:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_qos_t mesp_qos_at_most_once(void)
+{
+    mesp_qos_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_QOS_AT_MOST_ONCE;
+    return v;
+}
+
+mesp_qos_t mesp_qos_at_least_once(void)
+{
+    mesp_qos_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_QOS_AT_LEAST_ONCE;
+    return v;
+}
+
+mesp_qos_t mesp_qos_exactly_once(void)
+{
+    mesp_qos_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_QOS_EXACTLY_ONCE;
+    return v;
+}
+
+void mesp_write_mqtt_actions(mesp_writer_t *w, const mesp_mqtt_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_MQTT_ACTIONS_CONNECT:
+        mesp_write_mqtt_config(w, &v->u.connect);
+        break;
+    case MESP_MQTT_ACTIONS_PUBLISH:
+        mesp_write_u8(w, v->u.publish.handle);
+        mesp_write_str(w, v->u.publish.topic);
+        mesp_write_bytes(w, v->u.publish.payload);
+        mesp_write_qos(w, &v->u.publish.qos);
+        mesp_write_bool(w, v->u.publish.retain);
+        break;
+    case MESP_MQTT_ACTIONS_SUBSCRIBE:
+        mesp_write_u8(w, v->u.subscribe.handle);
+        mesp_write_str(w, v->u.subscribe.filter);
+        mesp_write_qos(w, &v->u.subscribe.qos);
+        break;
+    case MESP_MQTT_ACTIONS_UNSUBSCRIBE:
+        mesp_write_u8(w, v->u.unsubscribe.handle);
+        mesp_write_str(w, v->u.unsubscribe.filter);
+        break;
+    case MESP_MQTT_ACTIONS_RECV:
+        mesp_write_u8(w, v->u.recv.handle);
+        mesp_write_str(w, v->u.recv.filter);
+        break;
+    case MESP_MQTT_ACTIONS_CLOSE:
+        mesp_write_u8(w, v->u.close);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_mqtt_actions(mesp_reader_t *r, mesp_mqtt_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_MQTT_ACTIONS_CONNECT:
+        return mesp_read_mqtt_config(r, &out->u.connect);
+    case MESP_MQTT_ACTIONS_PUBLISH:
+        return mesp_read_u8(r, &out->u.publish.handle)
+            && mesp_read_str(r, &out->u.publish.topic)
+            && mesp_read_bytes(r, &out->u.publish.payload)
+            && mesp_read_qos(r, &out->u.publish.qos)
+            && mesp_read_bool(r, &out->u.publish.retain);
+    case MESP_MQTT_ACTIONS_SUBSCRIBE:
+        return mesp_read_u8(r, &out->u.subscribe.handle)
+            && mesp_read_str(r, &out->u.subscribe.filter)
+            && mesp_read_qos(r, &out->u.subscribe.qos);
+    case MESP_MQTT_ACTIONS_UNSUBSCRIBE:
+        return mesp_read_u8(r, &out->u.unsubscribe.handle)
+            && mesp_read_str(r, &out->u.unsubscribe.filter);
+    case MESP_MQTT_ACTIONS_RECV:
+        return mesp_read_u8(r, &out->u.recv.handle)
+            && mesp_read_str(r, &out->u.recv.filter);
+    case MESP_MQTT_ACTIONS_CLOSE:
+        return mesp_read_u8(r, &out->u.close);
+    default:
+        return false;
+    }
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_connect(mesp_mqtt_config_t value)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_CONNECT;
+    v.u.connect = value;
+    return v;
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_publish(uint8_t handle, mesp_str_t topic, mesp_bytes_t payload, mesp_qos_t qos, bool retain)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_PUBLISH;
+    v.u.publish.handle = handle;
+    v.u.publish.topic = topic;
+    v.u.publish.payload = payload;
+    v.u.publish.qos = qos;
+    v.u.publish.retain = retain;
+    return v;
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_subscribe(uint8_t handle, mesp_str_t filter, mesp_qos_t qos)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_SUBSCRIBE;
+    v.u.subscribe.handle = handle;
+    v.u.subscribe.filter = filter;
+    v.u.subscribe.qos = qos;
+    return v;
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_unsubscribe(uint8_t handle, mesp_str_t filter)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_UNSUBSCRIBE;
+    v.u.unsubscribe.handle = handle;
+    v.u.unsubscribe.filter = filter;
+    return v;
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_recv(uint8_t handle, mesp_str_t filter)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_RECV;
+    v.u.recv.handle = handle;
+    v.u.recv.filter = filter;
+    return v;
+}
+
+mesp_mqtt_actions_t mesp_mqtt_actions_close(uint8_t value)
+{
+    mesp_mqtt_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_ACTIONS_CLOSE;
+    v.u.close = value;
+    return v;
+}
+
+void mesp_write_sse_actions(mesp_writer_t *w, const mesp_sse_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_SSE_ACTIONS_SUBSCRIBE:
+        mesp_write_str(w, v->u.subscribe.url);
+        mesp_write_str_str_list(w, &v->u.subscribe.headers);
+        break;
+    case MESP_SSE_ACTIONS_RECV:
+        mesp_write_u8(w, v->u.recv);
+        break;
+    case MESP_SSE_ACTIONS_CLOSE:
+        mesp_write_u8(w, v->u.close);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_sse_actions(mesp_reader_t *r, mesp_sse_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_SSE_ACTIONS_SUBSCRIBE:
+        return mesp_read_str(r, &out->u.subscribe.url)
+            && mesp_read_str_str_list(r, &out->u.subscribe.headers);
+    case MESP_SSE_ACTIONS_RECV:
+        return mesp_read_u8(r, &out->u.recv);
+    case MESP_SSE_ACTIONS_CLOSE:
+        return mesp_read_u8(r, &out->u.close);
+    default:
+        return false;
+    }
+}
+
+mesp_sse_actions_t mesp_sse_actions_subscribe(mesp_str_t url, mesp_list_t headers)
+{
+    mesp_sse_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_ACTIONS_SUBSCRIBE;
+    v.u.subscribe.url = url;
+    v.u.subscribe.headers = headers;
+    return v;
+}
+
+mesp_sse_actions_t mesp_sse_actions_recv(uint8_t value)
+{
+    mesp_sse_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_ACTIONS_RECV;
+    v.u.recv = value;
+    return v;
+}
+
+mesp_sse_actions_t mesp_sse_actions_close(uint8_t value)
+{
+    mesp_sse_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_ACTIONS_CLOSE;
+    v.u.close = value;
+    return v;
+}
+
+void mesp_write_fetch_actions(mesp_writer_t *w, const mesp_fetch_actions_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_FETCH_ACTIONS_GET:
+        mesp_write_str(w, v->u.get.url);
+        mesp_write_str(w, v->u.get.input);
+        break;
+    case MESP_FETCH_ACTIONS_FORGET:
+        mesp_write_str(w, v->u.forget);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_fetch_actions(mesp_reader_t *r, mesp_fetch_actions_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_FETCH_ACTIONS_GET:
+        return mesp_read_str(r, &out->u.get.url)
+            && mesp_read_str(r, &out->u.get.input);
+    case MESP_FETCH_ACTIONS_FORGET:
+        return mesp_read_str(r, &out->u.forget);
+    default:
+        return false;
+    }
+}
+
+mesp_fetch_actions_t mesp_fetch_actions_get(mesp_str_t url, mesp_str_t input)
+{
+    mesp_fetch_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_ACTIONS_GET;
+    v.u.get.url = url;
+    v.u.get.input = input;
+    return v;
+}
+
+mesp_fetch_actions_t mesp_fetch_actions_forget(mesp_str_t value)
+{
+    mesp_fetch_actions_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_ACTIONS_FORGET;
+    v.u.forget = value;
+    return v;
+}
+
+void mesp_write_calc_request(mesp_writer_t *w, const mesp_calc_request_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_CALC_REQUEST_WIFI:
+        mesp_write_wifi_actions(w, &v->u.wifi);
+        break;
+    case MESP_CALC_REQUEST_HTTP:
+        mesp_write_http_req(w, &v->u.http);
+        break;
+    case MESP_CALC_REQUEST_MDNS:
+        mesp_write_mdns_actions(w, &v->u.mdns);
+        break;
+    case MESP_CALC_REQUEST_CANCEL:
+        mesp_write_u8(w, v->u.cancel);
+        break;
+    case MESP_CALC_REQUEST_PING:
+        break;
+    case MESP_CALC_REQUEST_SET_MAX_FRAME:
+        mesp_write_u16(w, v->u.set_max_frame);
+        break;
+    case MESP_CALC_REQUEST_SET_UART:
+        mesp_write_uart_settings(w, &v->u.set_uart);
+        break;
+    case MESP_CALC_REQUEST_SET_MODE:
+        mesp_write_mode(w, &v->u.set_mode);
+        break;
+    case MESP_CALC_REQUEST_TRACE:
+        mesp_write_trace_actions(w, &v->u.trace);
+        break;
+    case MESP_CALC_REQUEST_TCP:
+        mesp_write_tcp_actions(w, &v->u.tcp);
+        break;
+    case MESP_CALC_REQUEST_UDP:
+        mesp_write_udp_actions(w, &v->u.udp);
+        break;
+    case MESP_CALC_REQUEST_WS:
+        mesp_write_ws_actions(w, &v->u.ws);
+        break;
+    case MESP_CALC_REQUEST_MQTT:
+        mesp_write_mqtt_actions(w, &v->u.mqtt);
+        break;
+    case MESP_CALC_REQUEST_SSE:
+        mesp_write_sse_actions(w, &v->u.sse);
+        break;
+    case MESP_CALC_REQUEST_FETCH:
+        mesp_write_fetch_actions(w, &v->u.fetch);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_calc_request(mesp_reader_t *r, mesp_calc_request_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_CALC_REQUEST_WIFI:
+        return mesp_read_wifi_actions(r, &out->u.wifi);
+    case MESP_CALC_REQUEST_HTTP:
+        return mesp_read_http_req(r, &out->u.http);
+    case MESP_CALC_REQUEST_MDNS:
+        return mesp_read_mdns_actions(r, &out->u.mdns);
+    case MESP_CALC_REQUEST_CANCEL:
+        return mesp_read_u8(r, &out->u.cancel);
+    case MESP_CALC_REQUEST_PING:
+        return true;
+    case MESP_CALC_REQUEST_SET_MAX_FRAME:
+        return mesp_read_u16(r, &out->u.set_max_frame);
+    case MESP_CALC_REQUEST_SET_UART:
+        return mesp_read_uart_settings(r, &out->u.set_uart);
+    case MESP_CALC_REQUEST_SET_MODE:
+        return mesp_read_mode(r, &out->u.set_mode);
+    case MESP_CALC_REQUEST_TRACE:
+        return mesp_read_trace_actions(r, &out->u.trace);
+    case MESP_CALC_REQUEST_TCP:
+        return mesp_read_tcp_actions(r, &out->u.tcp);
+    case MESP_CALC_REQUEST_UDP:
+        return mesp_read_udp_actions(r, &out->u.udp);
+    case MESP_CALC_REQUEST_WS:
+        return mesp_read_ws_actions(r, &out->u.ws);
+    case MESP_CALC_REQUEST_MQTT:
+        return mesp_read_mqtt_actions(r, &out->u.mqtt);
+    case MESP_CALC_REQUEST_SSE:
+        return mesp_read_sse_actions(r, &out->u.sse);
+    case MESP_CALC_REQUEST_FETCH:
+        return mesp_read_fetch_actions(r, &out->u.fetch);
+    default:
+        return false;
+    }
+}
+
+mesp_calc_request_t mesp_calc_request_wifi(mesp_wifi_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_WIFI;
+    v.u.wifi = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_http(mesp_http_req_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_HTTP;
+    v.u.http = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_mdns(mesp_mdns_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_MDNS;
+    v.u.mdns = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_cancel(uint8_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_CANCEL;
+    v.u.cancel = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_ping(void)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_PING;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_set_max_frame(uint16_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_SET_MAX_FRAME;
+    v.u.set_max_frame = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_set_uart(mesp_uart_settings_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_SET_UART;
+    v.u.set_uart = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_set_mode(mesp_mode_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_SET_MODE;
+    v.u.set_mode = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_trace(mesp_trace_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_TRACE;
+    v.u.trace = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_tcp(mesp_tcp_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_TCP;
+    v.u.tcp = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_udp(mesp_udp_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_UDP;
+    v.u.udp = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_ws(mesp_ws_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_WS;
+    v.u.ws = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_mqtt(mesp_mqtt_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_MQTT;
+    v.u.mqtt = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_sse(mesp_sse_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_SSE;
+    v.u.sse = value;
+    return v;
+}
+
+mesp_calc_request_t mesp_calc_request_fetch(mesp_fetch_actions_t value)
+{
+    mesp_calc_request_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_REQUEST_FETCH;
+    v.u.fetch = value;
+    return v;
+}
+
+void mesp_write_number(mesp_writer_t *w, const mesp_number_t *v)
+{
+    mesp_write_i32(w, v->mantissa);
+    mesp_write_i8(w, v->exponent);
+}
+
+bool mesp_read_number(mesp_reader_t *r, mesp_number_t *out)
+{
+    return mesp_read_i32(r, &out->mantissa)
+        && mesp_read_i8(r, &out->exponent);
+}
+
+void mesp_write_json_value(mesp_writer_t *w, const mesp_json_value_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_JSON_VALUE_MISSING:
+        break;
+    case MESP_JSON_VALUE_NULL:
+        break;
+    case MESP_JSON_VALUE_BOOL:
+        mesp_write_bool(w, v->u.bool_);
+        break;
+    case MESP_JSON_VALUE_NUMBER:
+        mesp_write_number(w, &v->u.number);
+        break;
+    case MESP_JSON_VALUE_STRING:
+        mesp_write_str(w, v->u.string);
+        break;
+    case MESP_JSON_VALUE_NUMBERS:
+        mesp_write_number_list(w, &v->u.numbers);
+        break;
+    case MESP_JSON_VALUE_OTHER:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_json_value(mesp_reader_t *r, mesp_json_value_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_JSON_VALUE_MISSING:
+        return true;
+    case MESP_JSON_VALUE_NULL:
+        return true;
+    case MESP_JSON_VALUE_BOOL:
+        return mesp_read_bool(r, &out->u.bool_);
+    case MESP_JSON_VALUE_NUMBER:
+        return mesp_read_number(r, &out->u.number);
+    case MESP_JSON_VALUE_STRING:
+        return mesp_read_str(r, &out->u.string);
+    case MESP_JSON_VALUE_NUMBERS:
+        return mesp_read_number_list(r, &out->u.numbers);
+    case MESP_JSON_VALUE_OTHER:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_json_value_t mesp_json_value_missing(void)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_MISSING;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_null(void)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_NULL;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_bool(bool value)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_BOOL;
+    v.u.bool_ = value;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_number(mesp_number_t value)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_NUMBER;
+    v.u.number = value;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_string(mesp_str_t value)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_STRING;
+    v.u.string = value;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_numbers(mesp_list_t value)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_NUMBERS;
+    v.u.numbers = value;
+    return v;
+}
+
+mesp_json_value_t mesp_json_value_other(void)
+{
+    mesp_json_value_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_JSON_VALUE_OTHER;
+    return v;
+}
+
+void mesp_write_http_resp(mesp_writer_t *w, const mesp_http_resp_t *v)
+{
+    mesp_write_bytes(w, v->raw);
+    mesp_write_str_list(w, &v->links);
+    mesp_write_json_value_list(w, &v->values);
+}
+
+bool mesp_read_http_resp(mesp_reader_t *r, mesp_http_resp_t *out)
+{
+    return mesp_read_bytes(r, &out->raw)
+        && mesp_read_str_list(r, &out->links)
+        && mesp_read_json_value_list(r, &out->values);
+}
+
+void mesp_write_http_resp_result(mesp_writer_t *w, const mesp_http_resp_result_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_HTTP_RESP_RESULT_OK:
+        mesp_write_http_resp(w, &v->u.ok);
+        break;
+    case MESP_HTTP_RESP_RESULT_ERR:
+        mesp_write_i32(w, v->u.err);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_http_resp_result(mesp_reader_t *r, mesp_http_resp_result_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_HTTP_RESP_RESULT_OK:
+        return mesp_read_http_resp(r, &out->u.ok);
+    case MESP_HTTP_RESP_RESULT_ERR:
+        return mesp_read_i32(r, &out->u.err);
+    default:
+        return false;
+    }
+}
+
+mesp_http_resp_result_t mesp_http_resp_result_ok(mesp_http_resp_t value)
+{
+    mesp_http_resp_result_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_HTTP_RESP_RESULT_OK;
+    v.u.ok = value;
+    return v;
+}
+
+mesp_http_resp_result_t mesp_http_resp_result_err(int32_t value)
+{
+    mesp_http_resp_result_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_HTTP_RESP_RESULT_ERR;
+    v.u.err = value;
+    return v;
+}
+
+void mesp_write_access_point(mesp_writer_t *w, const mesp_access_point_t *v)
+{
+    mesp_write_str(w, v->ssid);
+    mesp_write_array(w, v->bssid, 6);
+    mesp_write_u8(w, v->channel);
+    mesp_write_i8(w, v->signal_strength);
+}
+
+bool mesp_read_access_point(mesp_reader_t *r, mesp_access_point_t *out)
+{
+    return mesp_read_str(r, &out->ssid)
+        && mesp_read_array(r, out->bssid, 6)
+        && mesp_read_u8(r, &out->channel)
+        && mesp_read_i8(r, &out->signal_strength);
+}
+
+void mesp_write_wifi_response(mesp_writer_t *w, const mesp_wifi_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_WIFI_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_WIFI_RESPONSE_IS_STARTED:
+        mesp_write_bool(w, v->u.is_started);
+        break;
+    case MESP_WIFI_RESPONSE_IS_CONNECTED:
+        mesp_write_bool(w, v->u.is_connected);
+        break;
+    case MESP_WIFI_RESPONSE_ACCESS_POINTS:
+        mesp_write_access_point_list(w, &v->u.access_points);
+        break;
+    case MESP_WIFI_RESPONSE_CAPABILITIES:
+        mesp_write_u8(w, v->u.capabilities);
+        break;
+    case MESP_WIFI_RESPONSE_STARTED:
+        break;
+    case MESP_WIFI_RESPONSE_STOPPED:
+        break;
+    case MESP_WIFI_RESPONSE_CONNECTED:
+        break;
+    case MESP_WIFI_RESPONSE_DISCONNECTED:
+        break;
+    case MESP_WIFI_RESPONSE_CONFIGURED:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_wifi_response(mesp_reader_t *r, mesp_wifi_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_WIFI_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_WIFI_RESPONSE_IS_STARTED:
+        return mesp_read_bool(r, &out->u.is_started);
+    case MESP_WIFI_RESPONSE_IS_CONNECTED:
+        return mesp_read_bool(r, &out->u.is_connected);
+    case MESP_WIFI_RESPONSE_ACCESS_POINTS:
+        return mesp_read_access_point_list(r, &out->u.access_points);
+    case MESP_WIFI_RESPONSE_CAPABILITIES:
+        return mesp_read_u8(r, &out->u.capabilities);
+    case MESP_WIFI_RESPONSE_STARTED:
+        return true;
+    case MESP_WIFI_RESPONSE_STOPPED:
+        return true;
+    case MESP_WIFI_RESPONSE_CONNECTED:
+        return true;
+    case MESP_WIFI_RESPONSE_DISCONNECTED:
+        return true;
+    case MESP_WIFI_RESPONSE_CONFIGURED:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_wifi_response_t mesp_wifi_response_error(int32_t value)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_is_started(bool value)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_IS_STARTED;
+    v.u.is_started = value;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_is_connected(bool value)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_IS_CONNECTED;
+    v.u.is_connected = value;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_access_points(mesp_list_t value)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_ACCESS_POINTS;
+    v.u.access_points = value;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_capabilities(uint8_t value)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_CAPABILITIES;
+    v.u.capabilities = value;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_started(void)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_STARTED;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_stopped(void)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_STOPPED;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_connected(void)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_CONNECTED;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_disconnected(void)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_DISCONNECTED;
+    return v;
+}
+
+mesp_wifi_response_t mesp_wifi_response_configured(void)
+{
+    mesp_wifi_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WIFI_RESPONSE_CONFIGURED;
+    return v;
+}
+
+void mesp_write_service_instance(mesp_writer_t *w, const mesp_service_instance_t *v)
+{
+    mesp_write_str(w, v->instance);
+    mesp_write_str(w, v->host);
+    mesp_write_array(w, v->addr, 4);
+    mesp_write_u16(w, v->port);
+    mesp_write_str_str_list(w, &v->txt);
+}
+
+bool mesp_read_service_instance(mesp_reader_t *r, mesp_service_instance_t *out)
+{
+    return mesp_read_str(r, &out->instance)
+        && mesp_read_str(r, &out->host)
+        && mesp_read_array(r, out->addr, 4)
+        && mesp_read_u16(r, &out->port)
+        && mesp_read_str_str_list(r, &out->txt);
+}
+
+void mesp_write_mdns_response(mesp_writer_t *w, const mesp_mdns_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_MDNS_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_MDNS_RESPONSE_HOSTNAME:
+        mesp_write_str(w, v->u.hostname);
+        break;
+    case MESP_MDNS_RESPONSE_SERVICES:
+        mesp_write_service_instance_list(w, &v->u.services);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_mdns_response(mesp_reader_t *r, mesp_mdns_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_MDNS_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_MDNS_RESPONSE_HOSTNAME:
+        return mesp_read_str(r, &out->u.hostname);
+    case MESP_MDNS_RESPONSE_SERVICES:
+        return mesp_read_service_instance_list(r, &out->u.services);
+    default:
+        return false;
+    }
+}
+
+mesp_mdns_response_t mesp_mdns_response_error(int32_t value)
+{
+    mesp_mdns_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MDNS_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_mdns_response_t mesp_mdns_response_hostname(mesp_str_t value)
+{
+    mesp_mdns_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MDNS_RESPONSE_HOSTNAME;
+    v.u.hostname = value;
+    return v;
+}
+
+mesp_mdns_response_t mesp_mdns_response_services(mesp_list_t value)
+{
+    mesp_mdns_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MDNS_RESPONSE_SERVICES;
+    v.u.services = value;
+    return v;
+}
+
+void mesp_write_direction(mesp_writer_t *w, const mesp_direction_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_DIRECTION_IN:
+        break;
+    case MESP_DIRECTION_OUT:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_direction(mesp_reader_t *r, mesp_direction_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_DIRECTION_IN:
+        return true;
+    case MESP_DIRECTION_OUT:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_direction_t mesp_direction_in(void)
+{
+    mesp_direction_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_DIRECTION_IN;
+    return v;
+}
+
+mesp_direction_t mesp_direction_out(void)
+{
+    mesp_direction_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_DIRECTION_OUT;
+    return v;
+}
+
+void mesp_write_trace_record(mesp_writer_t *w, const mesp_trace_record_t *v)
+{
+    mesp_write_u32(w, v->at_ms);
+    mesp_write_direction(w, &v->dir);
+    mesp_write_bytes(w, v->bytes);
+}
+
+bool mesp_read_trace_record(mesp_reader_t *r, mesp_trace_record_t *out)
+{
+    return mesp_read_u32(r, &out->at_ms)
+        && mesp_read_direction(r, &out->dir)
+        && mesp_read_bytes(r, &out->bytes);
+}
+
+void mesp_write_trace_response(mesp_writer_t *w, const mesp_trace_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_TRACE_RESPONSE_STARTED:
+        break;
+    case MESP_TRACE_RESPONSE_STOPPED:
+        break;
+    case MESP_TRACE_RESPONSE_RECORDS:
+        mesp_write_trace_record_list(w, &v->u.records);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_trace_response(mesp_reader_t *r, mesp_trace_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_TRACE_RESPONSE_STARTED:
+        return true;
+    case MESP_TRACE_RESPONSE_STOPPED:
+        return true;
+    case MESP_TRACE_RESPONSE_RECORDS:
+        return mesp_read_trace_record_list(r, &out->u.records);
+    default:
+        return false;
+    }
+}
+
+mesp_trace_response_t mesp_trace_response_started(void)
+{
+    mesp_trace_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_RESPONSE_STARTED;
+    return v;
+}
+
+mesp_trace_response_t mesp_trace_response_stopped(void)
+{
+    mesp_trace_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_RESPONSE_STOPPED;
+    return v;
+}
+
+mesp_trace_response_t mesp_trace_response_records(mesp_list_t value)
+{
+    mesp_trace_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TRACE_RESPONSE_RECORDS;
+    v.u.records = value;
+    return v;
+}
+
+void mesp_write_tcp_response(mesp_writer_t *w, const mesp_tcp_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_TCP_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_TCP_RESPONSE_OPENED:
+        mesp_write_u8(w, v->u.opened);
+        break;
+    case MESP_TCP_RESPONSE_WRITTEN:
+        break;
+    case MESP_TCP_RESPONSE_DATA:
+        mesp_write_bytes(w, v->u.data);
+        break;
+    case MESP_TCP_RESPONSE_CLOSED:
+        break;
+    case MESP_TCP_RESPONSE_LISTENING:
+        mesp_write_u8(w, v->u.listening.handle);
+        mesp_write_u16(w, v->u.listening.port);
+        break;
+    case MESP_TCP_RESPONSE_ACCEPTED:
+        mesp_write_u8(w, v->u.accepted.listener);
+        mesp_write_u8(w, v->u.accepted.handle);
+        mesp_write_array(w, v->u.accepted.addr, 4);
+        mesp_write_u16(w, v->u.accepted.port);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_tcp_response(mesp_reader_t *r, mesp_tcp_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_TCP_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_TCP_RESPONSE_OPENED:
+        return mesp_read_u8(r, &out->u.opened);
+    case MESP_TCP_RESPONSE_WRITTEN:
+        return true;
+    case MESP_TCP_RESPONSE_DATA:
+        return mesp_read_bytes(r, &out->u.data);
+    case MESP_TCP_RESPONSE_CLOSED:
+        return true;
+    case MESP_TCP_RESPONSE_LISTENING:
+        return mesp_read_u8(r, &out->u.listening.handle)
+            && mesp_read_u16(r, &out->u.listening.port);
+    case MESP_TCP_RESPONSE_ACCEPTED:
+        return mesp_read_u8(r, &out->u.accepted.listener)
+            && mesp_read_u8(r, &out->u.accepted.handle)
+            && mesp_read_array(r, out->u.accepted.addr, 4)
+            && mesp_read_u16(r, &out->u.accepted.port);
+    default:
+        return false;
+    }
+}
+
+mesp_tcp_response_t mesp_tcp_response_error(int32_t value)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_opened(uint8_t value)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_OPENED;
+    v.u.opened = value;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_written(void)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_WRITTEN;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_data(mesp_bytes_t value)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_DATA;
+    v.u.data = value;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_closed(void)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_CLOSED;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_listening(uint8_t handle, uint16_t port)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_LISTENING;
+    v.u.listening.handle = handle;
+    v.u.listening.port = port;
+    return v;
+}
+
+mesp_tcp_response_t mesp_tcp_response_accepted(uint8_t listener, uint8_t handle, const uint8_t addr[4], uint16_t port)
+{
+    mesp_tcp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_TCP_RESPONSE_ACCEPTED;
+    v.u.accepted.listener = listener;
+    v.u.accepted.handle = handle;
+    memcpy(v.u.accepted.addr, addr, 4);
+    v.u.accepted.port = port;
+    return v;
+}
+
+void mesp_write_datagram(mesp_writer_t *w, const mesp_datagram_t *v)
+{
+    mesp_write_array(w, v->addr, 4);
+    mesp_write_u16(w, v->port);
+    mesp_write_bytes(w, v->data);
+}
+
+bool mesp_read_datagram(mesp_reader_t *r, mesp_datagram_t *out)
+{
+    return mesp_read_array(r, out->addr, 4)
+        && mesp_read_u16(r, &out->port)
+        && mesp_read_bytes(r, &out->data);
+}
+
+void mesp_write_udp_response(mesp_writer_t *w, const mesp_udp_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_UDP_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_UDP_RESPONSE_BOUND:
+        mesp_write_u8(w, v->u.bound.handle);
+        mesp_write_u16(w, v->u.bound.port);
+        break;
+    case MESP_UDP_RESPONSE_SENT:
+        break;
+    case MESP_UDP_RESPONSE_RECEIVED:
+        mesp_write_datagram(w, &v->u.received);
+        break;
+    case MESP_UDP_RESPONSE_EMPTY:
+        break;
+    case MESP_UDP_RESPONSE_CLOSED:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_udp_response(mesp_reader_t *r, mesp_udp_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_UDP_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_UDP_RESPONSE_BOUND:
+        return mesp_read_u8(r, &out->u.bound.handle)
+            && mesp_read_u16(r, &out->u.bound.port);
+    case MESP_UDP_RESPONSE_SENT:
+        return true;
+    case MESP_UDP_RESPONSE_RECEIVED:
+        return mesp_read_datagram(r, &out->u.received);
+    case MESP_UDP_RESPONSE_EMPTY:
+        return true;
+    case MESP_UDP_RESPONSE_CLOSED:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_udp_response_t mesp_udp_response_error(int32_t value)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_udp_response_t mesp_udp_response_bound(uint8_t handle, uint16_t port)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_BOUND;
+    v.u.bound.handle = handle;
+    v.u.bound.port = port;
+    return v;
+}
+
+mesp_udp_response_t mesp_udp_response_sent(void)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_SENT;
+    return v;
+}
+
+mesp_udp_response_t mesp_udp_response_received(mesp_datagram_t value)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_RECEIVED;
+    v.u.received = value;
+    return v;
+}
+
+mesp_udp_response_t mesp_udp_response_empty(void)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_EMPTY;
+    return v;
+}
+
+mesp_udp_response_t mesp_udp_response_closed(void)
+{
+    mesp_udp_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_UDP_RESPONSE_CLOSED;
+    return v;
+}
+
+void mesp_write_ws_response(mesp_writer_t *w, const mesp_ws_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_WS_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_WS_RESPONSE_CONNECTED:
+        mesp_write_u8(w, v->u.connected);
+        break;
+    case MESP_WS_RESPONSE_SENT:
+        break;
+    case MESP_WS_RESPONSE_MESSAGE:
+        mesp_write_ws_message(w, &v->u.message);
+        break;
+    case MESP_WS_RESPONSE_EMPTY:
+        break;
+    case MESP_WS_RESPONSE_CLOSED:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_ws_response(mesp_reader_t *r, mesp_ws_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_WS_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_WS_RESPONSE_CONNECTED:
+        return mesp_read_u8(r, &out->u.connected);
+    case MESP_WS_RESPONSE_SENT:
+        return true;
+    case MESP_WS_RESPONSE_MESSAGE:
+        return mesp_read_ws_message(r, &out->u.message);
+    case MESP_WS_RESPONSE_EMPTY:
+        return true;
+    case MESP_WS_RESPONSE_CLOSED:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_ws_response_t mesp_ws_response_error(int32_t value)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_ws_response_t mesp_ws_response_connected(uint8_t value)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_CONNECTED;
+    v.u.connected = value;
+    return v;
+}
+
+mesp_ws_response_t mesp_ws_response_sent(void)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_SENT;
+    return v;
+}
+
+mesp_ws_response_t mesp_ws_response_message(mesp_ws_message_t value)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_MESSAGE;
+    v.u.message = value;
+    return v;
+}
+
+mesp_ws_response_t mesp_ws_response_empty(void)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_EMPTY;
+    return v;
+}
+
+mesp_ws_response_t mesp_ws_response_closed(void)
+{
+    mesp_ws_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_WS_RESPONSE_CLOSED;
+    return v;
+}
+
+void mesp_write_mqtt_message(mesp_writer_t *w, const mesp_mqtt_message_t *v)
+{
+    mesp_write_str(w, v->topic);
+    mesp_write_bytes(w, v->payload);
+}
+
+bool mesp_read_mqtt_message(mesp_reader_t *r, mesp_mqtt_message_t *out)
+{
+    return mesp_read_str(r, &out->topic)
+        && mesp_read_bytes(r, &out->payload);
+}
+
+void mesp_write_mqtt_response(mesp_writer_t *w, const mesp_mqtt_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_MQTT_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_MQTT_RESPONSE_CONNECTED:
+        mesp_write_u8(w, v->u.connected);
+        break;
+    case MESP_MQTT_RESPONSE_PUBLISHED:
+        break;
+    case MESP_MQTT_RESPONSE_SUBSCRIBED:
+        break;
+    case MESP_MQTT_RESPONSE_UNSUBSCRIBED:
+        break;
+    case MESP_MQTT_RESPONSE_MESSAGE:
+        mesp_write_mqtt_message(w, &v->u.message);
+        break;
+    case MESP_MQTT_RESPONSE_EMPTY:
+        break;
+    case MESP_MQTT_RESPONSE_CLOSED:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_mqtt_response(mesp_reader_t *r, mesp_mqtt_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_MQTT_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_MQTT_RESPONSE_CONNECTED:
+        return mesp_read_u8(r, &out->u.connected);
+    case MESP_MQTT_RESPONSE_PUBLISHED:
+        return true;
+    case MESP_MQTT_RESPONSE_SUBSCRIBED:
+        return true;
+    case MESP_MQTT_RESPONSE_UNSUBSCRIBED:
+        return true;
+    case MESP_MQTT_RESPONSE_MESSAGE:
+        return mesp_read_mqtt_message(r, &out->u.message);
+    case MESP_MQTT_RESPONSE_EMPTY:
+        return true;
+    case MESP_MQTT_RESPONSE_CLOSED:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_error(int32_t value)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_connected(uint8_t value)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_CONNECTED;
+    v.u.connected = value;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_published(void)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_PUBLISHED;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_subscribed(void)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_SUBSCRIBED;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_unsubscribed(void)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_UNSUBSCRIBED;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_message(mesp_mqtt_message_t value)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_MESSAGE;
+    v.u.message = value;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_empty(void)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_EMPTY;
+    return v;
+}
+
+mesp_mqtt_response_t mesp_mqtt_response_closed(void)
+{
+    mesp_mqtt_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_MQTT_RESPONSE_CLOSED;
+    return v;
+}
+
+void mesp_write_sse_event(mesp_writer_t *w, const mesp_sse_event_t *v)
+{
+    mesp_write_str(w, v->event);
+    mesp_write_str(w, v->data);
+    mesp_write_str(w, v->id);
+}
+
+bool mesp_read_sse_event(mesp_reader_t *r, mesp_sse_event_t *out)
+{
+    return mesp_read_str(r, &out->event)
+        && mesp_read_str(r, &out->data)
+        && mesp_read_str(r, &out->id);
+}
+
+void mesp_write_sse_response(mesp_writer_t *w, const mesp_sse_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_SSE_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_SSE_RESPONSE_SUBSCRIBED:
+        mesp_write_u8(w, v->u.subscribed);
+        break;
+    case MESP_SSE_RESPONSE_EVENT:
+        mesp_write_sse_event(w, &v->u.event);
+        break;
+    case MESP_SSE_RESPONSE_EMPTY:
+        break;
+    case MESP_SSE_RESPONSE_CLOSED:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_sse_response(mesp_reader_t *r, mesp_sse_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_SSE_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_SSE_RESPONSE_SUBSCRIBED:
+        return mesp_read_u8(r, &out->u.subscribed);
+    case MESP_SSE_RESPONSE_EVENT:
+        return mesp_read_sse_event(r, &out->u.event);
+    case MESP_SSE_RESPONSE_EMPTY:
+        return true;
+    case MESP_SSE_RESPONSE_CLOSED:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_sse_response_t mesp_sse_response_error(int32_t value)
+{
+    mesp_sse_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_sse_response_t mesp_sse_response_subscribed(uint8_t value)
+{
+    mesp_sse_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_RESPONSE_SUBSCRIBED;
+    v.u.subscribed = value;
+    return v;
+}
+
+mesp_sse_response_t mesp_sse_response_event(mesp_sse_event_t value)
+{
+    mesp_sse_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_RESPONSE_EVENT;
+    v.u.event = value;
+    return v;
+}
+
+mesp_sse_response_t mesp_sse_response_empty(void)
+{
+    mesp_sse_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_RESPONSE_EMPTY;
+    return v;
+}
+
+mesp_sse_response_t mesp_sse_response_closed(void)
+{
+    mesp_sse_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_SSE_RESPONSE_CLOSED;
+    return v;
+}
+
+void mesp_write_line(mesp_writer_t *w, const mesp_line_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_LINE_TEXT:
+        mesp_write_str(w, v->u.text);
+        break;
+    case MESP_LINE_LINK:
+        mesp_write_str(w, v->u.link.target);
+        mesp_write_str(w, v->u.link.label);
+        break;
+    case MESP_LINE_HEADING:
+        mesp_write_u8(w, v->u.heading.level);
+        mesp_write_str(w, v->u.heading.text);
+        break;
+    case MESP_LINE_LIST_ITEM:
+        mesp_write_str(w, v->u.list_item);
+        break;
+    case MESP_LINE_QUOTE:
+        mesp_write_str(w, v->u.quote);
+        break;
+    case MESP_LINE_PREFORMATTED:
+        mesp_write_str(w, v->u.preformatted);
+        break;
+    case MESP_LINE_SEARCH:
+        mesp_write_str(w, v->u.search.target);
+        mesp_write_str(w, v->u.search.label);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_line(mesp_reader_t *r, mesp_line_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_LINE_TEXT:
+        return mesp_read_str(r, &out->u.text);
+    case MESP_LINE_LINK:
+        return mesp_read_str(r, &out->u.link.target)
+            && mesp_read_str(r, &out->u.link.label);
+    case MESP_LINE_HEADING:
+        return mesp_read_u8(r, &out->u.heading.level)
+            && mesp_read_str(r, &out->u.heading.text);
+    case MESP_LINE_LIST_ITEM:
+        return mesp_read_str(r, &out->u.list_item);
+    case MESP_LINE_QUOTE:
+        return mesp_read_str(r, &out->u.quote);
+    case MESP_LINE_PREFORMATTED:
+        return mesp_read_str(r, &out->u.preformatted);
+    case MESP_LINE_SEARCH:
+        return mesp_read_str(r, &out->u.search.target)
+            && mesp_read_str(r, &out->u.search.label);
+    default:
+        return false;
+    }
+}
+
+mesp_line_t mesp_line_text(mesp_str_t value)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_TEXT;
+    v.u.text = value;
+    return v;
+}
+
+mesp_line_t mesp_line_link(mesp_str_t target, mesp_str_t label)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_LINK;
+    v.u.link.target = target;
+    v.u.link.label = label;
+    return v;
+}
+
+mesp_line_t mesp_line_heading(uint8_t level, mesp_str_t text)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_HEADING;
+    v.u.heading.level = level;
+    v.u.heading.text = text;
+    return v;
+}
+
+mesp_line_t mesp_line_list_item(mesp_str_t value)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_LIST_ITEM;
+    v.u.list_item = value;
+    return v;
+}
+
+mesp_line_t mesp_line_quote(mesp_str_t value)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_QUOTE;
+    v.u.quote = value;
+    return v;
+}
+
+mesp_line_t mesp_line_preformatted(mesp_str_t value)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_PREFORMATTED;
+    v.u.preformatted = value;
+    return v;
+}
+
+mesp_line_t mesp_line_search(mesp_str_t target, mesp_str_t label)
+{
+    mesp_line_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_LINE_SEARCH;
+    v.u.search.target = target;
+    v.u.search.label = label;
+    return v;
+}
+
+void mesp_write_page(mesp_writer_t *w, const mesp_page_t *v)
+{
+    mesp_write_line_list(w, &v->lines);
+    mesp_write_bool(w, v->truncated);
+}
+
+bool mesp_read_page(mesp_reader_t *r, mesp_page_t *out)
+{
+    return mesp_read_line_list(r, &out->lines)
+        && mesp_read_bool(r, &out->truncated);
+}
+
+void mesp_write_fetch_response(mesp_writer_t *w, const mesp_fetch_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_FETCH_RESPONSE_ERROR:
+        mesp_write_i32(w, v->u.error);
+        break;
+    case MESP_FETCH_RESPONSE_PAGE:
+        mesp_write_page(w, &v->u.page);
+        break;
+    case MESP_FETCH_RESPONSE_INPUT:
+        mesp_write_str(w, v->u.input.prompt);
+        mesp_write_bool(w, v->u.input.sensitive);
+        break;
+    case MESP_FETCH_RESPONSE_REDIRECT:
+        mesp_write_str(w, v->u.redirect);
+        break;
+    case MESP_FETCH_RESPONSE_FAILED:
+        mesp_write_u8(w, v->u.failed.status);
+        mesp_write_str(w, v->u.failed.meta);
+        break;
+    case MESP_FETCH_RESPONSE_CERT_CHANGED:
+        mesp_write_str(w, v->u.cert_changed);
+        break;
+    case MESP_FETCH_RESPONSE_FORGOTTEN:
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_fetch_response(mesp_reader_t *r, mesp_fetch_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_FETCH_RESPONSE_ERROR:
+        return mesp_read_i32(r, &out->u.error);
+    case MESP_FETCH_RESPONSE_PAGE:
+        return mesp_read_page(r, &out->u.page);
+    case MESP_FETCH_RESPONSE_INPUT:
+        return mesp_read_str(r, &out->u.input.prompt)
+            && mesp_read_bool(r, &out->u.input.sensitive);
+    case MESP_FETCH_RESPONSE_REDIRECT:
+        return mesp_read_str(r, &out->u.redirect);
+    case MESP_FETCH_RESPONSE_FAILED:
+        return mesp_read_u8(r, &out->u.failed.status)
+            && mesp_read_str(r, &out->u.failed.meta);
+    case MESP_FETCH_RESPONSE_CERT_CHANGED:
+        return mesp_read_str(r, &out->u.cert_changed);
+    case MESP_FETCH_RESPONSE_FORGOTTEN:
+        return true;
+    default:
+        return false;
+    }
+}
+
+mesp_fetch_response_t mesp_fetch_response_error(int32_t value)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_ERROR;
+    v.u.error = value;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_page(mesp_page_t value)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_PAGE;
+    v.u.page = value;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_input(mesp_str_t prompt, bool sensitive)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_INPUT;
+    v.u.input.prompt = prompt;
+    v.u.input.sensitive = sensitive;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_redirect(mesp_str_t value)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_REDIRECT;
+    v.u.redirect = value;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_failed(uint8_t status, mesp_str_t meta)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_FAILED;
+    v.u.failed.status = status;
+    v.u.failed.meta = meta;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_cert_changed(mesp_str_t value)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_CERT_CHANGED;
+    v.u.cert_changed = value;
+    return v;
+}
+
+mesp_fetch_response_t mesp_fetch_response_forgotten(void)
+{
+    mesp_fetch_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_FETCH_RESPONSE_FORGOTTEN;
+    return v;
+}
+
+void mesp_write_calc_response(mesp_writer_t *w, const mesp_calc_response_t *v)
+{
+    mesp_write_u8(w, v->tag);
+    switch (v->tag) {
+    case MESP_CALC_RESPONSE_WIFI:
+        mesp_write_wifi_response(w, &v->u.wifi);
+        break;
+    case MESP_CALC_RESPONSE_HTTP:
+        mesp_write_http_resp_result(w, &v->u.http);
+        break;
+    case MESP_CALC_RESPONSE_MDNS:
+        mesp_write_mdns_response(w, &v->u.mdns);
+        break;
+    case MESP_CALC_RESPONSE_BUSY:
+        break;
+    case MESP_CALC_RESPONSE_CANCEL:
+        mesp_write_bool(w, v->u.cancel);
+        break;
+    case MESP_CALC_RESPONSE_CANCELLED:
+        break;
+    case MESP_CALC_RESPONSE_PONG:
+        break;
+    case MESP_CALC_RESPONSE_MAX_FRAME:
+        mesp_write_u16(w, v->u.max_frame);
+        break;
+    case MESP_CALC_RESPONSE_FRAGMENT:
+        mesp_write_varint(w, v->u.fragment.index);
+        mesp_write_varint(w, v->u.fragment.count);
+        mesp_write_bytes(w, v->u.fragment.data);
+        break;
+    case MESP_CALC_RESPONSE_UART:
+        mesp_write_uart_settings(w, &v->u.uart);
+        break;
+    case MESP_CALC_RESPONSE_MODE:
+        mesp_write_mode(w, &v->u.mode);
+        break;
+    case MESP_CALC_RESPONSE_TRACE:
+        mesp_write_trace_response(w, &v->u.trace);
+        break;
+    case MESP_CALC_RESPONSE_TCP:
+        mesp_write_tcp_response(w, &v->u.tcp);
+        break;
+    case MESP_CALC_RESPONSE_UDP:
+        mesp_write_udp_response(w, &v->u.udp);
+        break;
+    case MESP_CALC_RESPONSE_WS:
+        mesp_write_ws_response(w, &v->u.ws);
+        break;
+    case MESP_CALC_RESPONSE_MQTT:
+        mesp_write_mqtt_response(w, &v->u.mqtt);
+        break;
+    case MESP_CALC_RESPONSE_SSE:
+        mesp_write_sse_response(w, &v->u.sse);
+        break;
+    case MESP_CALC_RESPONSE_FETCH:
+        mesp_write_fetch_response(w, &v->u.fetch);
+        break;
+    default:
+        w->error = true;
+        break;
+    }
+}
+
+bool mesp_read_calc_response(mesp_reader_t *r, mesp_calc_response_t *out)
+{
+    if (!mesp_read_u8(r, &out->tag)) {
+        return false;
+    }
+
+    switch (out->tag) {
+    case MESP_CALC_RESPONSE_WIFI:
+        return mesp_read_wifi_response(r, &out->u.wifi);
+    case MESP_CALC_RESPONSE_HTTP:
+        return mesp_read_http_resp_result(r, &out->u.http);
+    case MESP_CALC_RESPONSE_MDNS:
+        return mesp_read_mdns_response(r, &out->u.mdns);
+    case MESP_CALC_RESPONSE_BUSY:
+        return true;
+    case MESP_CALC_RESPONSE_CANCEL:
+        return mesp_read_bool(r, &out->u.cancel);
+    case MESP_CALC_RESPONSE_CANCELLED:
+        return true;
+    case MESP_CALC_RESPONSE_PONG:
+        return true;
+    case MESP_CALC_RESPONSE_MAX_FRAME:
+        return mesp_read_u16(r, &out->u.max_frame);
+    case MESP_CALC_RESPONSE_FRAGMENT:
+        return mesp_read_varint(r, &out->u.fragment.index)
+            && mesp_read_varint(r, &out->u.fragment.count)
+            && mesp_read_bytes(r, &out->u.fragment.data);
+    case MESP_CALC_RESPONSE_UART:
+        return mesp_read_uart_settings(r, &out->u.uart);
+    case MESP_CALC_RESPONSE_MODE:
+        return mesp_read_mode(r, &out->u.mode);
+    case MESP_CALC_RESPONSE_TRACE:
+        return mesp_read_trace_response(r, &out->u.trace);
+    case MESP_CALC_RESPONSE_TCP:
+        return mesp_read_tcp_response(r, &out->u.tcp);
+    case MESP_CALC_RESPONSE_UDP:
+        return mesp_read_udp_response(r, &out->u.udp);
+    case MESP_CALC_RESPONSE_WS:
+        return mesp_read_ws_response(r, &out->u.ws);
+    case MESP_CALC_RESPONSE_MQTT:
+        return mesp_read_mqtt_response(r, &out->u.mqtt);
+    case MESP_CALC_RESPONSE_SSE:
+        return mesp_read_sse_response(r, &out->u.sse);
+    case MESP_CALC_RESPONSE_FETCH:
+        return mesp_read_fetch_response(r, &out->u.fetch);
+    default:
+        return false;
+    }
+}
+
+mesp_calc_response_t mesp_calc_response_wifi(mesp_wifi_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_WIFI;
+    v.u.wifi = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_http(mesp_http_resp_result_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_HTTP;
+    v.u.http = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_mdns(mesp_mdns_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_MDNS;
+    v.u.mdns = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_busy(void)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_BUSY;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_cancel(bool value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_CANCEL;
+    v.u.cancel = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_cancelled(void)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_CANCELLED;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_pong(void)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_PONG;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_max_frame(uint16_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_MAX_FRAME;
+    v.u.max_frame = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_fragment(uint32_t index, uint32_t count, mesp_bytes_t data)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_FRAGMENT;
+    v.u.fragment.index = index;
+    v.u.fragment.count = count;
+    v.u.fragment.data = data;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_uart(mesp_uart_settings_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_UART;
+    v.u.uart = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_mode(mesp_mode_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_MODE;
+    v.u.mode = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_trace(mesp_trace_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_TRACE;
+    v.u.trace = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_tcp(mesp_tcp_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_TCP;
+    v.u.tcp = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_udp(mesp_udp_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_UDP;
+    v.u.udp = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_ws(mesp_ws_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_WS;
+    v.u.ws = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_mqtt(mesp_mqtt_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_MQTT;
+    v.u.mqtt = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_sse(mesp_sse_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_SSE;
+    v.u.sse = value;
+    return v;
+}
+
+mesp_calc_response_t mesp_calc_response_fetch(mesp_fetch_response_t value)
+{
+    mesp_calc_response_t v;
+
+    memset(&v, 0, sizeof(v));
+    v.tag = MESP_CALC_RESPONSE_FETCH;
+    v.u.fetch = value;
+    return v;
+}
+
+void mesp_write_request(mesp_writer_t *w, uint8_t id, const mesp_calc_request_t *req)
+{
+    mesp_write_u8(w, id);
+    mesp_write_calc_request(w, req);
+}
+
+bool mesp_read_response(mesp_reader_t *r, uint8_t *id, mesp_calc_response_t *out)
+{
+    return mesp_read_u8(r, id) && mesp_read_calc_response(r, out);
+}
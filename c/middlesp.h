@@ -0,0 +1,1258 @@
+/* Generated by middlesp-codegen from proto/src, do not edit.
+ *
+ * Builders and parsers for the payloads inside the uart link's frames (see
+ * proto/src/frame.rs). A request payload is the id its response is sent back
+ * with followed by a CalcRequest, and a response payload is that id followed
+ * by a CalcResponse. */
+
+#ifndef MIDDLESP_H
+#define MIDDLESP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Strings are not NUL terminated, when read they point into the buffer */
+typedef struct {
+    const char *ptr;
+    uint32_t len;
+} mesp_str_t;
+
+typedef struct {
+    const uint8_t *ptr;
+    uint32_t len;
+} mesp_bytes_t;
+
+/* A list is written from `count` elements at `items`, and read as `count`
+ * still encoded elements at `raw`, which mesp_list_reader() walks through */
+typedef struct {
+    uint32_t count;
+    const void *items;
+    const uint8_t *raw;
+    size_t raw_len;
+} mesp_list_t;
+
+/* `error` is set once anything did not fit or could not be written */
+typedef struct {
+    uint8_t *buf;
+    size_t cap;
+    size_t len;
+    bool error;
+} mesp_writer_t;
+
+typedef struct {
+    const uint8_t *buf;
+    size_t len;
+    size_t pos;
+} mesp_reader_t;
+
+void mesp_writer_init(mesp_writer_t *w, uint8_t *buf, size_t cap);
+void mesp_reader_init(mesp_reader_t *r, const uint8_t *buf, size_t len);
+/* Reads the elements of a list which was read from a response */
+mesp_reader_t mesp_list_reader(const mesp_list_t *list);
+mesp_str_t mesp_str(const char *s);
+mesp_list_t mesp_list(const void *items, uint32_t count);
+
+void mesp_write_u8(mesp_writer_t *w, uint8_t v);
+void mesp_write_u16(mesp_writer_t *w, uint16_t v);
+void mesp_write_u32(mesp_writer_t *w, uint32_t v);
+void mesp_write_i8(mesp_writer_t *w, int8_t v);
+void mesp_write_i32(mesp_writer_t *w, int32_t v);
+void mesp_write_bool(mesp_writer_t *w, bool v);
+void mesp_write_varint(mesp_writer_t *w, uint32_t v);
+void mesp_write_str(mesp_writer_t *w, mesp_str_t v);
+void mesp_write_bytes(mesp_writer_t *w, mesp_bytes_t v);
+void mesp_write_array(mesp_writer_t *w, const uint8_t *v, uint32_t len);
+
+bool mesp_read_u8(mesp_reader_t *r, uint8_t *out);
+bool mesp_read_u16(mesp_reader_t *r, uint16_t *out);
+bool mesp_read_u32(mesp_reader_t *r, uint32_t *out);
+bool mesp_read_i8(mesp_reader_t *r, int8_t *out);
+bool mesp_read_i32(mesp_reader_t *r, int32_t *out);
+bool mesp_read_bool(mesp_reader_t *r, bool *out);
+bool mesp_read_varint(mesp_reader_t *r, uint32_t *out);
+bool mesp_read_str(mesp_reader_t *r, mesp_str_t *out);
+bool mesp_read_bytes(mesp_reader_t *r, mesp_bytes_t *out);
+bool mesp_read_array(mesp_reader_t *r, uint8_t *out, uint32_t len);
+/* Reads a list's count, then steps over its elements with `skip` */
+bool mesp_read_list(mesp_reader_t *r, mesp_list_t *out, bool (*skip)(mesp_reader_t *));
+
+/* The url is not a `gemini://` or `gopher://` one we can fetch */
+#define MESP_ERR_BAD_URL (-6)
+/* The resource is not text, so there are no lines to send */
+#define MESP_ERR_NOT_TEXT (-7)
+/* There was no certificate trusted for the host to forget */
+#define MESP_ERR_NOT_TRUSTED (-9)
+/* There is no subscription to the given filter */
+#define MESP_ERR_NOT_SUBSCRIBED (-5)
+/* No socket of the right kind is open with the given handle */
+#define MESP_ERR_NO_SOCKET (-1)
+/* As many sockets are open as the module allows */
+#define MESP_ERR_TOO_MANY (-2)
+/* The module was built without TLS */
+#define MESP_ERR_NO_TLS (-3)
+/* Failed without an errno, e.g. the host name could not be resolved */
+#define MESP_ERR_OTHER (-4)
+/* A message arrived which was too long for the module to keep, so it was
+ * dropped
+ */
+#define MESP_ERR_TOO_BIG (-8)
+
+enum mesp_auth_method_tag {
+    MESP_AUTH_METHOD_NONE = 0,
+    MESP_AUTH_METHOD_WEP = 1,
+    MESP_AUTH_METHOD_WPA = 2,
+    MESP_AUTH_METHOD_WPA2_PERSONAL = 3,
+    MESP_AUTH_METHOD_WPAWPA2_PERSONAL = 4,
+    MESP_AUTH_METHOD_WPA2_ENTERPRISE = 5,
+    MESP_AUTH_METHOD_WPA3_PERSONAL = 6,
+    MESP_AUTH_METHOD_WPA2_WPA3_PERSONAL = 7,
+    MESP_AUTH_METHOD_WAPI_PERSONAL = 8,
+};
+
+typedef struct {
+    uint8_t tag;
+} mesp_auth_method_t;
+
+/* The parts of a client configuration the calculator gets to pick */
+typedef struct {
+    mesp_str_t ssid;
+    mesp_str_t password;
+    mesp_auth_method_t auth_method;
+} mesp_wifi_config_t;
+
+enum mesp_wifi_actions_tag {
+    /* [esp_idf_svc::wifi::AsyncWifi::is_started] */
+    MESP_WIFI_ACTIONS_IS_STARTED = 0,
+    /* [esp_idf_svc::wifi::AsyncWifi::is_connected] */
+    MESP_WIFI_ACTIONS_IS_CONNECTED = 1,
+    /* [esp_idf_svc::wifi::AsyncWifi::get_capabilities] */
+    MESP_WIFI_ACTIONS_GET_CAPABILITIES = 2,
+    /* [esp_idf_svc::wifi::AsyncWifi::start] */
+    MESP_WIFI_ACTIONS_START = 3,
+    /* [esp_idf_svc::wifi::AsyncWifi::stop] */
+    MESP_WIFI_ACTIONS_STOP = 4,
+    /* [esp_idf_svc::wifi::AsyncWifi::scan] */
+    MESP_WIFI_ACTIONS_SCAN = 5,
+    /* [esp_idf_svc::wifi::AsyncWifi::connect] */
+    MESP_WIFI_ACTIONS_CONNECT = 6,
+    /* [esp_idf_svc::wifi::AsyncWifi::disconnect] */
+    MESP_WIFI_ACTIONS_DISCONNECT = 7,
+    /* [esp_idf_svc::wifi::AsyncWifi::set_configuration] */
+    MESP_WIFI_ACTIONS_SET_CONFIG = 8,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_wifi_config_t set_config;
+    } u;
+} mesp_wifi_actions_t;
+
+typedef struct {
+    mesp_str_t f0;
+    mesp_str_t f1;
+} mesp_str_str_t;
+
+enum mesp_method_with_args_tag {
+    MESP_METHOD_WITH_ARGS_DELETE = 0,
+    MESP_METHOD_WITH_ARGS_GET = 1,
+    MESP_METHOD_WITH_ARGS_HEAD = 2,
+    MESP_METHOD_WITH_ARGS_POST = 3,
+    MESP_METHOD_WITH_ARGS_PUT = 4,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_list_t head;
+        struct {
+            mesp_list_t f0;
+            mesp_str_t f1;
+        } post;
+        mesp_list_t put;
+    } u;
+} mesp_method_with_args_t;
+
+/* What the module does with the body before sending it back */
+enum mesp_convert_tag {
+    /* Sent as is */
+    MESP_CONVERT_RAW = 0,
+    /* HTML turned into plain text, without scripts or styles and with each
+     * link numbered (e.g. `Home [1]`). Much more of the page is read than
+     * of a raw body, as only the text has to fit.
+     */
+    MESP_CONVERT_TEXT = 1,
+    /* Only the values the selectors pick out of a JSON document, which is
+     * read as it arrives so it can be far bigger than a raw body. Each
+     * selector is a JSON pointer (`/current/temp`) or JSONPath without
+     * wildcards or filters (`$.hourly.temp[0]`).
+     */
+    MESP_CONVERT_JSON = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_list_t json;
+    } u;
+} mesp_convert_t;
+
+typedef struct {
+    mesp_str_t url;
+    bool close;
+    mesp_method_with_args_t extra;
+    mesp_convert_t convert;
+} mesp_http_req_t;
+
+enum mesp_mdns_actions_tag {
+    /* [esp_idf_svc::mdns::EspMdns::set_hostname], an empty name restores
+     * the default `calc-<mac>` hostname
+     */
+    MESP_MDNS_ACTIONS_SET_HOSTNAME = 0,
+    /* [esp_idf_svc::mdns::EspMdns::query_ptr] for a service such as
+     * `_http._tcp`
+     */
+    MESP_MDNS_ACTIONS_BROWSE = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_str_t set_hostname;
+        mesp_str_t browse;
+    } u;
+} mesp_mdns_actions_t;
+
+/* Parity is 0 none, 1 even, 2 odd, and rts/cts are 0xFF when unused */
+typedef struct {
+    uint32_t baud;
+    uint8_t parity;
+    uint8_t stop_bits;
+    uint8_t tx;
+    uint8_t rx;
+    uint8_t rts;
+    uint8_t cts;
+} mesp_uart_settings_t;
+
+/* Which protocol we talk to the calculator with, picked at boot from the
+ * first byte we get (the frame start byte or the `A` of `AT`)
+ */
+enum mesp_mode_tag {
+    MESP_MODE_BINARY = 0,
+    MESP_MODE_TEXT = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+} mesp_mode_t;
+
+enum mesp_trace_actions_tag {
+    /* Throws away anything recorded so far and starts recording */
+    MESP_TRACE_ACTIONS_START = 0,
+    MESP_TRACE_ACTIONS_STOP = 1,
+    /* Hands over everything recorded so far, which is then forgotten.
+     * Recording carries on if it was started.
+     */
+    MESP_TRACE_ACTIONS_TAKE = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+} mesp_trace_actions_t;
+
+enum mesp_tcp_actions_tag {
+    /* Connects to `host:port`, answered with [TcpResponse::Opened] */
+    MESP_TCP_ACTIONS_OPEN = 0,
+    /* Sends all of the bytes, answered with [TcpResponse::Written] */
+    MESP_TCP_ACTIONS_WRITE = 1,
+    /* Reads up to the given number of bytes (at most 4 KiB), waiting a
+     * little while for some to arrive if none have
+     */
+    MESP_TCP_ACTIONS_READ = 2,
+    /* Closes a connection, or stops listening (connections already accepted
+     * stay open)
+     */
+    MESP_TCP_ACTIONS_CLOSE = 3,
+    /* Listens on a local port (`0` for any free one), answered with
+     * [TcpResponse::Listening]. Every client which connects afterwards is
+     * announced with a [TcpResponse::Accepted] sent with this request's id.
+     */
+    MESP_TCP_ACTIONS_LISTEN = 4,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        struct {
+            mesp_str_t host;
+            uint16_t port;
+            bool tls;
+        } open;
+        struct {
+            uint8_t f0;
+            mesp_bytes_t f1;
+        } write;
+        struct {
+            uint8_t f0;
+            uint16_t f1;
+        } read;
+        uint8_t close;
+        uint16_t listen;
+    } u;
+} mesp_tcp_actions_t;
+
+enum mesp_udp_actions_tag {
+    /* Binds a socket to a local port (`0` for any free one), answered with
+     * [UdpResponse::Bound]. Broadcasts are allowed on every socket.
+     */
+    MESP_UDP_ACTIONS_BIND = 0,
+    /* Sends one datagram, `host` may be `255.255.255.255` to broadcast */
+    MESP_UDP_ACTIONS_SEND = 1,
+    /* Takes the oldest datagram which has arrived, waiting a little while
+     * for one if none have
+     */
+    MESP_UDP_ACTIONS_RECV = 2,
+    MESP_UDP_ACTIONS_CLOSE = 3,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        uint16_t bind;
+        struct {
+            uint8_t handle;
+            mesp_str_t host;
+            uint16_t port;
+            mesp_bytes_t data;
+        } send;
+        uint8_t recv;
+        uint8_t close;
+    } u;
+} mesp_udp_actions_t;
+
+enum mesp_ws_message_tag {
+    MESP_WS_MESSAGE_TEXT = 0,
+    MESP_WS_MESSAGE_BINARY = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_str_t text;
+        mesp_bytes_t binary;
+    } u;
+} mesp_ws_message_t;
+
+enum mesp_ws_actions_tag {
+    /* Connects to a `ws://` or `wss://` url, sending the extra headers with
+     * the upgrade request. Answered with [WsResponse::Connected].
+     */
+    MESP_WS_ACTIONS_CONNECT = 0,
+    MESP_WS_ACTIONS_SEND = 1,
+    /* Takes the oldest message which has arrived, waiting a little while for
+     * one if none have
+     */
+    MESP_WS_ACTIONS_RECV = 2,
+    MESP_WS_ACTIONS_CLOSE = 3,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        struct {
+            mesp_str_t url;
+            mesp_list_t headers;
+        } connect;
+        struct {
+            uint8_t f0;
+            mesp_ws_message_t f1;
+        } send;
+        uint8_t recv;
+        uint8_t close;
+    } u;
+} mesp_ws_actions_t;
+
+typedef struct {
+    mesp_str_t url;
+    mesp_str_t client_id;
+    mesp_str_t username;
+    mesp_str_t password;
+} mesp_mqtt_config_t;
+
+enum mesp_qos_tag {
+    MESP_QOS_AT_MOST_ONCE = 0,
+    MESP_QOS_AT_LEAST_ONCE = 1,
+    MESP_QOS_EXACTLY_ONCE = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+} mesp_qos_t;
+
+enum mesp_mqtt_actions_tag {
+    /* Answered with [MqttResponse::Connected] once the broker has accepted
+     * us
+     */
+    MESP_MQTT_ACTIONS_CONNECT = 0,
+    MESP_MQTT_ACTIONS_PUBLISH = 1,
+    /* Subscribes to a topic filter, which may use the `+` and `#` wildcards.
+     * Messages matching it are queued (up to 16, dropping the oldest) until
+     * taken with [MqttActions::Recv].
+     */
+    MESP_MQTT_ACTIONS_SUBSCRIBE = 2,
+    /* Throws away anything still queued for the filter */
+    MESP_MQTT_ACTIONS_UNSUBSCRIBE = 3,
+    /* Takes the oldest message queued for a filter, given exactly as it was
+     * subscribed to, waiting a little while for one if none have arrived
+     */
+    MESP_MQTT_ACTIONS_RECV = 4,
+    MESP_MQTT_ACTIONS_CLOSE = 5,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_mqtt_config_t connect;
+        struct {
+            uint8_t handle;
+            mesp_str_t topic;
+            mesp_bytes_t payload;
+            mesp_qos_t qos;
+            bool retain;
+        } publish;
+        struct {
+            uint8_t handle;
+            mesp_str_t filter;
+            mesp_qos_t qos;
+        } subscribe;
+        struct {
+            uint8_t handle;
+            mesp_str_t filter;
+        } unsubscribe;
+        struct {
+            uint8_t handle;
+            mesp_str_t filter;
+        } recv;
+        uint8_t close;
+    } u;
+} mesp_mqtt_actions_t;
+
+enum mesp_sse_actions_tag {
+    /* Opens the stream with a GET, sending the extra headers. Answered with
+     * [SseResponse::Subscribed] once the server has answered.
+     */
+    MESP_SSE_ACTIONS_SUBSCRIBE = 0,
+    /* Takes the oldest event (up to 32 are queued, dropping the oldest),
+     * waiting a little while for one if none have arrived
+     */
+    MESP_SSE_ACTIONS_RECV = 1,
+    MESP_SSE_ACTIONS_CLOSE = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        struct {
+            mesp_str_t url;
+            mesp_list_t headers;
+        } subscribe;
+        uint8_t recv;
+        uint8_t close;
+    } u;
+} mesp_sse_actions_t;
+
+enum mesp_fetch_actions_tag {
+    /* Fetches a `gemini://` or `gopher://` url. `input` answers a
+     * [FetchResponse::Input] prompt or fills in a [Line::Search], and is
+     * left empty otherwise.
+     */
+    MESP_FETCH_ACTIONS_GET = 0,
+    /* Forgets the certificate trusted for a Gemini host, so whichever it
+     * shows next is trusted instead. Answered with [ERR_NOT_TRUSTED] if
+     * none was
+     */
+    MESP_FETCH_ACTIONS_FORGET = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        struct {
+            mesp_str_t url;
+            mesp_str_t input;
+        } get;
+        mesp_str_t forget;
+    } u;
+} mesp_fetch_actions_t;
+
+enum mesp_calc_request_tag {
+    MESP_CALC_REQUEST_WIFI = 0,
+    MESP_CALC_REQUEST_HTTP = 1,
+    MESP_CALC_REQUEST_MDNS = 2,
+    /* Drops the queued or running request with the given id, which is then
+     * answered with [CalcResponse::Cancelled]
+     */
+    MESP_CALC_REQUEST_CANCEL = 3,
+    /* Answered straight away with [CalcResponse::Pong], even while other
+     * requests are running
+     */
+    MESP_CALC_REQUEST_PING = 4,
+    /* The largest frame the calculator can receive, header and crc
+     * included, `0` for no limit. Responses which do not fit are sent as
+     * [CalcResponse::Fragment]s.
+     */
+    MESP_CALC_REQUEST_SET_MAX_FRAME = 5,
+    /* Switches the uart to new settings, answered at the old settings with
+     * [CalcResponse::Uart]. The switch happens once that answer is acked,
+     * and the settings are kept if the calculator talks to us with them
+     * within a few seconds (otherwise we go back to the old ones).
+     */
+    MESP_CALC_REQUEST_SET_UART = 6,
+    /* Switches between the binary protocol and the text one in [at], once
+     * the answer has gone out
+     */
+    MESP_CALC_REQUEST_SET_MODE = 7,
+    /* Records what goes over the uart, see [trace] */
+    MESP_CALC_REQUEST_TRACE = 8,
+    MESP_CALC_REQUEST_TCP = 9,
+    MESP_CALC_REQUEST_UDP = 10,
+    MESP_CALC_REQUEST_WS = 11,
+    MESP_CALC_REQUEST_MQTT = 12,
+    MESP_CALC_REQUEST_SSE = 13,
+    MESP_CALC_REQUEST_FETCH = 14,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_wifi_actions_t wifi;
+        mesp_http_req_t http;
+        mesp_mdns_actions_t mdns;
+        uint8_t cancel;
+        uint16_t set_max_frame;
+        mesp_uart_settings_t set_uart;
+        mesp_mode_t set_mode;
+        mesp_trace_actions_t trace;
+        mesp_tcp_actions_t tcp;
+        mesp_udp_actions_t udp;
+        mesp_ws_actions_t ws;
+        mesp_mqtt_actions_t mqtt;
+        mesp_sse_actions_t sse;
+        mesp_fetch_actions_t fetch;
+    } u;
+} mesp_calc_request_t;
+
+/* A decimal number, `mantissa * 10^exponent`, which keeps what the document
+ * said exactly (up to 9 significant digits) and suits calculators' own
+ * decimal floats
+ */
+typedef struct {
+    int32_t mantissa;
+    int8_t exponent;
+} mesp_number_t;
+
+/* What a selector picked out of a JSON document */
+enum mesp_json_value_tag {
+    /* Nothing in the document matched */
+    MESP_JSON_VALUE_MISSING = 0,
+    MESP_JSON_VALUE_NULL = 1,
+    MESP_JSON_VALUE_BOOL = 2,
+    MESP_JSON_VALUE_NUMBER = 3,
+    /* Cut off past 1 KiB */
+    MESP_JSON_VALUE_STRING = 4,
+    /* An array of numbers, only the first 256 of which are kept */
+    MESP_JSON_VALUE_NUMBERS = 5,
+    /* An object, or an array holding more than numbers, which has no field
+     * to go in
+     */
+    MESP_JSON_VALUE_OTHER = 6,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        bool bool_;
+        mesp_number_t number;
+        mesp_str_t string;
+        mesp_list_t numbers;
+    } u;
+} mesp_json_value_t;
+
+typedef struct {
+    mesp_bytes_t raw;
+    mesp_list_t links;
+    mesp_list_t values;
+} mesp_http_resp_t;
+
+enum mesp_http_resp_result_tag {
+    MESP_HTTP_RESP_RESULT_OK = 0,
+    MESP_HTTP_RESP_RESULT_ERR = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_http_resp_t ok;
+        int32_t err;
+    } u;
+} mesp_http_resp_result_t;
+
+/* An access point found by a scan */
+typedef struct {
+    mesp_str_t ssid;
+    uint8_t bssid[6];
+    uint8_t channel;
+    int8_t signal_strength;
+} mesp_access_point_t;
+
+enum mesp_wifi_response_tag {
+    MESP_WIFI_RESPONSE_ERROR = 0,
+    MESP_WIFI_RESPONSE_IS_STARTED = 1,
+    MESP_WIFI_RESPONSE_IS_CONNECTED = 2,
+    MESP_WIFI_RESPONSE_ACCESS_POINTS = 3,
+    MESP_WIFI_RESPONSE_CAPABILITIES = 4,
+    MESP_WIFI_RESPONSE_STARTED = 5,
+    MESP_WIFI_RESPONSE_STOPPED = 6,
+    MESP_WIFI_RESPONSE_CONNECTED = 7,
+    MESP_WIFI_RESPONSE_DISCONNECTED = 8,
+    MESP_WIFI_RESPONSE_CONFIGURED = 9,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        bool is_started;
+        bool is_connected;
+        mesp_list_t access_points;
+        uint8_t capabilities;
+    } u;
+} mesp_wifi_response_t;
+
+/* A single service instance found while browsing */
+typedef struct {
+    mesp_str_t instance;
+    mesp_str_t host;
+    uint8_t addr[4];
+    uint16_t port;
+    mesp_list_t txt;
+} mesp_service_instance_t;
+
+enum mesp_mdns_response_tag {
+    MESP_MDNS_RESPONSE_ERROR = 0,
+    MESP_MDNS_RESPONSE_HOSTNAME = 1,
+    MESP_MDNS_RESPONSE_SERVICES = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        mesp_str_t hostname;
+        mesp_list_t services;
+    } u;
+} mesp_mdns_response_t;
+
+enum mesp_direction_tag {
+    /* From the calculator */
+    MESP_DIRECTION_IN = 0,
+    /* To the calculator */
+    MESP_DIRECTION_OUT = 1,
+};
+
+typedef struct {
+    uint8_t tag;
+} mesp_direction_t;
+
+/* Bytes read from or written to the uart in one go, these are raw so may
+ * hold part of a frame or several of them
+ */
+typedef struct {
+    uint32_t at_ms;
+    mesp_direction_t dir;
+    mesp_bytes_t bytes;
+} mesp_trace_record_t;
+
+enum mesp_trace_response_tag {
+    MESP_TRACE_RESPONSE_STARTED = 0,
+    MESP_TRACE_RESPONSE_STOPPED = 1,
+    /* Oldest first, the oldest are dropped once the buffer is full */
+    MESP_TRACE_RESPONSE_RECORDS = 2,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_list_t records;
+    } u;
+} mesp_trace_response_t;
+
+enum mesp_tcp_response_tag {
+    /* An errno from the socket, or one of the `ERR_` codes (which are all
+     * negative)
+     */
+    MESP_TCP_RESPONSE_ERROR = 0,
+    MESP_TCP_RESPONSE_OPENED = 1,
+    MESP_TCP_RESPONSE_WRITTEN = 2,
+    /* Whatever had arrived, empty if nothing did in time */
+    MESP_TCP_RESPONSE_DATA = 3,
+    /* The socket has been closed, either as asked or (in answer to a read)
+     * because the other end closed it. Its handle is no longer valid.
+     */
+    MESP_TCP_RESPONSE_CLOSED = 4,
+    /* The listening socket's handle and the local port it is on */
+    MESP_TCP_RESPONSE_LISTENING = 5,
+    /* Sent unasked whenever a client connects to a listening socket, with
+     * the handle for the new connection and where it came from
+     */
+    MESP_TCP_RESPONSE_ACCEPTED = 6,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        uint8_t opened;
+        mesp_bytes_t data;
+        struct {
+            uint8_t handle;
+            uint16_t port;
+        } listening;
+        struct {
+            uint8_t listener;
+            uint8_t handle;
+            uint8_t addr[4];
+            uint16_t port;
+        } accepted;
+    } u;
+} mesp_tcp_response_t;
+
+/* A datagram which arrived, anything past 1472 bytes is cut off */
+typedef struct {
+    uint8_t addr[4];
+    uint16_t port;
+    mesp_bytes_t data;
+} mesp_datagram_t;
+
+enum mesp_udp_response_tag {
+    /* An errno from the socket, or one of the `ERR_` codes in [crate::tcp] */
+    MESP_UDP_RESPONSE_ERROR = 0,
+    /* The socket's handle and the local port it is bound to */
+    MESP_UDP_RESPONSE_BOUND = 1,
+    MESP_UDP_RESPONSE_SENT = 2,
+    MESP_UDP_RESPONSE_RECEIVED = 3,
+    /* Nothing arrived in time */
+    MESP_UDP_RESPONSE_EMPTY = 4,
+    MESP_UDP_RESPONSE_CLOSED = 5,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        struct {
+            uint8_t handle;
+            uint16_t port;
+        } bound;
+        mesp_datagram_t received;
+    } u;
+} mesp_udp_response_t;
+
+enum mesp_ws_response_tag {
+    /* An errno from the connection, [ERR_TOO_BIG] or one of the `ERR_` codes
+     * in [crate::tcp]
+     */
+    MESP_WS_RESPONSE_ERROR = 0,
+    MESP_WS_RESPONSE_CONNECTED = 1,
+    MESP_WS_RESPONSE_SENT = 2,
+    MESP_WS_RESPONSE_MESSAGE = 3,
+    /* Nothing arrived in time */
+    MESP_WS_RESPONSE_EMPTY = 4,
+    /* The connection is closed, either by us or by the server once every
+     * message it sent has been taken
+     */
+    MESP_WS_RESPONSE_CLOSED = 5,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        uint8_t connected;
+        mesp_ws_message_t message;
+    } u;
+} mesp_ws_response_t;
+
+typedef struct {
+    mesp_str_t topic;
+    mesp_bytes_t payload;
+} mesp_mqtt_message_t;
+
+enum mesp_mqtt_response_tag {
+    /* An errno from the connection, [ERR_NOT_SUBSCRIBED] or one of the
+     * `ERR_` codes in [crate::tcp]
+     */
+    MESP_MQTT_RESPONSE_ERROR = 0,
+    MESP_MQTT_RESPONSE_CONNECTED = 1,
+    MESP_MQTT_RESPONSE_PUBLISHED = 2,
+    MESP_MQTT_RESPONSE_SUBSCRIBED = 3,
+    MESP_MQTT_RESPONSE_UNSUBSCRIBED = 4,
+    MESP_MQTT_RESPONSE_MESSAGE = 5,
+    /* Nothing arrived in time */
+    MESP_MQTT_RESPONSE_EMPTY = 6,
+    /* The connection is closed, either by us or by the broker once every
+     * message queued for the filter has been taken
+     */
+    MESP_MQTT_RESPONSE_CLOSED = 7,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        uint8_t connected;
+        mesp_mqtt_message_t message;
+    } u;
+} mesp_mqtt_response_t;
+
+typedef struct {
+    mesp_str_t event;
+    mesp_str_t data;
+    mesp_str_t id;
+} mesp_sse_event_t;
+
+enum mesp_sse_response_tag {
+    /* An errno from the connection, or one of the `ERR_` codes in
+     * [crate::tcp]
+     */
+    MESP_SSE_RESPONSE_ERROR = 0,
+    MESP_SSE_RESPONSE_SUBSCRIBED = 1,
+    MESP_SSE_RESPONSE_EVENT = 2,
+    /* Nothing arrived in time */
+    MESP_SSE_RESPONSE_EMPTY = 3,
+    /* The stream is closed, either by us or because reconnecting kept
+     * failing, once every queued event has been taken
+     */
+    MESP_SSE_RESPONSE_CLOSED = 4,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        uint8_t subscribed;
+        mesp_sse_event_t event;
+    } u;
+} mesp_sse_response_t;
+
+enum mesp_line_tag {
+    MESP_LINE_TEXT = 0,
+    /* `target` is a full url, which need not be one we can fetch (e.g. an
+     * `https://` one)
+     */
+    MESP_LINE_LINK = 1,
+    /* Level 1 to 3 */
+    MESP_LINE_HEADING = 2,
+    MESP_LINE_LIST_ITEM = 3,
+    MESP_LINE_QUOTE = 4,
+    /* Shown as is, without wrapping */
+    MESP_LINE_PREFORMATTED = 5,
+    /* A Gopher search, fetched with the query as the input */
+    MESP_LINE_SEARCH = 6,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_str_t text;
+        struct {
+            mesp_str_t target;
+            mesp_str_t label;
+        } link;
+        struct {
+            uint8_t level;
+            mesp_str_t text;
+        } heading;
+        mesp_str_t list_item;
+        mesp_str_t quote;
+        mesp_str_t preformatted;
+        struct {
+            mesp_str_t target;
+            mesp_str_t label;
+        } search;
+    } u;
+} mesp_line_t;
+
+typedef struct {
+    mesp_list_t lines;
+    bool truncated;
+} mesp_page_t;
+
+enum mesp_fetch_response_tag {
+    /* An errno from the connection, one of the `ERR_` codes here or in
+     * [crate::tcp]
+     */
+    MESP_FETCH_RESPONSE_ERROR = 0,
+    MESP_FETCH_RESPONSE_PAGE = 1,
+    /* The server wants input, fetch the same url again with it */
+    MESP_FETCH_RESPONSE_INPUT = 2,
+    /* Moved to the given full url, which is not followed for you */
+    MESP_FETCH_RESPONSE_REDIRECT = 3,
+    /* A Gemini failure status (40 to 69) with the server's explanation */
+    MESP_FETCH_RESPONSE_FAILED = 4,
+    /* The host showed a different certificate to the one trusted for it,
+     * which may be an attack (or just a renewed certificate, in which case
+     * [FetchActions::Forget] it)
+     */
+    MESP_FETCH_RESPONSE_CERT_CHANGED = 5,
+    MESP_FETCH_RESPONSE_FORGOTTEN = 6,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        int32_t error;
+        mesp_page_t page;
+        struct {
+            mesp_str_t prompt;
+            bool sensitive;
+        } input;
+        mesp_str_t redirect;
+        struct {
+            uint8_t status;
+            mesp_str_t meta;
+        } failed;
+        mesp_str_t cert_changed;
+    } u;
+} mesp_fetch_response_t;
+
+enum mesp_calc_response_tag {
+    MESP_CALC_RESPONSE_WIFI = 0,
+    /* The body of the response, or the esp error code the request failed with */
+    MESP_CALC_RESPONSE_HTTP = 1,
+    MESP_CALC_RESPONSE_MDNS = 2,
+    /* The queue was full so the request was dropped without being run */
+    MESP_CALC_RESPONSE_BUSY = 3,
+    /* Answer to a [CalcRequest::Cancel], whether there was anything to cancel */
+    MESP_CALC_RESPONSE_CANCEL = 4,
+    /* The request was cancelled before it finished */
+    MESP_CALC_RESPONSE_CANCELLED = 5,
+    MESP_CALC_RESPONSE_PONG = 6,
+    /* The max frame size now in use, `0` for no limit */
+    MESP_CALC_RESPONSE_MAX_FRAME = 7,
+    /* One piece of a response too big for a single frame, the pieces'
+     * `data` joined in order is the serialised [CalcResponse]
+     */
+    MESP_CALC_RESPONSE_FRAGMENT = 8,
+    /* The uart settings in use once any switch is done, unchanged from
+     * before if the requested ones were invalid
+     */
+    MESP_CALC_RESPONSE_UART = 9,
+    /* The mode we are switching to */
+    MESP_CALC_RESPONSE_MODE = 10,
+    MESP_CALC_RESPONSE_TRACE = 11,
+    MESP_CALC_RESPONSE_TCP = 12,
+    MESP_CALC_RESPONSE_UDP = 13,
+    MESP_CALC_RESPONSE_WS = 14,
+    MESP_CALC_RESPONSE_MQTT = 15,
+    MESP_CALC_RESPONSE_SSE = 16,
+    MESP_CALC_RESPONSE_FETCH = 17,
+};
+
+typedef struct {
+    uint8_t tag;
+    union {
+        mesp_wifi_response_t wifi;
+        mesp_http_resp_result_t http;
+        mesp_mdns_response_t mdns;
+        bool cancel;
+        uint16_t max_frame;
+        struct {
+            uint32_t index;
+            uint32_t count;
+            mesp_bytes_t data;
+        } fragment;
+        mesp_uart_settings_t uart;
+        mesp_mode_t mode;
+        mesp_trace_response_t trace;
+        mesp_tcp_response_t tcp;
+        mesp_udp_response_t udp;
+        mesp_ws_response_t ws;
+        mesp_mqtt_response_t mqtt;
+        mesp_sse_response_t sse;
+        mesp_fetch_response_t fetch;
+    } u;
+} mesp_calc_response_t;
+
+void mesp_write_auth_method(mesp_writer_t *w, const mesp_auth_method_t *v);
+bool mesp_read_auth_method(mesp_reader_t *r, mesp_auth_method_t *out);
+mesp_auth_method_t mesp_auth_method_none(void);
+mesp_auth_method_t mesp_auth_method_wep(void);
+mesp_auth_method_t mesp_auth_method_wpa(void);
+mesp_auth_method_t mesp_auth_method_wpa2_personal(void);
+mesp_auth_method_t mesp_auth_method_wpawpa2_personal(void);
+mesp_auth_method_t mesp_auth_method_wpa2_enterprise(void);
+mesp_auth_method_t mesp_auth_method_wpa3_personal(void);
+mesp_auth_method_t mesp_auth_method_wpa2_wpa3_personal(void);
+mesp_auth_method_t mesp_auth_method_wapi_personal(void);
+void mesp_write_wifi_config(mesp_writer_t *w, const mesp_wifi_config_t *v);
+bool mesp_read_wifi_config(mesp_reader_t *r, mesp_wifi_config_t *out);
+void mesp_write_wifi_actions(mesp_writer_t *w, const mesp_wifi_actions_t *v);
+bool mesp_read_wifi_actions(mesp_reader_t *r, mesp_wifi_actions_t *out);
+mesp_wifi_actions_t mesp_wifi_actions_is_started(void);
+mesp_wifi_actions_t mesp_wifi_actions_is_connected(void);
+mesp_wifi_actions_t mesp_wifi_actions_get_capabilities(void);
+mesp_wifi_actions_t mesp_wifi_actions_start(void);
+mesp_wifi_actions_t mesp_wifi_actions_stop(void);
+mesp_wifi_actions_t mesp_wifi_actions_scan(void);
+mesp_wifi_actions_t mesp_wifi_actions_connect(void);
+mesp_wifi_actions_t mesp_wifi_actions_disconnect(void);
+mesp_wifi_actions_t mesp_wifi_actions_set_config(mesp_wifi_config_t value);
+void mesp_write_str_str(mesp_writer_t *w, const mesp_str_str_t *v);
+bool mesp_read_str_str(mesp_reader_t *r, mesp_str_str_t *out);
+void mesp_write_method_with_args(mesp_writer_t *w, const mesp_method_with_args_t *v);
+bool mesp_read_method_with_args(mesp_reader_t *r, mesp_method_with_args_t *out);
+mesp_method_with_args_t mesp_method_with_args_delete(void);
+mesp_method_with_args_t mesp_method_with_args_get(void);
+mesp_method_with_args_t mesp_method_with_args_head(mesp_list_t value);
+mesp_method_with_args_t mesp_method_with_args_post(mesp_list_t f0, mesp_str_t f1);
+mesp_method_with_args_t mesp_method_with_args_put(mesp_list_t value);
+void mesp_write_convert(mesp_writer_t *w, const mesp_convert_t *v);
+bool mesp_read_convert(mesp_reader_t *r, mesp_convert_t *out);
+mesp_convert_t mesp_convert_raw(void);
+mesp_convert_t mesp_convert_text(void);
+mesp_convert_t mesp_convert_json(mesp_list_t value);
+void mesp_write_http_req(mesp_writer_t *w, const mesp_http_req_t *v);
+bool mesp_read_http_req(mesp_reader_t *r, mesp_http_req_t *out);
+void mesp_write_mdns_actions(mesp_writer_t *w, const mesp_mdns_actions_t *v);
+bool mesp_read_mdns_actions(mesp_reader_t *r, mesp_mdns_actions_t *out);
+mesp_mdns_actions_t mesp_mdns_actions_set_hostname(mesp_str_t value);
+mesp_mdns_actions_t mesp_mdns_actions_browse(mesp_str_t value);
+void mesp_write_uart_settings(mesp_writer_t *w, const mesp_uart_settings_t *v);
+bool mesp_read_uart_settings(mesp_reader_t *r, mesp_uart_settings_t *out);
+void mesp_write_mode(mesp_writer_t *w, const mesp_mode_t *v);
+bool mesp_read_mode(mesp_reader_t *r, mesp_mode_t *out);
+mesp_mode_t mesp_mode_binary(void);
+mesp_mode_t mesp_mode_text(void);
+void mesp_write_trace_actions(mesp_writer_t *w, const mesp_trace_actions_t *v);
+bool mesp_read_trace_actions(mesp_reader_t *r, mesp_trace_actions_t *out);
+mesp_trace_actions_t mesp_trace_actions_start(void);
+mesp_trace_actions_t mesp_trace_actions_stop(void);
+mesp_trace_actions_t mesp_trace_actions_take(void);
+void mesp_write_tcp_actions(mesp_writer_t *w, const mesp_tcp_actions_t *v);
+bool mesp_read_tcp_actions(mesp_reader_t *r, mesp_tcp_actions_t *out);
+mesp_tcp_actions_t mesp_tcp_actions_open(mesp_str_t host, uint16_t port, bool tls);
+mesp_tcp_actions_t mesp_tcp_actions_write(uint8_t f0, mesp_bytes_t f1);
+mesp_tcp_actions_t mesp_tcp_actions_read(uint8_t f0, uint16_t f1);
+mesp_tcp_actions_t mesp_tcp_actions_close(uint8_t value);
+mesp_tcp_actions_t mesp_tcp_actions_listen(uint16_t value);
+void mesp_write_udp_actions(mesp_writer_t *w, const mesp_udp_actions_t *v);
+bool mesp_read_udp_actions(mesp_reader_t *r, mesp_udp_actions_t *out);
+mesp_udp_actions_t mesp_udp_actions_bind(uint16_t value);
+mesp_udp_actions_t mesp_udp_actions_send(uint8_t handle, mesp_str_t host, uint16_t port, mesp_bytes_t data);
+mesp_udp_actions_t mesp_udp_actions_recv(uint8_t value);
+mesp_udp_actions_t mesp_udp_actions_close(uint8_t value);
+void mesp_write_ws_message(mesp_writer_t *w, const mesp_ws_message_t *v);
+bool mesp_read_ws_message(mesp_reader_t *r, mesp_ws_message_t *out);
+mesp_ws_message_t mesp_ws_message_text(mesp_str_t value);
+mesp_ws_message_t mesp_ws_message_binary(mesp_bytes_t value);
+void mesp_write_ws_actions(mesp_writer_t *w, const mesp_ws_actions_t *v);
+bool mesp_read_ws_actions(mesp_reader_t *r, mesp_ws_actions_t *out);
+mesp_ws_actions_t mesp_ws_actions_connect(mesp_str_t url, mesp_list_t headers);
+mesp_ws_actions_t mesp_ws_actions_send(uint8_t f0, mesp_ws_message_t f1);
+mesp_ws_actions_t mesp_ws_actions_recv(uint8_t value);
+mesp_ws_actions_t mesp_ws_actions_close(uint8_t value);
+void mesp_write_mqtt_config(mesp_writer_t *w, const mesp_mqtt_config_t *v);
+bool mesp_read_mqtt_config(mesp_reader_t *r, mesp_mqtt_config_t *out);
+void mesp_write_qos(mesp_writer_t *w, const mesp_qos_t *v);
+bool mesp_read_qos(mesp_reader_t *r, mesp_qos_t *out);
+mesp_qos_t mesp_qos_at_most_once(void);
+mesp_qos_t mesp_qos_at_least_once(void);
+mesp_qos_t mesp_qos_exactly_once(void);
+void mesp_write_mqtt_actions(mesp_writer_t *w, const mesp_mqtt_actions_t *v);
+bool mesp_read_mqtt_actions(mesp_reader_t *r, mesp_mqtt_actions_t *out);
+mesp_mqtt_actions_t mesp_mqtt_actions_connect(mesp_mqtt_config_t value);
+mesp_mqtt_actions_t mesp_mqtt_actions_publish(uint8_t handle, mesp_str_t topic, mesp_bytes_t payload, mesp_qos_t qos, bool retain);
+mesp_mqtt_actions_t mesp_mqtt_actions_subscribe(uint8_t handle, mesp_str_t filter, mesp_qos_t qos);
+mesp_mqtt_actions_t mesp_mqtt_actions_unsubscribe(uint8_t handle, mesp_str_t filter);
+mesp_mqtt_actions_t mesp_mqtt_actions_recv(uint8_t handle, mesp_str_t filter);
+mesp_mqtt_actions_t mesp_mqtt_actions_close(uint8_t value);
+void mesp_write_sse_actions(mesp_writer_t *w, const mesp_sse_actions_t *v);
+bool mesp_read_sse_actions(mesp_reader_t *r, mesp_sse_actions_t *out);
+mesp_sse_actions_t mesp_sse_actions_subscribe(mesp_str_t url, mesp_list_t headers);
+mesp_sse_actions_t mesp_sse_actions_recv(uint8_t value);
+mesp_sse_actions_t mesp_sse_actions_close(uint8_t value);
+void mesp_write_fetch_actions(mesp_writer_t *w, const mesp_fetch_actions_t *v);
+bool mesp_read_fetch_actions(mesp_reader_t *r, mesp_fetch_actions_t *out);
+mesp_fetch_actions_t mesp_fetch_actions_get(mesp_str_t url, mesp_str_t input);
+mesp_fetch_actions_t mesp_fetch_actions_forget(mesp_str_t value);
+void mesp_write_calc_request(mesp_writer_t *w, const mesp_calc_request_t *v);
+bool mesp_read_calc_request(mesp_reader_t *r, mesp_calc_request_t *out);
+mesp_calc_request_t mesp_calc_request_wifi(mesp_wifi_actions_t value);
+mesp_calc_request_t mesp_calc_request_http(mesp_http_req_t value);
+mesp_calc_request_t mesp_calc_request_mdns(mesp_mdns_actions_t value);
+mesp_calc_request_t mesp_calc_request_cancel(uint8_t value);
+mesp_calc_request_t mesp_calc_request_ping(void);
+mesp_calc_request_t mesp_calc_request_set_max_frame(uint16_t value);
+mesp_calc_request_t mesp_calc_request_set_uart(mesp_uart_settings_t value);
+mesp_calc_request_t mesp_calc_request_set_mode(mesp_mode_t value);
+mesp_calc_request_t mesp_calc_request_trace(mesp_trace_actions_t value);
+mesp_calc_request_t mesp_calc_request_tcp(mesp_tcp_actions_t value);
+mesp_calc_request_t mesp_calc_request_udp(mesp_udp_actions_t value);
+mesp_calc_request_t mesp_calc_request_ws(mesp_ws_actions_t value);
+mesp_calc_request_t mesp_calc_request_mqtt(mesp_mqtt_actions_t value);
+mesp_calc_request_t mesp_calc_request_sse(mesp_sse_actions_t value);
+mesp_calc_request_t mesp_calc_request_fetch(mesp_fetch_actions_t value);
+void mesp_write_number(mesp_writer_t *w, const mesp_number_t *v);
+bool mesp_read_number(mesp_reader_t *r, mesp_number_t *out);
+void mesp_write_json_value(mesp_writer_t *w, const mesp_json_value_t *v);
+bool mesp_read_json_value(mesp_reader_t *r, mesp_json_value_t *out);
+mesp_json_value_t mesp_json_value_missing(void);
+mesp_json_value_t mesp_json_value_null(void);
+mesp_json_value_t mesp_json_value_bool(bool value);
+mesp_json_value_t mesp_json_value_number(mesp_number_t value);
+mesp_json_value_t mesp_json_value_string(mesp_str_t value);
+mesp_json_value_t mesp_json_value_numbers(mesp_list_t value);
+mesp_json_value_t mesp_json_value_other(void);
+void mesp_write_http_resp(mesp_writer_t *w, const mesp_http_resp_t *v);
+bool mesp_read_http_resp(mesp_reader_t *r, mesp_http_resp_t *out);
+void mesp_write_http_resp_result(mesp_writer_t *w, const mesp_http_resp_result_t *v);
+bool mesp_read_http_resp_result(mesp_reader_t *r, mesp_http_resp_result_t *out);
+mesp_http_resp_result_t mesp_http_resp_result_ok(mesp_http_resp_t value);
+mesp_http_resp_result_t mesp_http_resp_result_err(int32_t value);
+void mesp_write_access_point(mesp_writer_t *w, const mesp_access_point_t *v);
+bool mesp_read_access_point(mesp_reader_t *r, mesp_access_point_t *out);
+void mesp_write_wifi_response(mesp_writer_t *w, const mesp_wifi_response_t *v);
+bool mesp_read_wifi_response(mesp_reader_t *r, mesp_wifi_response_t *out);
+mesp_wifi_response_t mesp_wifi_response_error(int32_t value);
+mesp_wifi_response_t mesp_wifi_response_is_started(bool value);
+mesp_wifi_response_t mesp_wifi_response_is_connected(bool value);
+mesp_wifi_response_t mesp_wifi_response_access_points(mesp_list_t value);
+mesp_wifi_response_t mesp_wifi_response_capabilities(uint8_t value);
+mesp_wifi_response_t mesp_wifi_response_started(void);
+mesp_wifi_response_t mesp_wifi_response_stopped(void);
+mesp_wifi_response_t mesp_wifi_response_connected(void);
+mesp_wifi_response_t mesp_wifi_response_disconnected(void);
+mesp_wifi_response_t mesp_wifi_response_configured(void);
+void mesp_write_service_instance(mesp_writer_t *w, const mesp_service_instance_t *v);
+bool mesp_read_service_instance(mesp_reader_t *r, mesp_service_instance_t *out);
+void mesp_write_mdns_response(mesp_writer_t *w, const mesp_mdns_response_t *v);
+bool mesp_read_mdns_response(mesp_reader_t *r, mesp_mdns_response_t *out);
+mesp_mdns_response_t mesp_mdns_response_error(int32_t value);
+mesp_mdns_response_t mesp_mdns_response_hostname(mesp_str_t value);
+mesp_mdns_response_t mesp_mdns_response_services(mesp_list_t value);
+void mesp_write_direction(mesp_writer_t *w, const mesp_direction_t *v);
+bool mesp_read_direction(mesp_reader_t *r, mesp_direction_t *out);
+mesp_direction_t mesp_direction_in(void);
+mesp_direction_t mesp_direction_out(void);
+void mesp_write_trace_record(mesp_writer_t *w, const mesp_trace_record_t *v);
+bool mesp_read_trace_record(mesp_reader_t *r, mesp_trace_record_t *out);
+void mesp_write_trace_response(mesp_writer_t *w, const mesp_trace_response_t *v);
+bool mesp_read_trace_response(mesp_reader_t *r, mesp_trace_response_t *out);
+mesp_trace_response_t mesp_trace_response_started(void);
+mesp_trace_response_t mesp_trace_response_stopped(void);
+mesp_trace_response_t mesp_trace_response_records(mesp_list_t value);
+void mesp_write_tcp_response(mesp_writer_t *w, const mesp_tcp_response_t *v);
+bool mesp_read_tcp_response(mesp_reader_t *r, mesp_tcp_response_t *out);
+mesp_tcp_response_t mesp_tcp_response_error(int32_t value);
+mesp_tcp_response_t mesp_tcp_response_opened(uint8_t value);
+mesp_tcp_response_t mesp_tcp_response_written(void);
+mesp_tcp_response_t mesp_tcp_response_data(mesp_bytes_t value);
+mesp_tcp_response_t mesp_tcp_response_closed(void);
+mesp_tcp_response_t mesp_tcp_response_listening(uint8_t handle, uint16_t port);
+mesp_tcp_response_t mesp_tcp_response_accepted(uint8_t listener, uint8_t handle, const uint8_t addr[4], uint16_t port);
+void mesp_write_datagram(mesp_writer_t *w, const mesp_datagram_t *v);
+bool mesp_read_datagram(mesp_reader_t *r, mesp_datagram_t *out);
+void mesp_write_udp_response(mesp_writer_t *w, const mesp_udp_response_t *v);
+bool mesp_read_udp_response(mesp_reader_t *r, mesp_udp_response_t *out);
+mesp_udp_response_t mesp_udp_response_error(int32_t value);
+mesp_udp_response_t mesp_udp_response_bound(uint8_t handle, uint16_t port);
+mesp_udp_response_t mesp_udp_response_sent(void);
+mesp_udp_response_t mesp_udp_response_received(mesp_datagram_t value);
+mesp_udp_response_t mesp_udp_response_empty(void);
+mesp_udp_response_t mesp_udp_response_closed(void);
+void mesp_write_ws_response(mesp_writer_t *w, const mesp_ws_response_t *v);
+bool mesp_read_ws_response(mesp_reader_t *r, mesp_ws_response_t *out);
+mesp_ws_response_t mesp_ws_response_error(int32_t value);
+mesp_ws_response_t mesp_ws_response_connected(uint8_t value);
+mesp_ws_response_t mesp_ws_response_sent(void);
+mesp_ws_response_t mesp_ws_response_message(mesp_ws_message_t value);
+mesp_ws_response_t mesp_ws_response_empty(void);
+mesp_ws_response_t mesp_ws_response_closed(void);
+void mesp_write_mqtt_message(mesp_writer_t *w, const mesp_mqtt_message_t *v);
+bool mesp_read_mqtt_message(mesp_reader_t *r, mesp_mqtt_message_t *out);
+void mesp_write_mqtt_response(mesp_writer_t *w, const mesp_mqtt_response_t *v);
+bool mesp_read_mqtt_response(mesp_reader_t *r, mesp_mqtt_response_t *out);
+mesp_mqtt_response_t mesp_mqtt_response_error(int32_t value);
+mesp_mqtt_response_t mesp_mqtt_response_connected(uint8_t value);
+mesp_mqtt_response_t mesp_mqtt_response_published(void);
+mesp_mqtt_response_t mesp_mqtt_response_subscribed(void);
+mesp_mqtt_response_t mesp_mqtt_response_unsubscribed(void);
+mesp_mqtt_response_t mesp_mqtt_response_message(mesp_mqtt_message_t value);
+mesp_mqtt_response_t mesp_mqtt_response_empty(void);
+mesp_mqtt_response_t mesp_mqtt_response_closed(void);
+void mesp_write_sse_event(mesp_writer_t *w, const mesp_sse_event_t *v);
+bool mesp_read_sse_event(mesp_reader_t *r, mesp_sse_event_t *out);
+void mesp_write_sse_response(mesp_writer_t *w, const mesp_sse_response_t *v);
+bool mesp_read_sse_response(mesp_reader_t *r, mesp_sse_response_t *out);
+mesp_sse_response_t mesp_sse_response_error(int32_t value);
+mesp_sse_response_t mesp_sse_response_subscribed(uint8_t value);
+mesp_sse_response_t mesp_sse_response_event(mesp_sse_event_t value);
+mesp_sse_response_t mesp_sse_response_empty(void);
+mesp_sse_response_t mesp_sse_response_closed(void);
+void mesp_write_line(mesp_writer_t *w, const mesp_line_t *v);
+bool mesp_read_line(mesp_reader_t *r, mesp_line_t *out);
+mesp_line_t mesp_line_text(mesp_str_t value);
+mesp_line_t mesp_line_link(mesp_str_t target, mesp_str_t label);
+mesp_line_t mesp_line_heading(uint8_t level, mesp_str_t text);
+mesp_line_t mesp_line_list_item(mesp_str_t value);
+mesp_line_t mesp_line_quote(mesp_str_t value);
+mesp_line_t mesp_line_preformatted(mesp_str_t value);
+mesp_line_t mesp_line_search(mesp_str_t target, mesp_str_t label);
+void mesp_write_page(mesp_writer_t *w, const mesp_page_t *v);
+bool mesp_read_page(mesp_reader_t *r, mesp_page_t *out);
+void mesp_write_fetch_response(mesp_writer_t *w, const mesp_fetch_response_t *v);
+bool mesp_read_fetch_response(mesp_reader_t *r, mesp_fetch_response_t *out);
+mesp_fetch_response_t mesp_fetch_response_error(int32_t value);
+mesp_fetch_response_t mesp_fetch_response_page(mesp_page_t value);
+mesp_fetch_response_t mesp_fetch_response_input(mesp_str_t prompt, bool sensitive);
+mesp_fetch_response_t mesp_fetch_response_redirect(mesp_str_t value);
+mesp_fetch_response_t mesp_fetch_response_failed(uint8_t status, mesp_str_t meta);
+mesp_fetch_response_t mesp_fetch_response_cert_changed(mesp_str_t value);
+mesp_fetch_response_t mesp_fetch_response_forgotten(void);
+void mesp_write_calc_response(mesp_writer_t *w, const mesp_calc_response_t *v);
+bool mesp_read_calc_response(mesp_reader_t *r, mesp_calc_response_t *out);
+mesp_calc_response_t mesp_calc_response_wifi(mesp_wifi_response_t value);
+mesp_calc_response_t mesp_calc_response_http(mesp_http_resp_result_t value);
+mesp_calc_response_t mesp_calc_response_mdns(mesp_mdns_response_t value);
+mesp_calc_response_t mesp_calc_response_busy(void);
+mesp_calc_response_t mesp_calc_response_cancel(bool value);
+mesp_calc_response_t mesp_calc_response_cancelled(void);
+mesp_calc_response_t mesp_calc_response_pong(void);
+mesp_calc_response_t mesp_calc_response_max_frame(uint16_t value);
+mesp_calc_response_t mesp_calc_response_fragment(uint32_t index, uint32_t count, mesp_bytes_t data);
+mesp_calc_response_t mesp_calc_response_uart(mesp_uart_settings_t value);
+mesp_calc_response_t mesp_calc_response_mode(mesp_mode_t value);
+mesp_calc_response_t mesp_calc_response_trace(mesp_trace_response_t value);
+mesp_calc_response_t mesp_calc_response_tcp(mesp_tcp_response_t value);
+mesp_calc_response_t mesp_calc_response_udp(mesp_udp_response_t value);
+mesp_calc_response_t mesp_calc_response_ws(mesp_ws_response_t value);
+mesp_calc_response_t mesp_calc_response_mqtt(mesp_mqtt_response_t value);
+mesp_calc_response_t mesp_calc_response_sse(mesp_sse_response_t value);
+mesp_calc_response_t mesp_calc_response_fetch(mesp_fetch_response_t value);
+
+/* A request payload: the id its response comes back with, then the request */
+void mesp_write_request(mesp_writer_t *w, uint8_t id, const mesp_calc_request_t *req);
+/* A response payload. NOTE: strings, bytes and lists point into `r`'s buffer */
+bool mesp_read_response(mesp_reader_t *r, uint8_t *id, mesp_calc_response_t *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,489 @@
+//! Writes the schema out as plain C99 that the calculator toolchains
+//! (the CE toolchain's clang, and gcc for Casio) are happy with: no 64 bit
+//! integers, no allocation and nothing past `<string.h>`.
+
+use std::fmt::Write;
+
+use crate::{ty_name, Field, Schema, Shape, Ty, Variant, WireItem};
+
+const HEADER_PRELUDE: &str = include_str!("prelude.h");
+const SOURCE_PRELUDE: &str = include_str!("prelude.c");
+
+const BANNER: &str = "/* Generated by middlesp-codegen from proto/src, do not edit.
+ *
+ * Builders and parsers for the payloads inside the uart link's frames (see
+ * proto/src/frame.rs). A request payload is the id its response is sent back
+ * with followed by a CalcRequest, and a response payload is that id followed
+ * by a CalcResponse. */
+";
+
+/// Names that can not be used as C identifiers as they are, `bool` being a
+/// macro from `<stdbool.h>`
+const KEYWORDS: [&str; 17] = [
+    "auto", "bool", "break", "case", "char", "const", "default", "do", "double", "else", "float",
+    "int", "long", "short", "signed", "union", "unsigned",
+];
+
+/// The header and source
+pub fn emit(schema: &Schema) -> (String, String) {
+    let mut h = String::new();
+    let mut c = String::new();
+
+    h.push_str(BANNER);
+    h.push_str("\n#ifndef MIDDLESP_H\n#define MIDDLESP_H\n\n");
+    h.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n");
+    h.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+    h.push_str(HEADER_PRELUDE);
+
+    if !schema.constants.is_empty() {
+        h.push('\n');
+    }
+    for constant in &schema.constants {
+        comment(&mut h, "", &constant.docs);
+        writeln!(h, "#define MESP_{} ({})", constant.name, constant.value).unwrap();
+    }
+
+    c.push_str(BANNER);
+    c.push_str("\n#include <string.h>\n\n#include \"middlesp.h\"\n\n");
+    c.push_str(SOURCE_PRELUDE);
+
+    for item in &schema.items {
+        h.push('\n');
+        declare_type(&mut h, item);
+    }
+
+    h.push('\n');
+    for item in &schema.items {
+        declare_functions(&mut h, item);
+    }
+
+    h.push_str(
+        "
+/* A request payload: the id its response comes back with, then the request */
+void mesp_write_request(mesp_writer_t *w, uint8_t id, const mesp_calc_request_t *req);
+/* A response payload. NOTE: strings, bytes and lists point into `r`'s buffer */
+bool mesp_read_response(mesp_reader_t *r, uint8_t *id, mesp_calc_response_t *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
+",
+    );
+
+    let mut lists = Vec::new();
+    for item in &schema.items {
+        for field in fields(item) {
+            collect_lists(&field.ty, &mut lists);
+        }
+    }
+    for elem in &lists {
+        c.push('\n');
+        define_list(&mut c, elem);
+    }
+
+    for item in &schema.items {
+        c.push('\n');
+        define_functions(&mut c, item);
+    }
+
+    c.push_str(
+        "
+void mesp_write_request(mesp_writer_t *w, uint8_t id, const mesp_calc_request_t *req)
+{
+    mesp_write_u8(w, id);
+    mesp_write_calc_request(w, req);
+}
+
+bool mesp_read_response(mesp_reader_t *r, uint8_t *id, mesp_calc_response_t *out)
+{
+    return mesp_read_u8(r, id) && mesp_read_calc_response(r, out);
+}
+",
+    );
+
+    (h, c)
+}
+
+fn fields(item: &WireItem) -> Vec<&Field> {
+    match &item.shape {
+        Shape::Struct(fields) => fields.iter().collect(),
+        Shape::Enum(variants) => variants.iter().flat_map(|v| &v.fields).collect(),
+    }
+}
+
+/// Every list element type, inner lists first
+fn collect_lists(ty: &Ty, lists: &mut Vec<Ty>) {
+    if let Ty::List(inner) = ty {
+        collect_lists(inner, lists);
+        if !lists.contains(inner) {
+            lists.push((**inner).clone());
+        }
+    }
+}
+
+/// `HttpResp` to `http_resp`, keeping runs of capitals such as the `WPA2` in
+/// `WPA2Personal` together
+fn snake(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut res = String::with_capacity(name.len() + 4);
+    for (i, &ch) in chars.iter().enumerate() {
+        let word_start = i != 0
+            && ch.is_ascii_uppercase()
+            && (!chars[i - 1].is_ascii_uppercase()
+                || chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase()));
+        if word_start {
+            res.push('_');
+        }
+        res.push(ch.to_ascii_lowercase());
+    }
+
+    if KEYWORDS.contains(&res.as_str()) {
+        res.push('_');
+    }
+
+    res
+}
+
+/// The part of function names standing for `ty`, e.g. `u8` or `str_list`
+fn fn_name(ty: &Ty) -> String {
+    match ty {
+        Ty::List(inner) => format!("{}_list", fn_name(inner)),
+        ty => snake(&ty_name(ty)).trim_end_matches('_').to_string(),
+    }
+}
+
+fn c_type(ty: &Ty) -> String {
+    match ty {
+        Ty::U8 | Ty::Array(_) => "uint8_t".to_string(),
+        Ty::U16 => "uint16_t".to_string(),
+        Ty::U32 | Ty::Varint => "uint32_t".to_string(),
+        Ty::I8 => "int8_t".to_string(),
+        Ty::I32 => "int32_t".to_string(),
+        Ty::Bool => "bool".to_string(),
+        Ty::Str => "mesp_str_t".to_string(),
+        Ty::Bytes => "mesp_bytes_t".to_string(),
+        Ty::List(_) => "mesp_list_t".to_string(),
+        Ty::Named(name) => format!("mesp_{}_t", snake(name)),
+    }
+}
+
+fn decl(ty: &Ty, name: &str) -> String {
+    match ty {
+        Ty::Array(n) => format!("uint8_t {name}[{n}]"),
+        ty => format!("{} {name}", c_type(ty)),
+    }
+}
+
+fn field_name(field: &Field, i: usize) -> String {
+    match &field.name {
+        Some(name) => snake(name),
+        None => format!("f{i}"),
+    }
+}
+
+fn comment(out: &mut String, indent: &str, docs: &[String]) {
+    let docs: Vec<String> = docs.iter().map(|d| d.replace("*/", "* /")).collect();
+    match docs.as_slice() {
+        [] => {}
+        [line] => writeln!(out, "{indent}/* {line} */").unwrap(),
+        [first, rest @ ..] => {
+            writeln!(out, "{indent}/* {first}").unwrap();
+            for line in rest {
+                match line.is_empty() {
+                    true => writeln!(out, "{indent} *").unwrap(),
+                    false => writeln!(out, "{indent} * {line}").unwrap(),
+                }
+            }
+            writeln!(out, "{indent} */").unwrap();
+        }
+    }
+}
+
+/// Where a variant's field lives inside `base`, single field variants hold
+/// the field directly
+fn variant_field(base: &str, variant: &Variant, i: usize) -> String {
+    let name = snake(&variant.name);
+    match variant.fields.as_slice() {
+        [field] if field.name.is_none() => format!("{base}u.{name}"),
+        fields => format!("{base}u.{name}.{}", field_name(&fields[i], i)),
+    }
+}
+
+fn tag(item: &WireItem, variant: &Variant) -> String {
+    format!(
+        "MESP_{}_{}",
+        snake(&item.name).to_ascii_uppercase(),
+        snake(&variant.name)
+            .trim_end_matches('_')
+            .to_ascii_uppercase()
+    )
+}
+
+fn declare_type(h: &mut String, item: &WireItem) {
+    let name = snake(&item.name);
+    comment(h, "", &item.docs);
+
+    match &item.shape {
+        Shape::Struct(fields) => {
+            h.push_str("typedef struct {\n");
+            for (i, field) in fields.iter().enumerate() {
+                writeln!(h, "    {};", decl(&field.ty, &field_name(field, i))).unwrap();
+            }
+            writeln!(h, "}} mesp_{name}_t;").unwrap();
+        }
+        Shape::Enum(variants) => {
+            writeln!(h, "enum mesp_{name}_tag {{").unwrap();
+            for variant in variants {
+                comment(h, "    ", &variant.docs);
+                writeln!(h, "    {} = {},", tag(item, variant), variant.id).unwrap();
+            }
+            h.push_str("};\n\n");
+
+            h.push_str("typedef struct {\n    uint8_t tag;\n");
+            if variants.iter().any(|v| !v.fields.is_empty()) {
+                h.push_str("    union {\n");
+                for variant in variants {
+                    let member = snake(&variant.name);
+                    match variant.fields.as_slice() {
+                        [] => {}
+                        [field] if field.name.is_none() => {
+                            writeln!(h, "        {};", decl(&field.ty, &member)).unwrap()
+                        }
+                        fields => {
+                            h.push_str("        struct {\n");
+                            for (i, field) in fields.iter().enumerate() {
+                                writeln!(
+                                    h,
+                                    "            {};",
+                                    decl(&field.ty, &field_name(field, i))
+                                )
+                                .unwrap();
+                            }
+                            writeln!(h, "        }} {member};").unwrap();
+                        }
+                    }
+                }
+                h.push_str("    } u;\n");
+            }
+            writeln!(h, "}} mesp_{name}_t;").unwrap();
+        }
+    }
+}
+
+fn builder_params(variant: &Variant) -> String {
+    match variant.fields.as_slice() {
+        [] => "void".to_string(),
+        [field] if field.name.is_none() => param(&field.ty, "value"),
+        fields => fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| param(&field.ty, &field_name(field, i)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn param(ty: &Ty, name: &str) -> String {
+    match ty {
+        Ty::Array(n) => format!("const uint8_t {name}[{n}]"),
+        ty => decl(ty, name),
+    }
+}
+
+fn declare_functions(h: &mut String, item: &WireItem) {
+    let name = snake(&item.name);
+
+    writeln!(
+        h,
+        "void mesp_write_{name}(mesp_writer_t *w, const mesp_{name}_t *v);"
+    )
+    .unwrap();
+    writeln!(
+        h,
+        "bool mesp_read_{name}(mesp_reader_t *r, mesp_{name}_t *out);"
+    )
+    .unwrap();
+
+    if let Shape::Enum(variants) = &item.shape {
+        for variant in variants {
+            writeln!(
+                h,
+                "mesp_{name}_t mesp_{name}_{}({});",
+                snake(&variant.name).trim_end_matches('_'),
+                builder_params(variant)
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn write_stmt(ty: &Ty, value: &str) -> String {
+    match ty {
+        Ty::Array(n) => format!("mesp_write_array(w, {value}, {n});"),
+        Ty::List(_) | Ty::Named(_) => format!("mesp_write_{}(w, &{value});", fn_name(ty)),
+        ty => format!("mesp_write_{}(w, {value});", fn_name(ty)),
+    }
+}
+
+fn read_expr(ty: &Ty, place: &str) -> String {
+    match ty {
+        Ty::Array(n) => format!("mesp_read_array(r, {place}, {n})"),
+        ty => format!("mesp_read_{}(r, &{place})", fn_name(ty)),
+    }
+}
+
+/// `return a && b;` over the reads, or `return true;` if there are none
+fn read_all(out: &mut String, indent: &str, reads: &[String]) {
+    match reads {
+        [] => writeln!(out, "{indent}return true;").unwrap(),
+        [first, rest @ ..] => {
+            write!(out, "{indent}return {first}").unwrap();
+            for read in rest {
+                write!(out, "\n{indent}    && {read}").unwrap();
+            }
+            out.push_str(";\n");
+        }
+    }
+}
+
+fn define_list(c: &mut String, elem: &Ty) {
+    assert!(
+        !matches!(elem, Ty::Array(_)),
+        "Lists of arrays are not supported"
+    );
+
+    let name = fn_name(elem);
+    let ty = c_type(elem);
+
+    writeln!(c, "static bool mesp_skip_{name}(mesp_reader_t *r)\n{{").unwrap();
+    writeln!(c, "    {ty} v;\n    return {};\n}}\n", read_expr(elem, "v")).unwrap();
+
+    writeln!(
+        c,
+        "static void mesp_write_{name}_list(mesp_writer_t *w, const mesp_list_t *v)\n{{"
+    )
+    .unwrap();
+    writeln!(c, "    const {ty} *items = (const {ty} *)v->items;").unwrap();
+    c.push_str("    uint32_t i;\n\n    mesp_write_varint(w, v->count);\n");
+    c.push_str("    for (i = 0; i < v->count; i++) {\n");
+    writeln!(c, "        {}", write_stmt(elem, "items[i]")).unwrap();
+    c.push_str("    }\n}\n\n");
+
+    writeln!(
+        c,
+        "static bool mesp_read_{name}_list(mesp_reader_t *r, mesp_list_t *out)\n{{"
+    )
+    .unwrap();
+    writeln!(
+        c,
+        "    return mesp_read_list(r, out, mesp_skip_{name});\n}}"
+    )
+    .unwrap();
+}
+
+fn define_functions(c: &mut String, item: &WireItem) {
+    let name = snake(&item.name);
+
+    match &item.shape {
+        Shape::Struct(fields) => {
+            writeln!(
+                c,
+                "void mesp_write_{name}(mesp_writer_t *w, const mesp_{name}_t *v)\n{{"
+            )
+            .unwrap();
+            for (i, field) in fields.iter().enumerate() {
+                let value = format!("v->{}", field_name(field, i));
+                writeln!(c, "    {}", write_stmt(&field.ty, &value)).unwrap();
+            }
+            c.push_str("}\n\n");
+
+            writeln!(
+                c,
+                "bool mesp_read_{name}(mesp_reader_t *r, mesp_{name}_t *out)\n{{"
+            )
+            .unwrap();
+            let reads: Vec<String> = fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| read_expr(&field.ty, &format!("out->{}", field_name(field, i))))
+                .collect();
+            read_all(c, "    ", &reads);
+            c.push_str("}\n");
+        }
+        Shape::Enum(variants) => {
+            writeln!(
+                c,
+                "void mesp_write_{name}(mesp_writer_t *w, const mesp_{name}_t *v)\n{{"
+            )
+            .unwrap();
+            c.push_str("    mesp_write_u8(w, v->tag);\n    switch (v->tag) {\n");
+            for variant in variants {
+                writeln!(c, "    case {}:", tag(item, variant)).unwrap();
+                for (i, field) in variant.fields.iter().enumerate() {
+                    let value = variant_field("v->", variant, i);
+                    writeln!(c, "        {}", write_stmt(&field.ty, &value)).unwrap();
+                }
+                c.push_str("        break;\n");
+            }
+            c.push_str("    default:\n        w->error = true;\n        break;\n    }\n}\n\n");
+
+            writeln!(
+                c,
+                "bool mesp_read_{name}(mesp_reader_t *r, mesp_{name}_t *out)\n{{"
+            )
+            .unwrap();
+            c.push_str("    if (!mesp_read_u8(r, &out->tag)) {\n        return false;\n    }\n\n");
+            c.push_str("    switch (out->tag) {\n");
+            for variant in variants {
+                writeln!(c, "    case {}:", tag(item, variant)).unwrap();
+                let reads: Vec<String> = variant
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| read_expr(&field.ty, &variant_field("out->", variant, i)))
+                    .collect();
+                read_all(c, "        ", &reads);
+            }
+            c.push_str("    default:\n        return false;\n    }\n}\n");
+
+            for variant in variants {
+                c.push('\n');
+                define_builder(c, item, variant);
+            }
+        }
+    }
+}
+
+fn define_builder(c: &mut String, item: &WireItem, variant: &Variant) {
+    let name = snake(&item.name);
+
+    writeln!(
+        c,
+        "mesp_{name}_t mesp_{name}_{}({})\n{{",
+        snake(&variant.name).trim_end_matches('_'),
+        builder_params(variant)
+    )
+    .unwrap();
+    writeln!(c, "    mesp_{name}_t v;\n").unwrap();
+    c.push_str("    memset(&v, 0, sizeof(v));\n");
+    writeln!(c, "    v.tag = {};", tag(item, variant)).unwrap();
+
+    let single = matches!(variant.fields.as_slice(), [field] if field.name.is_none());
+    for (i, field) in variant.fields.iter().enumerate() {
+        let place = variant_field("v.", variant, i);
+        let param = if single {
+            "value".to_string()
+        } else {
+            field_name(field, i)
+        };
+
+        match &field.ty {
+            Ty::Array(n) => writeln!(c, "    memcpy({place}, {param}, {n});").unwrap(),
+            _ => writeln!(c, "    {place} = {param};").unwrap(),
+        }
+    }
+
+    c.push_str("    return v;\n}\n");
+}
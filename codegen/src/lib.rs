@@ -0,0 +1,483 @@
+//! Generates the C client library in `c/` from the message types in
+//! `middlesp-proto`, so calculator programs get the same ids and field layouts as
+//! the firmware.
+//!
+//! The types are read from the source with their `#[wire(id = N)]`
+//! attributes, starting from [ROOTS] and following every field. The few types
+//! with hand written impls are described in [builtin]. Any `pub const` `i32`s
+//! (the error codes) become `#define`s.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use syn::{
+    Attribute, Expr, ExprLit, ExprUnary, Fields, GenericArgument, Item, Lit, PathArguments, Type,
+    UnOp, Visibility,
+};
+
+mod c;
+
+/// The types everything else is reached from
+const ROOTS: [&str; 2] = ["CalcRequest", "CalcResponse"];
+
+/// How a value is laid out on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ty {
+    U8,
+    U16,
+    U32,
+    I8,
+    I32,
+    Bool,
+    Varint,
+    Str,
+    Bytes,
+    Array(usize),
+    List(Box<Ty>),
+    /// A struct or enum in [Schema::items]
+    Named(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    /// `None` for tuple fields
+    pub name: Option<String>,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    pub id: u8,
+    pub docs: Vec<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+#[derive(Debug, Clone)]
+pub struct WireItem {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub shape: Shape,
+}
+
+/// A `pub const` of type `i32`, such as an error code sent in an `Error`
+/// variant
+#[derive(Debug, Clone)]
+pub struct Constant {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub value: i32,
+}
+
+/// Every type reachable from [ROOTS], dependencies before the types using
+/// them
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub items: Vec<WireItem>,
+    pub constants: Vec<Constant>,
+}
+
+/// Regenerates `middlesp.h` and `middlesp.c` in `out_dir`, only touching them
+/// if they changed
+pub fn generate(spec_dir: &Path, out_dir: &Path) -> anyhow::Result<()> {
+    let schema = Schema::load(spec_dir)?;
+    let (header, source) = c::emit(&schema);
+
+    fs::create_dir_all(out_dir)?;
+    write_if_changed(&out_dir.join("middlesp.h"), &header)?;
+    write_if_changed(&out_dir.join("middlesp.c"), &source)?;
+
+    Ok(())
+}
+
+fn write_if_changed(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if fs::read_to_string(path).is_ok_and(|old| old == contents) {
+        return Ok(());
+    }
+
+    fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+impl Schema {
+    pub fn load(spec_dir: &Path) -> anyhow::Result<Self> {
+        let mut parsed = Parsed::default();
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(spec_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        paths.sort();
+
+        for path in paths
+            .iter()
+            .filter(|p| p.extension().is_some_and(|e| e == "rs"))
+        {
+            let src = fs::read_to_string(path)?;
+            let file =
+                syn::parse_file(&src).with_context(|| format!("Failed to parse {path:?}"))?;
+
+            for item in file.items {
+                parsed.add(item)?;
+            }
+        }
+
+        let mut schema = Self {
+            constants: parsed.constants.clone(),
+            ..Self::default()
+        };
+        for root in ROOTS {
+            schema.resolve(&parsed, root)?;
+        }
+
+        Ok(schema)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WireItem> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// Adds `name` and everything it uses, if not already added
+    fn resolve(&mut self, parsed: &Parsed, name: &str) -> anyhow::Result<()> {
+        if self.get(name).is_some() {
+            return Ok(());
+        }
+
+        let item = match parsed.items.get(name) {
+            Some(item) => self.convert_item(parsed, item)?,
+            None => builtin(name).ok_or_else(|| anyhow!("Unknown wire type {name}"))?,
+        };
+
+        let fields = match &item.shape {
+            Shape::Struct(fields) => fields.iter().collect::<Vec<_>>(),
+            Shape::Enum(variants) => variants.iter().flat_map(|v| &v.fields).collect(),
+        };
+        for field in fields {
+            self.resolve_ty(parsed, &field.ty)?;
+        }
+
+        self.items.push(item);
+        Ok(())
+    }
+
+    fn resolve_ty(&mut self, parsed: &Parsed, ty: &Ty) -> anyhow::Result<()> {
+        match ty {
+            Ty::Named(name) => self.resolve(parsed, name),
+            Ty::List(inner) => self.resolve_ty(parsed, inner),
+            _ => Ok(()),
+        }
+    }
+
+    fn convert_item(&mut self, parsed: &Parsed, item: &Item) -> anyhow::Result<WireItem> {
+        Ok(match item {
+            Item::Struct(s) => WireItem {
+                name: s.ident.to_string(),
+                docs: docs(&s.attrs),
+                shape: Shape::Struct(self.convert_fields(parsed, &s.fields)?),
+            },
+            Item::Enum(e) => {
+                let variants = e
+                    .variants
+                    .iter()
+                    .map(|v| {
+                        let id = wire_id(&v.attrs)?
+                            .ok_or_else(|| anyhow!("{}::{} has no wire id", e.ident, v.ident))?;
+
+                        Ok(Variant {
+                            name: v.ident.to_string(),
+                            id,
+                            docs: docs(&v.attrs),
+                            fields: self.convert_fields(parsed, &v.fields)?,
+                        })
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+
+                WireItem {
+                    name: e.ident.to_string(),
+                    docs: docs(&e.attrs),
+                    shape: Shape::Enum(variants),
+                }
+            }
+            _ => unreachable!("Only structs and enums are collected"),
+        })
+    }
+
+    fn convert_fields(&mut self, parsed: &Parsed, fields: &Fields) -> anyhow::Result<Vec<Field>> {
+        fields
+            .iter()
+            .map(|f| {
+                Ok(Field {
+                    name: f.ident.as_ref().map(|i| i.to_string()),
+                    ty: self.convert_ty(parsed, &f.ty)?,
+                })
+            })
+            .collect()
+    }
+
+    fn convert_ty(&mut self, parsed: &Parsed, ty: &Type) -> anyhow::Result<Ty> {
+        match ty {
+            Type::Tuple(tuple) => {
+                let fields = tuple
+                    .elems
+                    .iter()
+                    .map(|ty| {
+                        Ok(Field {
+                            name: None,
+                            ty: self.convert_ty(parsed, ty)?,
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                // Tuples become structs named after what they hold, e.g. `StrStr`
+                let name: String = fields.iter().map(|f| ty_name(&f.ty)).collect();
+                if self.get(&name).is_none() {
+                    for field in &fields {
+                        self.resolve_ty(parsed, &field.ty)?;
+                    }
+                    self.items.push(WireItem {
+                        name: name.clone(),
+                        docs: Vec::new(),
+                        shape: Shape::Struct(fields),
+                    });
+                }
+
+                Ok(Ty::Named(name))
+            }
+            Type::Array(array) => match &array.len {
+                Expr::Lit(lit) => match &lit.lit {
+                    Lit::Int(n) => Ok(Ty::Array(n.base10_parse()?)),
+                    _ => bail!("Array length must be an integer"),
+                },
+                _ => bail!("Array length must be a literal"),
+            },
+            Type::Path(path) => {
+                let segment = path.path.segments.last().unwrap();
+                let args: Vec<&Type> = match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => args
+                        .args
+                        .iter()
+                        .filter_map(|arg| match arg {
+                            GenericArgument::Type(ty) => Some(ty),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                let name = segment.ident.to_string();
+                Ok(match (name.as_str(), args.as_slice()) {
+                    ("u8", []) => Ty::U8,
+                    ("u16", []) => Ty::U16,
+                    ("u32", []) => Ty::U32,
+                    ("i8", []) => Ty::I8,
+                    ("i32", []) => Ty::I32,
+                    ("bool", []) => Ty::Bool,
+                    ("Varint", []) => Ty::Varint,
+                    // heapless strings are sent the same as `String`
+                    ("String", _) => Ty::Str,
+                    ("Vec", [inner]) => match self.convert_ty(parsed, inner)? {
+                        Ty::U8 => Ty::Bytes,
+                        inner => Ty::List(Box::new(inner)),
+                    },
+                    ("Result", [ok, err]) => {
+                        let ok = self.convert_ty(parsed, ok)?;
+                        let err = self.convert_ty(parsed, err)?;
+                        let name = format!("{}Result", ty_name(&ok));
+                        if self.get(&name).is_none() {
+                            self.resolve_ty(parsed, &ok)?;
+                            self.resolve_ty(parsed, &err)?;
+                            self.items.push(result_item(&name, ok, err));
+                        }
+
+                        Ty::Named(name)
+                    }
+                    (alias, []) if parsed.aliases.contains_key(alias) => {
+                        self.convert_ty(parsed, &parsed.aliases[alias])?
+                    }
+                    (name, []) => Ty::Named(name.to_string()),
+                    _ => bail!("Unsupported wire type {name}"),
+                })
+            }
+            _ => bail!("Unsupported wire type"),
+        }
+    }
+}
+
+/// The source items we might need, found by name
+#[derive(Default)]
+struct Parsed {
+    /// Structs and enums deriving our traits
+    items: HashMap<String, Item>,
+    aliases: HashMap<String, Type>,
+    /// In the order they were found
+    constants: Vec<Constant>,
+}
+
+impl Parsed {
+    fn add(&mut self, item: Item) -> anyhow::Result<()> {
+        match &item {
+            Item::Struct(s) if derives_wire(&s.attrs) => {
+                self.items.insert(s.ident.to_string(), item);
+            }
+            Item::Enum(e) if derives_wire(&e.attrs) => {
+                self.items.insert(e.ident.to_string(), item);
+            }
+            Item::Type(t) => {
+                self.aliases.insert(t.ident.to_string(), (*t.ty).clone());
+            }
+            Item::Const(c)
+                if matches!(c.vis, Visibility::Public(_))
+                    && matches!(&*c.ty, Type::Path(p) if p.path.is_ident("i32")) =>
+            {
+                self.constants.push(Constant {
+                    name: c.ident.to_string(),
+                    docs: docs(&c.attrs),
+                    value: const_value(&c.expr)
+                        .with_context(|| format!("{} is not a plain number", c.ident))?,
+                });
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// The value of a literal, possibly negated
+fn const_value(expr: &Expr) -> anyhow::Result<i32> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(n), ..
+        }) => Ok(n.base10_parse()?),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => Ok(-const_value(expr)?),
+        _ => bail!("Unsupported constant"),
+    }
+}
+
+fn derives_wire(attrs: &[Attribute]) -> bool {
+    let mut found = false;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            found |= meta.path.is_ident("Serialise") || meta.path.is_ident("Deserialise");
+            Ok(())
+        });
+    }
+
+    found
+}
+
+fn wire_id(attrs: &[Attribute]) -> anyhow::Result<Option<u8>> {
+    let mut id = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("wire")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(id)
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta.require_name_value().ok()?.value {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Str(s) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The name a type is known by in generated names
+fn ty_name(ty: &Ty) -> String {
+    match ty {
+        Ty::U8 => "U8".to_string(),
+        Ty::U16 => "U16".to_string(),
+        Ty::U32 => "U32".to_string(),
+        Ty::I8 => "I8".to_string(),
+        Ty::I32 => "I32".to_string(),
+        Ty::Bool => "Bool".to_string(),
+        Ty::Varint => "Varint".to_string(),
+        Ty::Str => "Str".to_string(),
+        Ty::Bytes => "Bytes".to_string(),
+        Ty::Array(n) => format!("Array{n}"),
+        Ty::List(inner) => format!("{}List", ty_name(inner)),
+        Ty::Named(name) => name.clone(),
+    }
+}
+
+/// `Result<T, E>`, sent as `0` then `T` or `1` then `E`
+fn result_item(name: &str, ok: Ty, err: Ty) -> WireItem {
+    WireItem {
+        name: name.to_string(),
+        docs: Vec::new(),
+        shape: Shape::Enum(vec![
+            Variant {
+                name: "Ok".to_string(),
+                id: 0,
+                docs: Vec::new(),
+                fields: vec![Field { name: None, ty: ok }],
+            },
+            Variant {
+                name: "Err".to_string(),
+                id: 1,
+                docs: Vec::new(),
+                fields: vec![Field {
+                    name: None,
+                    ty: err,
+                }],
+            },
+        ]),
+    }
+}
+
+/// Types with hand written impls, which have to be kept in step with
+/// `uart.rs` by hand
+fn builtin(name: &str) -> Option<WireItem> {
+    let field = |name: &str, ty| Field {
+        name: Some(name.to_string()),
+        ty,
+    };
+
+    let (docs, fields) = match name {
+        "UartSettings" => (
+            &["Parity is 0 none, 1 even, 2 odd, and rts/cts are 0xFF when unused"][..],
+            vec![
+                field("baud", Ty::U32),
+                field("parity", Ty::U8),
+                field("stop_bits", Ty::U8),
+                field("tx", Ty::U8),
+                field("rx", Ty::U8),
+                field("rts", Ty::U8),
+                field("cts", Ty::U8),
+            ],
+        ),
+        _ => return None,
+    };
+
+    Some(WireItem {
+        name: name.to_string(),
+        docs: docs.iter().map(|d| d.to_string()).collect(),
+        shape: Shape::Struct(fields),
+    })
+}
@@ -0,0 +1,242 @@
+void mesp_writer_init(mesp_writer_t *w, uint8_t *buf, size_t cap)
+{
+    w->buf = buf;
+    w->cap = cap;
+    w->len = 0;
+    w->error = false;
+}
+
+void mesp_reader_init(mesp_reader_t *r, const uint8_t *buf, size_t len)
+{
+    r->buf = buf;
+    r->len = len;
+    r->pos = 0;
+}
+
+mesp_reader_t mesp_list_reader(const mesp_list_t *list)
+{
+    mesp_reader_t r;
+    mesp_reader_init(&r, list->raw, list->raw_len);
+    return r;
+}
+
+mesp_str_t mesp_str(const char *s)
+{
+    mesp_str_t str;
+    str.ptr = s;
+    str.len = (uint32_t)strlen(s);
+    return str;
+}
+
+mesp_list_t mesp_list(const void *items, uint32_t count)
+{
+    mesp_list_t list;
+    list.count = count;
+    list.items = items;
+    list.raw = NULL;
+    list.raw_len = 0;
+    return list;
+}
+
+void mesp_write_array(mesp_writer_t *w, const uint8_t *v, uint32_t len)
+{
+    if (w->error || len > w->cap - w->len) {
+        w->error = true;
+        return;
+    }
+
+    memcpy(w->buf + w->len, v, len);
+    w->len += len;
+}
+
+void mesp_write_u8(mesp_writer_t *w, uint8_t v)
+{
+    mesp_write_array(w, &v, 1);
+}
+
+void mesp_write_u16(mesp_writer_t *w, uint16_t v)
+{
+    uint8_t b[2];
+    b[0] = (uint8_t)(v >> 8);
+    b[1] = (uint8_t)v;
+    mesp_write_array(w, b, 2);
+}
+
+void mesp_write_u32(mesp_writer_t *w, uint32_t v)
+{
+    uint8_t b[4];
+    b[0] = (uint8_t)(v >> 24);
+    b[1] = (uint8_t)(v >> 16);
+    b[2] = (uint8_t)(v >> 8);
+    b[3] = (uint8_t)v;
+    mesp_write_array(w, b, 4);
+}
+
+void mesp_write_i8(mesp_writer_t *w, int8_t v)
+{
+    mesp_write_u8(w, (uint8_t)v);
+}
+
+void mesp_write_i32(mesp_writer_t *w, int32_t v)
+{
+    mesp_write_u32(w, (uint32_t)v);
+}
+
+void mesp_write_bool(mesp_writer_t *w, bool v)
+{
+    mesp_write_u8(w, v ? 1 : 0);
+}
+
+void mesp_write_varint(mesp_writer_t *w, uint32_t v)
+{
+    while (v >= 0x80) {
+        mesp_write_u8(w, (uint8_t)(v | 0x80));
+        v >>= 7;
+    }
+    mesp_write_u8(w, (uint8_t)v);
+}
+
+void mesp_write_str(mesp_writer_t *w, mesp_str_t v)
+{
+    mesp_write_varint(w, v.len);
+    mesp_write_array(w, (const uint8_t *)v.ptr, v.len);
+}
+
+void mesp_write_bytes(mesp_writer_t *w, mesp_bytes_t v)
+{
+    mesp_write_varint(w, v.len);
+    mesp_write_array(w, v.ptr, v.len);
+}
+
+/* Points `out` at the next `len` bytes */
+static bool mesp_take(mesp_reader_t *r, const uint8_t **out, uint32_t len)
+{
+    if (len > r->len - r->pos) {
+        return false;
+    }
+
+    *out = r->buf + r->pos;
+    r->pos += len;
+    return true;
+}
+
+bool mesp_read_array(mesp_reader_t *r, uint8_t *out, uint32_t len)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, len)) {
+        return false;
+    }
+
+    memcpy(out, b, len);
+    return true;
+}
+
+bool mesp_read_u8(mesp_reader_t *r, uint8_t *out)
+{
+    return mesp_read_array(r, out, 1);
+}
+
+bool mesp_read_u16(mesp_reader_t *r, uint16_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, 2)) {
+        return false;
+    }
+
+    *out = (uint16_t)((uint16_t)b[0] << 8 | b[1]);
+    return true;
+}
+
+bool mesp_read_u32(mesp_reader_t *r, uint32_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_take(r, &b, 4)) {
+        return false;
+    }
+
+    *out = (uint32_t)b[0] << 24 | (uint32_t)b[1] << 16 | (uint32_t)b[2] << 8 | b[3];
+    return true;
+}
+
+bool mesp_read_i8(mesp_reader_t *r, int8_t *out)
+{
+    return mesp_read_u8(r, (uint8_t *)out);
+}
+
+bool mesp_read_i32(mesp_reader_t *r, int32_t *out)
+{
+    return mesp_read_u32(r, (uint32_t *)out);
+}
+
+bool mesp_read_bool(mesp_reader_t *r, bool *out)
+{
+    uint8_t b;
+    if (!mesp_read_u8(r, &b) || b > 1) {
+        return false;
+    }
+
+    *out = b == 1;
+    return true;
+}
+
+bool mesp_read_varint(mesp_reader_t *r, uint32_t *out)
+{
+    uint32_t n = 0;
+    uint8_t i, b;
+
+    for (i = 0; i < 5; i++) {
+        if (!mesp_read_u8(r, &b)) {
+            return false;
+        }
+        /* Only the bottom 4 bits of the 5th byte are left for a u32 */
+        if (i == 4 && b > 0x0F) {
+            return false;
+        }
+
+        n |= (uint32_t)(b & 0x7F) << (7 * i);
+        if (!(b & 0x80)) {
+            *out = n;
+            return true;
+        }
+    }
+
+    return false;
+}
+
+bool mesp_read_str(mesp_reader_t *r, mesp_str_t *out)
+{
+    const uint8_t *b;
+    if (!mesp_read_varint(r, &out->len) || !mesp_take(r, &b, out->len)) {
+        return false;
+    }
+
+    out->ptr = (const char *)b;
+    return true;
+}
+
+bool mesp_read_bytes(mesp_reader_t *r, mesp_bytes_t *out)
+{
+    return mesp_read_varint(r, &out->len) && mesp_take(r, &out->ptr, out->len);
+}
+
+bool mesp_read_list(mesp_reader_t *r, mesp_list_t *out, bool (*skip)(mesp_reader_t *))
+{
+    uint32_t i;
+    size_t start;
+
+    if (!mesp_read_varint(r, &out->count)) {
+        return false;
+    }
+
+    start = r->pos;
+    for (i = 0; i < out->count; i++) {
+        if (!skip(r)) {
+            return false;
+        }
+    }
+
+    out->items = NULL;
+    out->raw = r->buf + start;
+    out->raw_len = r->pos - start;
+    return true;
+}
@@ -0,0 +1,64 @@
+/* Strings are not NUL terminated, when read they point into the buffer */
+typedef struct {
+    const char *ptr;
+    uint32_t len;
+} mesp_str_t;
+
+typedef struct {
+    const uint8_t *ptr;
+    uint32_t len;
+} mesp_bytes_t;
+
+/* A list is written from `count` elements at `items`, and read as `count`
+ * still encoded elements at `raw`, which mesp_list_reader() walks through */
+typedef struct {
+    uint32_t count;
+    const void *items;
+    const uint8_t *raw;
+    size_t raw_len;
+} mesp_list_t;
+
+/* `error` is set once anything did not fit or could not be written */
+typedef struct {
+    uint8_t *buf;
+    size_t cap;
+    size_t len;
+    bool error;
+} mesp_writer_t;
+
+typedef struct {
+    const uint8_t *buf;
+    size_t len;
+    size_t pos;
+} mesp_reader_t;
+
+void mesp_writer_init(mesp_writer_t *w, uint8_t *buf, size_t cap);
+void mesp_reader_init(mesp_reader_t *r, const uint8_t *buf, size_t len);
+/* Reads the elements of a list which was read from a response */
+mesp_reader_t mesp_list_reader(const mesp_list_t *list);
+mesp_str_t mesp_str(const char *s);
+mesp_list_t mesp_list(const void *items, uint32_t count);
+
+void mesp_write_u8(mesp_writer_t *w, uint8_t v);
+void mesp_write_u16(mesp_writer_t *w, uint16_t v);
+void mesp_write_u32(mesp_writer_t *w, uint32_t v);
+void mesp_write_i8(mesp_writer_t *w, int8_t v);
+void mesp_write_i32(mesp_writer_t *w, int32_t v);
+void mesp_write_bool(mesp_writer_t *w, bool v);
+void mesp_write_varint(mesp_writer_t *w, uint32_t v);
+void mesp_write_str(mesp_writer_t *w, mesp_str_t v);
+void mesp_write_bytes(mesp_writer_t *w, mesp_bytes_t v);
+void mesp_write_array(mesp_writer_t *w, const uint8_t *v, uint32_t len);
+
+bool mesp_read_u8(mesp_reader_t *r, uint8_t *out);
+bool mesp_read_u16(mesp_reader_t *r, uint16_t *out);
+bool mesp_read_u32(mesp_reader_t *r, uint32_t *out);
+bool mesp_read_i8(mesp_reader_t *r, int8_t *out);
+bool mesp_read_i32(mesp_reader_t *r, int32_t *out);
+bool mesp_read_bool(mesp_reader_t *r, bool *out);
+bool mesp_read_varint(mesp_reader_t *r, uint32_t *out);
+bool mesp_read_str(mesp_reader_t *r, mesp_str_t *out);
+bool mesp_read_bytes(mesp_reader_t *r, mesp_bytes_t *out);
+bool mesp_read_array(mesp_reader_t *r, uint8_t *out, uint32_t len);
+/* Reads a list's count, then steps over its elements with `skip` */
+bool mesp_read_list(mesp_reader_t *r, mesp_list_t *out, bool (*skip)(mesp_reader_t *));
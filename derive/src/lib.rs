@@ -0,0 +1,294 @@
+//! Derives `Serialise` and `Deserialise` for the message types in `middlesp-proto`.
+//!
+//! Enums are sent as the id from each variant's `#[wire(id = N)]` followed by
+//! the variant's fields in order, structs as their fields in order. Both
+//! derives are generated from the same field list, so whatever one writes the
+//! other reads back.
+//!
+//! ```
+//! # use middlesp_derive::{Deserialise, Serialise};
+//! # use middlesp_proto::{Deserialise, Serialise};
+//! #[derive(Serialise, Deserialise)]
+//! pub enum MdnsActions {
+//!     #[wire(id = 0)]
+//!     SetHostname(String),
+//!     #[wire(id = 1)]
+//!     Browse(String),
+//! }
+//!
+//! let bytes = MdnsActions::Browse("_http._tcp".into()).to_bytes().unwrap();
+//! assert_eq!(bytes[0], 1);
+//! assert!(matches!(
+//!     MdnsActions::from_bytes(&mut bytes.as_slice()).unwrap(),
+//!     MdnsActions::Browse(s) if s == "_http._tcp"
+//! ));
+//! ```
+//!
+//! Deriving `Serialise` on an enum also gives it a `const fn id(&self)`.
+//!
+//! Every variant needs an id of its own, so neither of these compile:
+//!
+//! ```compile_fail
+//! # use middlesp_derive::Serialise;
+//! #[derive(Serialise)]
+//! enum Repeated {
+//!     #[wire(id = 0)]
+//!     A,
+//!     #[wire(id = 0)]
+//!     B,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use middlesp_derive::Deserialise;
+//! #[derive(Deserialise)]
+//! enum Missing {
+//!     #[wire(id = 0)]
+//!     A,
+//!     B,
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DataEnum, DeriveInput, Error, Fields,
+    Ident, LitInt,
+};
+
+#[proc_macro_derive(Serialise, attributes(wire))]
+pub fn derive_serialise(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    serialise(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Deserialise, attributes(wire))]
+pub fn derive_deserialise(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    deserialise(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Where the traits live in the crate using the derives
+fn krate() -> TokenStream2 {
+    quote!(::middlesp_proto)
+}
+
+fn serialise(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let krate = krate();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            check_no_id(&input.attrs)?;
+            for field in &data.fields {
+                check_no_id(&field.attrs)?;
+            }
+            let (pattern, names) = bind(&data.fields);
+
+            quote! {
+                let Self #pattern = self;
+                #[allow(unused_mut)]
+                let mut v = ::std::vec::Vec::new();
+                #( v.extend(#krate::Serialise::to_bytes(#names)?); )*
+                ::core::result::Result::Ok(v)
+            }
+        }
+        Data::Enum(data) => {
+            let ids = variant_ids(data)?;
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let (pattern, names) = bind(&variant.fields);
+
+                quote! {
+                    Self::#ident #pattern => {
+                        #( v.extend(#krate::Serialise::to_bytes(#names)?); )*
+                    }
+                }
+            });
+            let idents = data.variants.iter().map(|variant| &variant.ident);
+
+            return Ok(quote! {
+                impl #impl_generics #name #ty_generics #where_clause {
+                    /// The id this variant is sent with
+                    pub const fn id(&self) -> u8 {
+                        match self {
+                            #( Self::#idents { .. } => #ids, )*
+                        }
+                    }
+                }
+
+                impl #impl_generics #krate::Serialise for #name #ty_generics #where_clause {
+                    fn to_bytes(self) -> ::anyhow::Result<::std::vec::Vec<u8>> {
+                        #[allow(unused_mut)]
+                        let mut v = ::std::vec![self.id()];
+                        match self {
+                            #( #arms )*
+                        }
+                        ::core::result::Result::Ok(v)
+                    }
+                }
+            });
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "Unions can not be sent")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::Serialise for #name #ty_generics #where_clause {
+            fn to_bytes(self) -> ::anyhow::Result<::std::vec::Vec<u8>> {
+                #body
+            }
+        }
+    })
+}
+
+fn deserialise(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let krate = krate();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            check_no_id(&input.attrs)?;
+            let construct = construct(&data.fields);
+
+            quote!(::core::result::Result::Ok(Self #construct))
+        }
+        Data::Enum(data) => {
+            let ids = variant_ids(data)?;
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let construct = construct(&variant.fields);
+
+                quote!(Self::#ident #construct)
+            });
+            let name = name.to_string();
+
+            quote! {
+                ::core::result::Result::Ok(
+                    match <u8 as #krate::Deserialise>::from_bytes(src)? {
+                        #( #ids => #arms, )*
+                        i => ::anyhow::bail!("Unknown id: {} when trying to decode {}", i, #name),
+                    }
+                )
+            }
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "Unions can not be sent")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::Deserialise for #name #ty_generics #where_clause {
+            fn from_bytes<R: #krate::Read>(src: &mut R) -> ::anyhow::Result<Self> {
+                let _nested = #krate::Nested::enter()?;
+                #body
+            }
+        }
+    })
+}
+
+/// The ids of every variant in order, erroring on any missing or repeated id
+fn variant_ids(data: &DataEnum) -> syn::Result<Vec<LitInt>> {
+    let mut seen = HashMap::<u8, &Ident>::new();
+    let mut ids = Vec::with_capacity(data.variants.len());
+
+    for variant in &data.variants {
+        let Some(id) = wire_id(&variant.attrs)? else {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                "Every variant needs a #[wire(id = N)]",
+            ));
+        };
+
+        let n = id.base10_parse::<u8>()?;
+        if let Some(other) = seen.insert(n, &variant.ident) {
+            return Err(Error::new(
+                id.span(),
+                format!("Wire id {n} is already used by {other}"),
+            ));
+        }
+
+        for field in &variant.fields {
+            check_no_id(&field.attrs)?;
+        }
+
+        ids.push(LitInt::new(&format!("{n}u8"), id.span()));
+    }
+
+    Ok(ids)
+}
+
+/// Finds `id = N` in a `#[wire(...)]` attribute
+fn wire_id(attrs: &[Attribute]) -> syn::Result<Option<LitInt>> {
+    let mut id = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("wire")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("id") {
+                return Err(meta.error("Unknown wire attribute, expected `id = N`"));
+            }
+            if id.is_some() {
+                return Err(meta.error("Wire id given twice"));
+            }
+
+            id = Some(meta.value()?.parse::<LitInt>()?);
+            Ok(())
+        })?;
+    }
+
+    Ok(id)
+}
+
+fn check_no_id(attrs: &[Attribute]) -> syn::Result<()> {
+    match attrs.iter().find(|attr| attr.path().is_ident("wire")) {
+        Some(attr) => Err(Error::new(attr.span(), "Wire ids only go on enum variants")),
+        None => Ok(()),
+    }
+}
+
+/// A pattern binding every field, and the names they are bound to
+fn bind(fields: &Fields) -> (TokenStream2, Vec<Ident>) {
+    match fields {
+        Fields::Named(named) => {
+            let names: Vec<_> = named.named.iter().filter_map(|f| f.ident.clone()).collect();
+
+            (quote!({ #( #names ),* }), names)
+        }
+        Fields::Unnamed(unnamed) => {
+            let names: Vec<_> = (0..unnamed.unnamed.len())
+                .map(|i| format_ident!("f{i}"))
+                .collect();
+
+            (quote!(( #( #names ),* )), names)
+        }
+        Fields::Unit => (quote!(), Vec::new()),
+    }
+}
+
+/// Builds the fields back in the order [bind] wrote them
+fn construct(fields: &Fields) -> TokenStream2 {
+    let krate = krate();
+    let read = quote!(#krate::Deserialise::from_bytes(src)?);
+
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+
+            quote!({ #( #names: #read ),* })
+        }
+        Fields::Unnamed(unnamed) => {
+            let reads = unnamed.unnamed.iter().map(|_| &read);
+
+            quote!(( #( #reads ),* ))
+        }
+        Fields::Unit => quote!(),
+    }
+}
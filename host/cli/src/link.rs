@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use middlesp_proto::frame::{self, encode, Kind, Parsed, Seen};
+use serialport::SerialPort;
+
+/// How long a half received frame may sit around before we give up on it
+const RX_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long we wait for an ack before resending a frame
+const ACK_TIMEOUT: Duration = Duration::from_millis(500);
+/// How many times a frame is resent before we give up on it
+const MAX_RETRIES: u8 = 5;
+
+/// The calculator's side of the link in `src/link.rs`, over a serial port
+pub struct Link {
+    port: Box<dyn SerialPort>,
+    rx: Vec<u8>,
+    last_rx: Instant,
+    next_seq: u8,
+    seen: Seen,
+    /// Payloads of data frames which arrived while we were waiting on an ack
+    inbox: VecDeque<Vec<u8>>,
+    /// The sequence number and frame we are waiting for an ack on
+    unacked: Option<(u8, Vec<u8>)>,
+}
+
+impl Link {
+    /// Opens the port and starts a new session, so the module does not take
+    /// our data frames for resends of the last session's
+    pub fn open(path: &str, baud: u32) -> anyhow::Result<Self> {
+        let port = serialport::new(path, baud)
+            .timeout(Duration::from_millis(50))
+            .open()
+            .with_context(|| format!("Failed to open {path}"))?;
+
+        let mut link = Self {
+            port,
+            rx: Vec::new(),
+            last_rx: Instant::now(),
+            next_seq: 0,
+            seen: Seen::default(),
+            inbox: VecDeque::new(),
+            unacked: None,
+        };
+        link.send_frame(encode(Kind::Reset, 0, &[]), 0)?;
+
+        Ok(link)
+    }
+
+    /// Sends `payload` in a data frame, resending it until it is acked
+    pub fn send(&mut self, payload: &[u8]) -> anyhow::Result<()> {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+
+        self.send_frame(encode(Kind::Data, seq, payload), seq)
+    }
+
+    /// Sends `frame`, resending it until `seq` is acked
+    fn send_frame(&mut self, frame: Vec<u8>, seq: u8) -> anyhow::Result<()> {
+        self.unacked = Some((seq, frame.clone()));
+
+        for _ in 0..=MAX_RETRIES {
+            self.port.write_all(&frame)?;
+
+            let sent_at = Instant::now();
+            while sent_at.elapsed() < ACK_TIMEOUT {
+                self.poll()?;
+                if self.unacked.is_none() {
+                    return Ok(());
+                }
+            }
+        }
+
+        self.unacked = None;
+        bail!("Frame {seq} was never acked")
+    }
+
+    /// Waits up to `timeout` for the payload of the next new data frame
+    pub fn recv(&mut self, timeout: Duration) -> anyhow::Result<Option<Vec<u8>>> {
+        let start = Instant::now();
+        loop {
+            if let Some(payload) = self.inbox.pop_front() {
+                return Ok(Some(payload));
+            }
+
+            if start.elapsed() > timeout {
+                return Ok(None);
+            }
+
+            self.poll()?;
+        }
+    }
+
+    /// Reads whatever has arrived and deals with every complete frame in it
+    fn poll(&mut self) -> anyhow::Result<()> {
+        let mut buf = [0u8; 256];
+        match self.port.read(&mut buf) {
+            Ok(size) => {
+                self.rx.extend_from_slice(&buf[..size]);
+                self.last_rx = Instant::now();
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let stale = self.last_rx.elapsed() > RX_TIMEOUT;
+        while let Some(parsed) = frame::take_frame(&mut self.rx, stale) {
+            match parsed {
+                Parsed::Frame(Kind::Data, seq, payload) => {
+                    self.port.write_all(&encode(Kind::Ack, seq, &[]))?;
+
+                    if self.seen.is_new(seq) {
+                        self.inbox.push_back(payload);
+                    }
+                }
+                Parsed::Frame(Kind::Reset, seq, _) => {
+                    self.port.write_all(&encode(Kind::Ack, seq, &[]))?;
+                    self.seen.clear();
+                }
+                Parsed::Frame(Kind::Ack, seq, _) => {
+                    if self.unacked.as_ref().is_some_and(|(s, _)| *s == seq) {
+                        self.unacked = None;
+                    }
+                }
+                Parsed::Frame(Kind::Nak, seq, _) => {
+                    if let Some((_, frame)) = self.unacked.as_ref().filter(|(s, _)| *s == seq) {
+                        self.port.write_all(frame)?;
+                    }
+                }
+                Parsed::Corrupt(seq) => {
+                    eprintln!("Received corrupt frame {seq}");
+                    self.port.write_all(&encode(Kind::Nak, seq, &[]))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
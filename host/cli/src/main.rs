@@ -0,0 +1,612 @@
+//! Talks to the module from a PC over a serial port (or the pty of a
+//! simulator), e.g. `middlesp-cli --port /dev/ttyUSB0 scan`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Parser, Subcommand};
+use link::Link;
+use middlesp_proto::{
+    fetch::FetchActions,
+    http::{Convert, Headers, HttpReq, MethodWithArgs},
+    mdns::MdnsActions,
+    mqtt::{MqttActions, MqttConfig, Qos},
+    sse::SseActions,
+    tcp::{Handle, TcpActions, TcpResponse},
+    trace::{TraceActions, TraceResponse},
+    udp::UdpActions,
+    wifi::{AuthMethod, WifiActions, WifiConfig},
+    ws::{WsActions, WsMessage},
+    CalcRequest, CalcResponse, Deserialise, Serialise,
+};
+
+mod link;
+mod print;
+mod trace;
+
+/// Id our requests are sent with. The module answers its own start up
+/// requests with id `0`, so those are never mistaken for ours.
+const REQUEST_ID: u8 = 1;
+
+/// Id listen requests are sent with, as the module announces connections
+/// with it long after we have exited and those must not be taken for the
+/// answers to later requests
+const LISTEN_ID: u8 = 2;
+
+#[derive(Parser)]
+#[command(version, about = "Talks to a middlesp module over serial")]
+struct Args {
+    /// Serial port (or pty) the module is on, needed by everything but
+    /// `decode`
+    #[arg(short, long)]
+    port: Option<String>,
+    #[arg(short, long, default_value_t = 115_200)]
+    baud: u32,
+    /// Seconds to wait for each response
+    #[arg(short, long, default_value_t = 15)]
+    timeout: u64,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Ping,
+    /// Whether the wifi has been started
+    Started,
+    /// Whether the wifi is connected to an access point
+    Connected,
+    Capabilities,
+    Start,
+    Stop,
+    /// Lists the access points in range
+    Scan,
+    /// Sets the access point to use and connects to it
+    Connect {
+        ssid: String,
+        /// Left out for open networks
+        password: Option<String>,
+    },
+    Disconnect,
+    Get(Http),
+    Delete(Http),
+    Head(Http),
+    Put(Http),
+    Post {
+        #[command(flatten)]
+        http: Http,
+        body: String,
+    },
+    /// Sets the mDNS hostname, an empty name restores the default
+    Hostname {
+        name: String,
+    },
+    /// Lists the mDNS instances of a service such as `_http._tcp`
+    Browse {
+        service: String,
+    },
+    /// Fetches a `gemini://` or `gopher://` page and prints its lines, with
+    /// the links numbered
+    Fetch {
+        url: String,
+        /// Answers the page's prompt, or fills in a Gopher search
+        #[arg(short, long, default_value = "")]
+        input: String,
+    },
+    /// Forgets the certificate trusted for a Gemini host
+    Forget {
+        host: String,
+    },
+    /// Raw TCP sockets, which stay open on the module between runs
+    #[command(subcommand)]
+    Tcp(TcpCommand),
+    /// UDP sockets, which stay open on the module between runs
+    #[command(subcommand)]
+    Udp(UdpCommand),
+    /// WebSockets, which stay open on the module between runs
+    #[command(subcommand)]
+    Ws(WsCommand),
+    /// MQTT clients, which stay connected on the module between runs
+    #[command(subcommand)]
+    Mqtt(MqttCommand),
+    /// Server-Sent Event streams, which stay open on the module between runs
+    #[command(subcommand)]
+    Sse(SseCommand),
+    /// Sends a payload given in hex, starting with the request id, and prints
+    /// the response with the same id
+    Raw {
+        hex: String,
+    },
+    /// Records everything which goes over the uart on the module
+    #[command(subcommand)]
+    Trace(TraceCommand),
+    /// Prints a capture file from `trace save` decoded
+    Decode {
+        file: PathBuf,
+    },
+    /// Sends the calculator's side of a capture file again, e.g. into the
+    /// simulator to reproduce a bug
+    Replay {
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum TraceCommand {
+    /// Throws away anything recorded so far and starts recording
+    Start,
+    Stop,
+    /// Saves what has been recorded to a capture file, which the module then
+    /// forgets
+    Save {
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum TcpCommand {
+    /// Connects and prints the handle to use with the other commands
+    Open {
+        host: String,
+        port: u16,
+        #[arg(long)]
+        tls: bool,
+    },
+    Write {
+        handle: Handle,
+        data: String,
+    },
+    /// Prints whatever has arrived, waiting a little while if nothing has
+    Read {
+        handle: Handle,
+        #[arg(long, default_value_t = 4096)]
+        max: u16,
+    },
+    Close {
+        handle: Handle,
+    },
+    /// Listens on a port, any free one if left out, and prints every client
+    /// which connects until stopped. The listening socket stays open after.
+    Listen {
+        #[arg(default_value_t = 0)]
+        port: u16,
+    },
+}
+
+#[derive(Subcommand)]
+enum UdpCommand {
+    /// Binds to a local port, any free one if left out, and prints the handle
+    /// to use with the other commands
+    Bind {
+        #[arg(default_value_t = 0)]
+        port: u16,
+    },
+    /// Sends a datagram, `255.255.255.255` broadcasts it
+    Send {
+        handle: Handle,
+        host: String,
+        port: u16,
+        data: String,
+    },
+    /// Prints the oldest datagram which has arrived
+    Recv {
+        handle: Handle,
+    },
+    Close {
+        handle: Handle,
+    },
+}
+
+#[derive(Subcommand)]
+enum WsCommand {
+    /// Connects to a `ws://` or `wss://` url and prints the handle to use
+    /// with the other commands
+    Connect {
+        url: String,
+        /// Extra headers, as `Name: value`
+        #[arg(short = 'H', long = "header")]
+        headers: Vec<String>,
+    },
+    /// Sends a text message, or a binary one given in hex with `--hex`
+    Send {
+        handle: Handle,
+        data: String,
+        #[arg(long)]
+        hex: bool,
+    },
+    /// Prints the oldest message which has arrived
+    Recv {
+        handle: Handle,
+    },
+    Close {
+        handle: Handle,
+    },
+}
+
+#[derive(Subcommand)]
+enum MqttCommand {
+    /// Connects to an `mqtt://` or `mqtts://` broker and prints the handle to
+    /// use with the other commands
+    Connect {
+        url: String,
+        #[arg(long, default_value = "")]
+        client_id: String,
+        #[arg(short, long, default_value = "")]
+        username: String,
+        #[arg(short = 'P', long, default_value = "")]
+        password: String,
+    },
+    Publish {
+        handle: Handle,
+        topic: String,
+        payload: String,
+        #[arg(short, long, default_value_t = 0)]
+        qos: u8,
+        #[arg(long)]
+        retain: bool,
+    },
+    /// Starts queueing messages for a topic filter, which may use `+` and `#`
+    Subscribe {
+        handle: Handle,
+        filter: String,
+        #[arg(short, long, default_value_t = 0)]
+        qos: u8,
+    },
+    Unsubscribe {
+        handle: Handle,
+        filter: String,
+    },
+    /// Prints the oldest message queued for a filter, given exactly as it was
+    /// subscribed to
+    Recv {
+        handle: Handle,
+        filter: String,
+    },
+    Close {
+        handle: Handle,
+    },
+}
+
+#[derive(Subcommand)]
+enum SseCommand {
+    /// Opens an `http://` or `https://` event stream and prints the handle to
+    /// use with the other commands
+    Subscribe {
+        url: String,
+        /// Extra headers, as `Name: value`
+        #[arg(short = 'H', long = "header")]
+        headers: Vec<String>,
+    },
+    /// Prints the oldest event which has arrived
+    Recv {
+        handle: Handle,
+    },
+    Close {
+        handle: Handle,
+    },
+}
+
+#[derive(clap::Args)]
+struct Http {
+    url: String,
+    /// Extra headers, as `Name: value`, which only HEAD, POST and PUT send
+    #[arg(short = 'H', long = "header")]
+    headers: Vec<String>,
+    /// Do not keep the connection open afterwards
+    #[arg(long)]
+    close: bool,
+    /// Have the module turn the HTML into text, with numbered links
+    #[arg(long, conflicts_with = "json")]
+    text: bool,
+    /// Have the module send back only the value a JSON pointer (`/a/0`) or
+    /// JSONPath (`$.a[0]`) picks out of the body, can be given more than once
+    #[arg(short, long)]
+    json: Vec<String>,
+}
+
+impl Http {
+    fn into_req(self, method: impl FnOnce(Headers) -> MethodWithArgs) -> anyhow::Result<HttpReq> {
+        let headers = parse_headers(&self.headers)?;
+
+        let convert = if self.text {
+            Convert::Text
+        } else if !self.json.is_empty() {
+            Convert::Json(self.json)
+        } else {
+            Convert::Raw
+        };
+
+        Ok(HttpReq::new(self.url, self.close, method(headers), convert))
+    }
+}
+
+/// Splits `Name: value` headers up
+fn parse_headers(headers: &[String]) -> anyhow::Result<Headers> {
+    headers
+        .iter()
+        .map(|h| {
+            let (name, value) = h
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Header {h} is missing a :"))?;
+
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let (command, port) = match (args.command, args.port) {
+        (Command::Decode { file }, _) => {
+            trace::decode(&trace::load(&file)?);
+            return Ok(());
+        }
+        (_, None) => bail!("--port is needed to talk to the module"),
+        (Command::Replay { file }, Some(port)) => {
+            return trace::replay(&port, args.baud, &trace::load(&file)?);
+        }
+        (command, Some(port)) => (command, port),
+    };
+    let mut link = Link::open(&port, args.baud)?;
+    let timeout = Duration::from_secs(args.timeout);
+
+    let requests = match command {
+        Command::Ping => vec![CalcRequest::Ping],
+        Command::Started => vec![CalcRequest::Wifi(WifiActions::IsStarted)],
+        Command::Connected => vec![CalcRequest::Wifi(WifiActions::IsConnected)],
+        Command::Capabilities => vec![CalcRequest::Wifi(WifiActions::GetCapabilities)],
+        Command::Start => vec![CalcRequest::Wifi(WifiActions::Start)],
+        Command::Stop => vec![CalcRequest::Wifi(WifiActions::Stop)],
+        Command::Scan => vec![CalcRequest::Wifi(WifiActions::Scan)],
+        Command::Connect { ssid, password } => {
+            let password = password.unwrap_or_default();
+            let config = WifiConfig {
+                ssid: ssid
+                    .as_str()
+                    .try_into()
+                    .map_err(|_| anyhow!("SSID is too long"))?,
+                password: password
+                    .as_str()
+                    .try_into()
+                    .map_err(|_| anyhow!("Password is too long"))?,
+                auth_method: if password.is_empty() {
+                    AuthMethod::None
+                } else {
+                    AuthMethod::WPA2Personal
+                },
+            };
+
+            vec![
+                CalcRequest::Wifi(WifiActions::SetConfig(config)),
+                CalcRequest::Wifi(WifiActions::Connect),
+            ]
+        }
+        Command::Disconnect => vec![CalcRequest::Wifi(WifiActions::Disconnect)],
+        Command::Get(http) | Command::Delete(http) if !http.headers.is_empty() => {
+            bail!("Only HEAD, POST and PUT requests can send headers")
+        }
+        Command::Get(http) => vec![CalcRequest::Http(http.into_req(|_| MethodWithArgs::Get)?)],
+        Command::Delete(http) => vec![CalcRequest::Http(
+            http.into_req(|_| MethodWithArgs::Delete)?,
+        )],
+        Command::Head(http) => vec![CalcRequest::Http(http.into_req(MethodWithArgs::Head)?)],
+        Command::Put(http) => vec![CalcRequest::Http(http.into_req(MethodWithArgs::Put)?)],
+        Command::Post { http, body } => vec![CalcRequest::Http(
+            http.into_req(|headers| MethodWithArgs::Post(headers, body))?,
+        )],
+        Command::Fetch { url, input } => {
+            vec![CalcRequest::Fetch(FetchActions::Get { url, input })]
+        }
+        Command::Forget { host } => vec![CalcRequest::Fetch(FetchActions::Forget(host))],
+        Command::Hostname { name } => vec![CalcRequest::Mdns(MdnsActions::SetHostname(name))],
+        Command::Browse { service } => vec![CalcRequest::Mdns(MdnsActions::Browse(service))],
+        Command::Tcp(cmd) => vec![CalcRequest::Tcp(match cmd {
+            TcpCommand::Open { host, port, tls } => TcpActions::Open { host, port, tls },
+            TcpCommand::Write { handle, data } => TcpActions::Write(handle, data.into_bytes()),
+            TcpCommand::Read { handle, max } => TcpActions::Read(handle, max),
+            TcpCommand::Close { handle } => TcpActions::Close(handle),
+            TcpCommand::Listen { port } => {
+                let mut payload = vec![LISTEN_ID];
+                payload.extend(CalcRequest::Tcp(TcpActions::Listen(port)).to_bytes()?);
+
+                let resp = request(&mut link, &payload, timeout)?;
+                print::response(&resp);
+                if !matches!(resp, CalcResponse::Tcp(TcpResponse::Listening { .. })) {
+                    return Ok(());
+                }
+
+                loop {
+                    if let Some(resp) = wait_for(&mut link, LISTEN_ID, timeout)? {
+                        print::response(&resp);
+                    }
+                }
+            }
+        })],
+        Command::Udp(cmd) => vec![CalcRequest::Udp(match cmd {
+            UdpCommand::Bind { port } => UdpActions::Bind(port),
+            UdpCommand::Send {
+                handle,
+                host,
+                port,
+                data,
+            } => UdpActions::Send {
+                handle,
+                host,
+                port,
+                data: data.into_bytes(),
+            },
+            UdpCommand::Recv { handle } => UdpActions::Recv(handle),
+            UdpCommand::Close { handle } => UdpActions::Close(handle),
+        })],
+        Command::Ws(cmd) => vec![CalcRequest::Ws(match cmd {
+            WsCommand::Connect { url, headers } => WsActions::Connect {
+                url,
+                headers: parse_headers(&headers)?,
+            },
+            WsCommand::Send { handle, data, hex } => WsActions::Send(
+                handle,
+                if hex {
+                    WsMessage::Binary(parse_hex(&data)?)
+                } else {
+                    WsMessage::Text(data)
+                },
+            ),
+            WsCommand::Recv { handle } => WsActions::Recv(handle),
+            WsCommand::Close { handle } => WsActions::Close(handle),
+        })],
+        Command::Mqtt(cmd) => vec![CalcRequest::Mqtt(match cmd {
+            MqttCommand::Connect {
+                url,
+                client_id,
+                username,
+                password,
+            } => MqttActions::Connect(MqttConfig {
+                url,
+                client_id,
+                username,
+                password,
+            }),
+            MqttCommand::Publish {
+                handle,
+                topic,
+                payload,
+                qos,
+                retain,
+            } => MqttActions::Publish {
+                handle,
+                topic,
+                payload: payload.into_bytes(),
+                qos: parse_qos(qos)?,
+                retain,
+            },
+            MqttCommand::Subscribe {
+                handle,
+                filter,
+                qos,
+            } => MqttActions::Subscribe {
+                handle,
+                filter,
+                qos: parse_qos(qos)?,
+            },
+            MqttCommand::Unsubscribe { handle, filter } => {
+                MqttActions::Unsubscribe { handle, filter }
+            }
+            MqttCommand::Recv { handle, filter } => MqttActions::Recv { handle, filter },
+            MqttCommand::Close { handle } => MqttActions::Close(handle),
+        })],
+        Command::Sse(cmd) => vec![CalcRequest::Sse(match cmd {
+            SseCommand::Subscribe { url, headers } => SseActions::Subscribe {
+                url,
+                headers: parse_headers(&headers)?,
+            },
+            SseCommand::Recv { handle } => SseActions::Recv(handle),
+            SseCommand::Close { handle } => SseActions::Close(handle),
+        })],
+        Command::Raw { hex } => {
+            let payload = parse_hex(&hex)?;
+            let resp = request(&mut link, &payload, timeout)?;
+            print::response(&resp);
+
+            return Ok(());
+        }
+        Command::Trace(TraceCommand::Start) => vec![CalcRequest::Trace(TraceActions::Start)],
+        Command::Trace(TraceCommand::Stop) => vec![CalcRequest::Trace(TraceActions::Stop)],
+        Command::Trace(TraceCommand::Save { file }) => {
+            let mut payload = vec![REQUEST_ID];
+            payload.extend(CalcRequest::Trace(TraceActions::Take).to_bytes()?);
+
+            return match request(&mut link, &payload, timeout)? {
+                CalcResponse::Trace(TraceResponse::Records(records)) => trace::save(&file, records),
+                resp => {
+                    print::response(&resp);
+                    Ok(())
+                }
+            };
+        }
+        Command::Decode { .. } | Command::Replay { .. } => unreachable!(),
+    };
+
+    for req in requests {
+        let mut payload = vec![REQUEST_ID];
+        payload.extend(req.to_bytes()?);
+
+        let resp = request(&mut link, &payload, timeout)?;
+        print::response(&resp);
+    }
+
+    Ok(())
+}
+
+/// Sends a `[id][CalcRequest]` payload and waits for the response with the
+/// same id
+fn request(link: &mut Link, payload: &[u8], timeout: Duration) -> anyhow::Result<CalcResponse> {
+    let Some(&id) = payload.first() else {
+        bail!("The payload needs at least a request id");
+    };
+
+    link.send(payload)?;
+
+    wait_for(link, id, timeout)?
+        .ok_or_else(|| anyhow!("No response to {id} within {}s", timeout.as_secs()))
+}
+
+/// Waits for a response with the given id, skipping any others and joining
+/// up any fragments. Returns `None` if nothing arrives within `timeout`.
+fn wait_for(link: &mut Link, id: u8, timeout: Duration) -> anyhow::Result<Option<CalcResponse>> {
+    let mut fragments = 0;
+    let mut joined = Vec::new();
+
+    loop {
+        let Some(frame) = link.recv(timeout)? else {
+            return Ok(None);
+        };
+
+        match frame.split_first() {
+            Some((&got, mut rest)) if got == id => {
+                let raw = rest;
+                let resp = CalcResponse::from_bytes(&mut rest)
+                    .with_context(|| format!("Could not decode {}", print::hex(raw)))?;
+
+                let CalcResponse::Fragment { index, count, data } = resp else {
+                    return Ok(Some(resp));
+                };
+                if index.0 != fragments {
+                    bail!(
+                        "Got fragment {} of {id} when expecting {fragments}",
+                        index.0
+                    );
+                }
+
+                fragments += 1;
+                joined.extend(data);
+                if fragments == count.0 {
+                    return CalcResponse::from_bytes(&mut joined.as_slice())
+                        .map(Some)
+                        .context("Could not decode the joined fragments");
+                }
+            }
+            Some((got, _)) => eprintln!("Skipping the response to {got}"),
+            None => eprintln!("Skipping an empty frame"),
+        }
+    }
+}
+
+fn parse_qos(level: u8) -> anyhow::Result<Qos> {
+    Qos::try_from(level).map_err(|_| anyhow!("Qos must be 0, 1 or 2, not {level}"))
+}
+
+fn parse_hex(hex: &str) -> anyhow::Result<Vec<u8>> {
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        bail!("Hex has an odd number of digits");
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let byte: String = pair.iter().collect();
+            u8::from_str_radix(&byte, 16).with_context(|| format!("{byte} is not hex"))
+        })
+        .collect()
+}
@@ -0,0 +1,274 @@
+use middlesp_proto::{
+    fetch::{FetchResponse, Line, ERR_BAD_URL, ERR_NOT_TEXT, ERR_NOT_TRUSTED},
+    http::JsonValue,
+    mdns::MdnsResponse,
+    mqtt::{MqttResponse, ERR_NOT_SUBSCRIBED},
+    sse::SseResponse,
+    tcp::{TcpResponse, ERR_NO_SOCKET, ERR_NO_TLS, ERR_OTHER, ERR_TOO_MANY},
+    trace::TraceResponse,
+    udp::UdpResponse,
+    wifi::{AccessPoint, WifiResponse},
+    ws::{WsMessage, WsResponse, ERR_TOO_BIG},
+    CalcResponse,
+};
+
+/// Names for the capability bits, in bit order
+const CAPABILITIES: [&str; 3] = ["client", "access point", "mixed"];
+
+/// Prints a response the way a person would want to read it
+pub fn response(resp: &CalcResponse) {
+    match resp {
+        CalcResponse::Wifi(resp) => wifi(resp),
+        CalcResponse::Http(Ok(resp)) if !resp.values().is_empty() => {
+            for value in resp.values() {
+                json_value(value);
+            }
+        }
+        CalcResponse::Http(Ok(resp)) => {
+            let body = resp.body();
+            match std::str::from_utf8(body) {
+                Ok(text) => println!("{text}"),
+                Err(_) => println!("{} byte binary body: {}", body.len(), hex(body)),
+            }
+            for (i, link) in resp.links().iter().enumerate() {
+                println!("[{}] {link}", i + 1);
+            }
+        }
+        CalcResponse::Http(Err(code)) => println!("Request failed with error {code}"),
+        CalcResponse::Mdns(MdnsResponse::Error(code)) => println!("Failed with error {code}"),
+        CalcResponse::Mdns(MdnsResponse::Hostname(name)) => println!("Hostname is {name}.local"),
+        CalcResponse::Mdns(MdnsResponse::Services(services)) => {
+            if services.is_empty() {
+                println!("No services found");
+            }
+
+            for s in services {
+                let [a, b, c, d] = s.addr;
+                println!("{} on {} ({a}.{b}.{c}.{d}:{})", s.instance, s.host, s.port);
+                for (key, value) in &s.txt {
+                    println!("    {key}={value}");
+                }
+            }
+        }
+        CalcResponse::Tcp(resp) => tcp(resp),
+        CalcResponse::Udp(resp) => udp(resp),
+        CalcResponse::Ws(resp) => ws(resp),
+        CalcResponse::Mqtt(resp) => mqtt(resp),
+        CalcResponse::Sse(resp) => sse(resp),
+        CalcResponse::Fetch(resp) => fetch(resp),
+        CalcResponse::Busy => println!("Busy, the request was dropped"),
+        CalcResponse::Cancel(found) => println!("Cancelled: {found}"),
+        CalcResponse::Cancelled => println!("The request was cancelled"),
+        CalcResponse::Pong => println!("Pong"),
+        CalcResponse::MaxFrame(0) => println!("No max frame size"),
+        CalcResponse::MaxFrame(size) => println!("Max frame size is {size}"),
+        CalcResponse::Fragment { index, count, data } => {
+            println!("Fragment {} of {}: {}", index.0 + 1, count.0, hex(data))
+        }
+        CalcResponse::Uart(settings) => println!("{settings:?}"),
+        CalcResponse::Mode(mode) => println!("Switching to {mode:?} mode"),
+        CalcResponse::Trace(TraceResponse::Started) => println!("Recording"),
+        CalcResponse::Trace(TraceResponse::Stopped) => println!("Stopped recording"),
+        CalcResponse::Trace(TraceResponse::Records(records)) => crate::trace::decode(records),
+    }
+}
+
+fn wifi(resp: &WifiResponse) {
+    match resp {
+        WifiResponse::Error(code) => println!("Failed with error {code}"),
+        WifiResponse::IsStarted(b) => println!("Started: {b}"),
+        WifiResponse::IsConnected(b) => println!("Connected: {b}"),
+        WifiResponse::AccessPoints(points) => access_points(points),
+        WifiResponse::Capabilities(bits) => {
+            let names: Vec<_> = (0..)
+                .zip(CAPABILITIES)
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .map(|(_, name)| name)
+                .collect();
+            println!("Capabilities: {}", names.join(", "));
+        }
+        WifiResponse::Started => println!("Started"),
+        WifiResponse::Stopped => println!("Stopped"),
+        WifiResponse::Connected => println!("Connected"),
+        WifiResponse::Disconnected => println!("Disconnected"),
+        WifiResponse::Configured => println!("Configured"),
+    }
+}
+
+fn tcp(resp: &TcpResponse) {
+    match resp {
+        TcpResponse::Error(code) => socket_error(*code),
+        TcpResponse::Opened(handle) => println!("Opened socket {handle}"),
+        TcpResponse::Written => println!("Written"),
+        TcpResponse::Data(data) if data.is_empty() => println!("Nothing has arrived"),
+        TcpResponse::Data(data) => match std::str::from_utf8(data) {
+            Ok(text) => print!("{text}"),
+            Err(_) => println!("{} bytes: {}", data.len(), hex(data)),
+        },
+        TcpResponse::Closed => println!("Closed"),
+        TcpResponse::Listening { handle, port } => {
+            println!("Listening on port {port} with socket {handle}")
+        }
+        TcpResponse::Accepted {
+            listener,
+            handle,
+            addr: [a, b, c, d],
+            port,
+        } => {
+            println!("{a}.{b}.{c}.{d}:{port} connected to socket {listener}, it is socket {handle}")
+        }
+    }
+}
+
+fn udp(resp: &UdpResponse) {
+    match resp {
+        UdpResponse::Error(code) => socket_error(*code),
+        UdpResponse::Bound { handle, port } => println!("Bound socket {handle} to port {port}"),
+        UdpResponse::Sent => println!("Sent"),
+        UdpResponse::Received(d) => {
+            let [a, b, c, e] = d.addr;
+            match std::str::from_utf8(&d.data) {
+                Ok(text) => println!("From {a}.{b}.{c}.{e}:{}: {text}", d.port),
+                Err(_) => println!("From {a}.{b}.{c}.{e}:{}: {}", d.port, hex(&d.data)),
+            }
+        }
+        UdpResponse::Empty => println!("Nothing has arrived"),
+        UdpResponse::Closed => println!("Closed"),
+    }
+}
+
+fn ws(resp: &WsResponse) {
+    match resp {
+        WsResponse::Error(ERR_TOO_BIG) => println!("A message was too long to keep"),
+        WsResponse::Error(code) => socket_error(*code),
+        WsResponse::Connected(handle) => println!("Connected websocket {handle}"),
+        WsResponse::Sent => println!("Sent"),
+        WsResponse::Message(WsMessage::Text(text)) => println!("{text}"),
+        WsResponse::Message(WsMessage::Binary(data)) => {
+            println!("{} bytes: {}", data.len(), hex(data))
+        }
+        WsResponse::Empty => println!("Nothing has arrived"),
+        WsResponse::Closed => println!("Closed"),
+    }
+}
+
+fn mqtt(resp: &MqttResponse) {
+    match resp {
+        MqttResponse::Error(ERR_NOT_SUBSCRIBED) => println!("Not subscribed to that filter"),
+        MqttResponse::Error(code) => socket_error(*code),
+        MqttResponse::Connected(handle) => println!("Connected mqtt client {handle}"),
+        MqttResponse::Published => println!("Published"),
+        MqttResponse::Subscribed => println!("Subscribed"),
+        MqttResponse::Unsubscribed => println!("Unsubscribed"),
+        MqttResponse::Message(msg) => match std::str::from_utf8(&msg.payload) {
+            Ok(text) => println!("{}: {text}", msg.topic),
+            Err(_) => println!("{}: {}", msg.topic, hex(&msg.payload)),
+        },
+        MqttResponse::Empty => println!("Nothing has arrived"),
+        MqttResponse::Closed => println!("Closed"),
+    }
+}
+
+fn sse(resp: &SseResponse) {
+    match resp {
+        SseResponse::Error(code) => socket_error(*code),
+        SseResponse::Subscribed(handle) => println!("Subscribed to event stream {handle}"),
+        SseResponse::Event(event) if event.id.is_empty() => {
+            println!("{}: {}", event.event, event.data)
+        }
+        SseResponse::Event(event) => println!("{} ({}): {}", event.event, event.id, event.data),
+        SseResponse::Empty => println!("Nothing has arrived"),
+        SseResponse::Closed => println!("Closed"),
+    }
+}
+
+fn json_value(value: &JsonValue) {
+    match value {
+        JsonValue::Missing => println!("(missing)"),
+        JsonValue::Null => println!("null"),
+        JsonValue::Bool(b) => println!("{b}"),
+        JsonValue::Number(n) => println!("{n}"),
+        JsonValue::String(s) => println!("{s:?}"),
+        JsonValue::Numbers(numbers) => {
+            let numbers: Vec<_> = numbers.iter().map(|n| n.to_string()).collect();
+            println!("[{}]", numbers.join(", "));
+        }
+        JsonValue::Other => println!("(object or mixed array)"),
+    }
+}
+
+fn fetch(resp: &FetchResponse) {
+    match resp {
+        FetchResponse::Error(ERR_BAD_URL) => println!("Only gemini:// and gopher:// urls work"),
+        FetchResponse::Error(ERR_NOT_TEXT) => println!("That is not a text page"),
+        FetchResponse::Error(ERR_NOT_TRUSTED) => {
+            println!("No certificate was trusted for that host")
+        }
+        FetchResponse::Error(code) => socket_error(*code),
+        FetchResponse::Page(page) => {
+            let mut links = 0;
+            for line in &page.lines {
+                match line {
+                    Line::Text(text) | Line::Preformatted(text) => println!("{text}"),
+                    Line::Link { target, label } | Line::Search { target, label } => {
+                        links += 1;
+                        println!("[{links}] {label} ({target})");
+                    }
+                    Line::Heading { level, text } => {
+                        println!("{} {text}", "#".repeat(*level as usize))
+                    }
+                    Line::ListItem(text) => println!("* {text}"),
+                    Line::Quote(text) => println!("> {text}"),
+                }
+            }
+            if page.truncated {
+                println!("(cut off)");
+            }
+        }
+        FetchResponse::Input { prompt, .. } => println!("{prompt} (fetch again with --input)"),
+        FetchResponse::Redirect(url) => println!("Moved to {url}"),
+        FetchResponse::Failed { status, meta } => println!("Failed with {status}: {meta}"),
+        FetchResponse::CertChanged(host) => {
+            println!("The certificate for {host} has changed, `forget {host}` to trust the new one")
+        }
+        FetchResponse::Forgotten => println!("Forgotten"),
+    }
+}
+
+fn socket_error(code: i32) {
+    match code {
+        ERR_NO_SOCKET => println!("No socket of that kind is open with that handle"),
+        ERR_TOO_MANY => println!("Too many sockets are open"),
+        ERR_NO_TLS => println!("The module can not do TLS"),
+        ERR_OTHER => println!("Failed, e.g. the host could not be found"),
+        errno => println!("Failed with errno {errno}"),
+    }
+}
+
+fn access_points(points: &[AccessPoint]) {
+    let width = points
+        .iter()
+        .map(|ap| ap.ssid.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+
+    println!(
+        "{:width$}  {:17}  {:>7}  {:>4}",
+        "SSID", "BSSID", "CHANNEL", "RSSI"
+    );
+    for ap in points {
+        let bssid: Vec<_> = ap.bssid.iter().map(|b| format!("{b:02x}")).collect();
+        println!(
+            "{:width$}  {:17}  {:>7}  {:>4}",
+            ap.ssid,
+            bssid.join(":"),
+            ap.channel,
+            ap.signal_strength
+        );
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
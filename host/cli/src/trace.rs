@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use middlesp_proto::{
+    frame::{self, Kind, Parsed, START},
+    trace::{Direction, TraceRecord},
+    CalcRequest, CalcResponse, Deserialise, Serialise,
+};
+use serialport::SerialPort;
+
+use crate::print::hex;
+
+/// How long a replay keeps listening after the last recorded byte
+const REPLAY_TAIL: Duration = Duration::from_secs(2);
+
+pub fn save(path: &Path, records: Vec<TraceRecord>) -> anyhow::Result<()> {
+    let count = records.len();
+    fs::write(path, records.to_bytes()?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Saved {count} records to {}", path.display());
+
+    Ok(())
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Vec<TraceRecord>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    Vec::from_bytes(&mut bytes.as_slice())
+        .with_context(|| format!("{} is not a capture file", path.display()))
+}
+
+/// Splits what went each way back up into frames (or lines in text mode)
+/// and prints them decoded
+#[derive(Default)]
+pub struct Decoder {
+    /// Bytes not yet making up a whole frame or line, for each direction
+    rx: [Vec<u8>; 2],
+    /// Whether the calculator is talking in text mode, `None` until it has
+    /// sent a frame's start byte or the `A` of `AT`. The capture may start
+    /// part way through something (once the ring has wrapped), so anything
+    /// before those is skipped the same way the module does.
+    text: Option<bool>,
+}
+
+impl Decoder {
+    pub fn feed(&mut self, at_ms: u32, dir: Direction, bytes: &[u8]) {
+        self.rx[dir as usize].extend_from_slice(bytes);
+
+        if self.text.is_none() && dir == Direction::In {
+            let rx = &mut self.rx[dir as usize];
+            match rx.iter().position(|b| matches!(*b, START | b'A' | b'a')) {
+                Some(i) => {
+                    self.text = Some(rx[i] != START);
+                    rx.drain(..i);
+                }
+                None => rx.clear(),
+            }
+        }
+
+        // The module answers in whatever the calculator talks in, so what it
+        // sent before then waits until we know
+        let Some(text) = self.text else {
+            return;
+        };
+
+        for dir in [Direction::In, Direction::Out] {
+            let rx = &mut self.rx[dir as usize];
+            if text {
+                while let Some(end) = rx.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = rx.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    if !line.trim().is_empty() {
+                        print_line(at_ms, dir, &format!("{:?}", line.trim()));
+                    }
+                }
+            } else {
+                while let Some(parsed) = frame::take_frame(rx, false) {
+                    print_line(at_ms, dir, &describe(dir, parsed));
+                }
+            }
+        }
+    }
+}
+
+pub fn decode(records: &[TraceRecord]) {
+    let mut decoder = Decoder::default();
+    for record in records {
+        decoder.feed(record.at_ms, record.dir, &record.bytes);
+    }
+}
+
+/// Sends what the calculator sent, with the same timing, printing whatever
+/// comes back. Meant for the simulator, which should then go through the
+/// same session as the module did.
+pub fn replay(port: &str, baud: u32, records: &[TraceRecord]) -> anyhow::Result<()> {
+    let mut port = serialport::new(port, baud)
+        .timeout(Duration::from_millis(10))
+        .open()
+        .with_context(|| format!("Failed to open {port}"))?;
+
+    let mut decoder = Decoder::default();
+    let first = records.first().map_or(0, |r| r.at_ms);
+    let start = Instant::now();
+
+    for record in records.iter().filter(|r| r.dir == Direction::In) {
+        let due = Duration::from_millis(record.at_ms.wrapping_sub(first).into());
+        listen(&mut *port, &mut decoder, start, due)?;
+
+        port.write_all(&record.bytes)?;
+        decoder.feed(
+            start.elapsed().as_millis() as u32,
+            Direction::In,
+            &record.bytes,
+        );
+    }
+
+    listen(
+        &mut *port,
+        &mut decoder,
+        start,
+        start.elapsed() + REPLAY_TAIL,
+    )
+}
+
+/// Prints whatever the other side sends until `until` after `start`
+fn listen(
+    port: &mut dyn SerialPort,
+    decoder: &mut Decoder,
+    start: Instant,
+    until: Duration,
+) -> anyhow::Result<()> {
+    let mut buf = [0u8; 256];
+    while start.elapsed() < until {
+        match port.read(&mut buf) {
+            Ok(size) => {
+                let at_ms = start.elapsed().as_millis() as u32;
+                decoder.feed(at_ms, Direction::Out, &buf[..size]);
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+fn print_line(at_ms: u32, dir: Direction, desc: &str) {
+    let arrow = match dir {
+        Direction::In => "->",
+        Direction::Out => "<-",
+    };
+
+    println!("{:>5}.{:03} {arrow} {desc}", at_ms / 1000, at_ms % 1000);
+}
+
+fn describe(dir: Direction, parsed: Parsed) -> String {
+    match parsed {
+        Parsed::Frame(Kind::Data, seq, payload) => {
+            let Some((id, rest)) = payload.split_first() else {
+                return format!("data {seq} with no payload");
+            };
+
+            let msg = match dir {
+                Direction::In => decode_msg::<CalcRequest>(rest),
+                Direction::Out => decode_msg::<CalcResponse>(rest),
+            };
+            format!("data {seq} #{id} {msg}")
+        }
+        Parsed::Frame(Kind::Ack, seq, _) => format!("ack {seq}"),
+        Parsed::Frame(Kind::Nak, seq, _) => format!("nak {seq}"),
+        Parsed::Frame(Kind::Reset, seq, _) => format!("reset {seq}"),
+        Parsed::Corrupt(seq) => format!("corrupt frame {seq}"),
+    }
+}
+
+fn decode_msg<T: Deserialise + Debug>(mut bytes: &[u8]) -> String {
+    let raw = bytes;
+    match T::from_bytes(&mut bytes) {
+        Ok(msg) => format!("{msg:?}"),
+        Err(e) => format!("{} ({e})", hex(raw)),
+    }
+}
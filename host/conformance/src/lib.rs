@@ -0,0 +1,57 @@
+//! Reads the golden bytes in `wire.txt`, which the tests check
+//! `middlesp-proto` against.
+
+use anyhow::{anyhow, bail, Context};
+
+pub const FIXTURE: &str = include_str!("../wire.txt");
+
+/// One line of the fixture
+#[derive(Debug)]
+pub struct Case {
+    /// Line number in the fixture, for error messages
+    pub line: usize,
+    pub ty: String,
+    pub bytes: Vec<u8>,
+    /// The `Debug` output of the decoded value, `None` if the bytes have to
+    /// be rejected
+    pub expected: Option<String>,
+    pub reencoded: Vec<u8>,
+}
+
+pub fn cases() -> anyhow::Result<Vec<Case>> {
+    FIXTURE
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| parse(n, line).with_context(|| format!("Bad fixture on line {n}")))
+        .collect()
+}
+
+fn parse(line: usize, text: &str) -> anyhow::Result<Case> {
+    let cols: Vec<&str> = text.split(" | ").map(str::trim).collect();
+    let (ty, bytes, expected, reencoded) = match cols.as_slice() {
+        [ty, bytes, expected] => (ty, hex(bytes)?, expected, None),
+        [ty, bytes, expected, reencoded] => (ty, hex(bytes)?, expected, Some(hex(reencoded)?)),
+        _ => bail!("Expected 3 or 4 columns, got {}", cols.len()),
+    };
+
+    let expected = (*expected != "error").then(|| expected.to_string());
+    if expected.is_none() && reencoded.is_some() {
+        bail!("Rejected bytes can not be re-encoded");
+    }
+
+    Ok(Case {
+        line,
+        ty: ty.to_string(),
+        reencoded: reencoded.unwrap_or_else(|| bytes.clone()),
+        bytes,
+        expected,
+    })
+}
+
+fn hex(text: &str) -> anyhow::Result<Vec<u8>> {
+    text.split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| anyhow!("{byte} is not a hex byte")))
+        .collect()
+}
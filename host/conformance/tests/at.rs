@@ -0,0 +1,118 @@
+use middlesp_proto::{
+    at,
+    tcp::TcpResponse,
+    udp::{Datagram, UdpResponse},
+    wifi::{WifiActions, WifiResponse},
+    ws::{WsMessage, WsResponse},
+    CalcRequest, CalcResponse,
+};
+
+/// Commands next to the `Debug` output of the requests they stand for
+const COMMANDS: [(&str, &str); 6] = [
+    (r#"AT+TCPWRITE=0,"hi\n""#, "[Tcp(Write(0, [104, 105, 10]))]"),
+    (r#"AT+TCPWRITE=0,"\xff\x80""#, "[Tcp(Write(0, [255, 128]))]"),
+    (r#"AT+TCPWRITE=0,"\x00é""#, "[Tcp(Write(0, [0, 195, 169]))]"),
+    (
+        r#"AT+UDPSEND=1,"10.0.0.1",7,"\xc3\x28""#,
+        r#"[Udp(Send { handle: 1, host: "10.0.0.1", port: 7, data: [195, 40] })]"#,
+    ),
+    (
+        r#"AT+MQTTPUB=3,"t","\xfe""#,
+        r#"[Mqtt(Publish { handle: 3, topic: "t", payload: [254], qos: AtMostOnce, retain: false })]"#,
+    ),
+    (
+        r#"AT+GET="http://a/\xc3\xa9""#,
+        r#"[Http(HttpReq { url: "http://a/é", close: false, extra: Get, convert: Raw })]"#,
+    ),
+];
+
+#[test]
+fn parses_data_byte_for_byte() {
+    for (line, expected) in COMMANDS {
+        let reqs = at::parse(line).unwrap_or_else(|e| panic!("{line}: {e:#}"));
+        assert_eq!(format!("{reqs:?}"), expected, "{line}");
+    }
+}
+
+#[test]
+fn rejects_bad_escapes() {
+    for line in [
+        r#"AT+TCPWRITE=0,"\x""#,
+        r#"AT+TCPWRITE=0,"\xf""#,
+        r#"AT+TCPWRITE=0,"\xzz""#,
+    ] {
+        assert!(at::parse(line).is_err(), "{line}");
+    }
+}
+
+#[test]
+fn formats_data_byte_for_byte() {
+    let cases = [
+        (
+            CalcResponse::Tcp(TcpResponse::Data(vec![0xff, 0x80, b'"', b'\\', b'\n', 0])),
+            r#"+DATA:6,"\xff\x80\"\\\n\x00""#,
+        ),
+        (
+            CalcResponse::Udp(UdpResponse::Received(Datagram {
+                addr: [10, 0, 0, 1],
+                port: 7,
+                data: vec![0xc3, 0x28, 0x7f],
+            })),
+            r#"+DGRAM:10.0.0.1,7,3,"\xc3(\x7f""#,
+        ),
+        (
+            CalcResponse::Ws(WsResponse::Message(WsMessage::Text("é".to_string()))),
+            r#"+WSMSG:2,"\xc3\xa9""#,
+        ),
+    ];
+
+    for (resp, expected) in cases {
+        assert_eq!(at::format(resp), [expected, "OK"]);
+    }
+}
+
+/// Answers the requests of one command in order the way the module does,
+/// dropping the rest once one fails
+fn answer(
+    reqs: Vec<CalcRequest>,
+    mut respond: impl FnMut(CalcRequest) -> CalcResponse,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for req in reqs {
+        let answer = at::format(respond(req));
+        let failed = at::is_error(&answer);
+        lines.extend(answer);
+        if failed {
+            break;
+        }
+    }
+
+    lines
+}
+
+#[test]
+fn connect_ends_with_one_answer() {
+    let reqs = at::parse(r#"AT+CONNECT="net","secret""#).unwrap();
+    assert!(matches!(
+        reqs.as_slice(),
+        [
+            CalcRequest::Wifi(WifiActions::SetConfig(_)),
+            CalcRequest::Wifi(WifiActions::Connect)
+        ]
+    ));
+
+    let lines = answer(reqs.clone(), |req| match req {
+        CalcRequest::Wifi(WifiActions::SetConfig(_)) => {
+            CalcResponse::Wifi(WifiResponse::Configured)
+        }
+        _ => CalcResponse::Wifi(WifiResponse::Connected),
+    });
+    assert_eq!(lines, ["+CONFIGURED", "OK"]);
+
+    // The connect is never tried once the config could not be set
+    let lines = answer(reqs, |req| match req {
+        CalcRequest::Wifi(WifiActions::SetConfig(_)) => CalcResponse::Wifi(WifiResponse::Error(-1)),
+        req => panic!("{req:?} should have been dropped"),
+    });
+    assert_eq!(lines, ["ERROR:-1"]);
+}
@@ -0,0 +1,98 @@
+use std::fmt::Debug;
+
+use middlesp_derive::{Deserialise, Serialise};
+use middlesp_proto::{Deserialise, Serialise};
+
+#[derive(Debug, Clone, PartialEq, Serialise, Deserialise)]
+enum Shape {
+    #[wire(id = 0)]
+    Empty,
+    #[wire(id = 7)]
+    Line(u8, String),
+    #[wire(id = 2)]
+    Box {
+        width: u16,
+        height: u16,
+        filled: bool,
+    },
+    #[wire(id = 255)]
+    Nested(Vec<Shape>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialise, Deserialise)]
+struct Drawing {
+    name: String,
+    shapes: Vec<Shape>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialise, Deserialise)]
+struct Point(i8, i8);
+
+#[derive(Debug, Clone, PartialEq, Serialise, Deserialise)]
+struct Marker;
+
+/// Checks `value` is sent as `bytes` and read back as itself
+fn round_trip<T: Serialise + Deserialise + Clone + PartialEq + Debug>(value: T, bytes: &[u8]) {
+    assert_eq!(value.clone().to_bytes().unwrap(), bytes, "{value:?}");
+
+    let mut src = bytes;
+    assert_eq!(T::from_bytes(&mut src).unwrap(), value);
+    assert!(src.is_empty(), "{value:?} left {} bytes", src.len());
+}
+
+#[test]
+fn unit_variants() {
+    round_trip(Shape::Empty, &[0]);
+    assert_eq!(Shape::Empty.id(), 0);
+}
+
+#[test]
+fn tuple_variants() {
+    round_trip(Shape::Line(3, "ab".into()), &[7, 3, 2, b'a', b'b']);
+    assert_eq!(Shape::Line(3, "ab".into()).id(), 7);
+    round_trip(
+        Shape::Nested(vec![Shape::Empty, Shape::Nested(vec![])]),
+        &[255, 2, 0, 255, 0],
+    );
+}
+
+#[test]
+fn struct_variants() {
+    // Fields go in the order they are declared in
+    round_trip(
+        Shape::Box {
+            width: 300,
+            height: 1,
+            filled: true,
+        },
+        &[2, 1, 44, 0, 1, 1],
+    );
+    round_trip(
+        Shape::Box {
+            width: 0,
+            height: 2,
+            filled: false,
+        },
+        &[2, 0, 0, 0, 2, 0],
+    );
+}
+
+#[test]
+fn structs() {
+    round_trip(
+        Drawing {
+            name: "d".into(),
+            shapes: vec![Shape::Empty, Shape::Line(1, String::new())],
+        },
+        &[1, b'd', 2, 0, 7, 1, 0],
+    );
+    round_trip(Point(-1, 2), &[0xff, 2]);
+    round_trip(Marker, &[]);
+}
+
+#[test]
+fn rejects_unknown_ids() {
+    for id in [1, 3, 254] {
+        assert!(Shape::from_bytes(&mut &[id][..]).is_err(), "{id}");
+    }
+}
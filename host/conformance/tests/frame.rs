@@ -0,0 +1,71 @@
+use middlesp_proto::frame::{encode, take_frame, Kind, Parsed, Seen, SEEN_SIZE};
+
+/// What the receiving side does with each frame: the payloads of new data
+/// frames, forgetting what it has seen on a reset
+fn receive(seen: &mut Seen, rx: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let mut payloads = Vec::new();
+    while let Some(parsed) = take_frame(rx, false) {
+        match parsed {
+            Parsed::Frame(Kind::Data, seq, payload) => {
+                if seen.is_new(seq) {
+                    payloads.push(payload);
+                }
+            }
+            Parsed::Frame(Kind::Reset, _, _) => seen.clear(),
+            parsed => panic!("Unexpected {parsed:?}"),
+        }
+    }
+
+    payloads
+}
+
+#[test]
+fn drops_resent_frames() {
+    let mut seen = Seen::default();
+    let mut rx = [
+        encode(Kind::Data, 0, b"a"),
+        encode(Kind::Data, 1, b"b"),
+        encode(Kind::Data, 0, b"a"),
+        encode(Kind::Data, 2, b"c"),
+        encode(Kind::Data, 1, b"b"),
+    ]
+    .concat();
+
+    assert_eq!(receive(&mut seen, &mut rx), [b"a", b"b", b"c"]);
+}
+
+#[test]
+fn only_remembers_recent_frames() {
+    let mut seen = Seen::default();
+    for seq in 0..=SEEN_SIZE as u8 {
+        assert!(seen.is_new(seq));
+    }
+
+    assert!(seen.is_new(0));
+    assert!(!seen.is_new(SEEN_SIZE as u8));
+}
+
+#[test]
+fn reconnects_with_seq_0() {
+    let mut seen = Seen::default();
+
+    let mut first = [
+        encode(Kind::Reset, 0, &[]),
+        encode(Kind::Data, 0, b"first"),
+        encode(Kind::Data, 1, b"second"),
+    ]
+    .concat();
+    assert_eq!(receive(&mut seen, &mut first), [&b"first"[..], b"second"]);
+
+    // Without a reset the next session looks like resends of the last
+    let mut unannounced = encode(Kind::Data, 0, b"again");
+    assert!(receive(&mut seen, &mut unannounced).is_empty());
+
+    let mut second = [
+        encode(Kind::Reset, 0, &[]),
+        encode(Kind::Data, 0, b"again"),
+        encode(Kind::Data, 1, b"more"),
+    ]
+    .concat();
+    assert_eq!(receive(&mut seen, &mut second), [&b"again"[..], b"more"]);
+}
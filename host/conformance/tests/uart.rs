@@ -0,0 +1,49 @@
+use middlesp_proto::uart::UartSettings;
+
+fn with_pins(tx: u8, rx: u8, rts: Option<u8>, cts: Option<u8>) -> UartSettings {
+    UartSettings {
+        tx,
+        rx,
+        rts,
+        cts,
+        ..UartSettings::default()
+    }
+}
+
+#[test]
+fn defaults_are_valid() {
+    assert!(UartSettings::default().is_valid());
+    assert!(with_pins(17, 16, Some(33), Some(39)).is_valid());
+}
+
+#[test]
+fn rejects_pins_which_can_not_be_used() {
+    let cases = [
+        // Missing pins and pins past the last one
+        with_pins(20, 4, None, None),
+        with_pins(5, 40, None, None),
+        with_pins(5, 4, None, Some(255)),
+        // Flash pins
+        with_pins(5, 6, None, None),
+        with_pins(11, 4, None, None),
+        with_pins(5, 4, Some(9), None),
+        // Input only pins driving a line
+        with_pins(34, 4, None, None),
+        with_pins(5, 4, Some(39), None),
+        // The same pin twice
+        with_pins(5, 5, None, None),
+        with_pins(5, 4, Some(12), Some(12)),
+        with_pins(5, 4, None, Some(4)),
+    ];
+
+    for settings in cases {
+        assert!(!settings.is_valid(), "{settings:?}");
+    }
+}
+
+#[test]
+fn finds_flash_pins() {
+    assert_eq!(UartSettings::default().flash_pin(), None);
+    assert_eq!(with_pins(5, 6, None, None).flash_pin(), Some(6));
+    assert_eq!(with_pins(5, 4, Some(12), Some(11)).flash_pin(), Some(11));
+}
@@ -0,0 +1,113 @@
+use std::fmt::Debug;
+
+use middlesp_conformance::{cases, Case};
+use middlesp_proto::{
+    http::MethodWithArgs,
+    wifi::{WifiActions, WifiResponse},
+    CalcRequest, CalcResponse, Deserialise, Serialise,
+};
+
+/// Every type in the fixture, each of their variants needs at least one line
+const TYPES: [&str; 5] = [
+    "WifiActions",
+    "MethodWithArgs",
+    "CalcRequest",
+    "WifiResponse",
+    "CalcResponse",
+];
+
+fn check<T: Deserialise + Serialise + Debug>(case: &Case) -> Result<(), String> {
+    let mut src = case.bytes.as_slice();
+    let res = T::from_bytes(&mut src);
+
+    let (value, expected) = match (res, &case.expected) {
+        (Err(_), None) => return Ok(()),
+        (Ok(value), None) => return Err(format!("Expected an error, got {value:?}")),
+        (Err(e), Some(_)) => return Err(format!("Failed to decode: {e:#}")),
+        (Ok(value), Some(expected)) => (value, expected),
+    };
+
+    if !src.is_empty() {
+        return Err(format!("{} bytes were left over", src.len()));
+    }
+
+    let decoded = format!("{value:?}");
+    if &decoded != expected {
+        return Err(format!("Decoded as {decoded}"));
+    }
+
+    let bytes = value
+        .to_bytes()
+        .map_err(|e| format!("Failed to encode: {e:#}"))?;
+    if bytes != case.reencoded {
+        return Err(format!("Re-encoded as {bytes:02x?}"));
+    }
+
+    Ok(())
+}
+
+fn run(case: &Case) -> Result<(), String> {
+    match case.ty.as_str() {
+        "WifiActions" => check::<WifiActions>(case),
+        "MethodWithArgs" => check::<MethodWithArgs>(case),
+        "CalcRequest" => check::<CalcRequest>(case),
+        "WifiResponse" => check::<WifiResponse>(case),
+        "CalcResponse" => check::<CalcResponse>(case),
+        ty => Err(format!("Unknown type {ty}")),
+    }
+}
+
+/// Whether `id` is a variant of `ty`, going by whether the decoder knows it
+fn is_variant(ty: &str, id: u8) -> bool {
+    let res = match ty {
+        "WifiActions" => WifiActions::from_bytes(&mut &[id][..]).map(drop),
+        "MethodWithArgs" => MethodWithArgs::from_bytes(&mut &[id][..]).map(drop),
+        "CalcRequest" => CalcRequest::from_bytes(&mut &[id][..]).map(drop),
+        "WifiResponse" => WifiResponse::from_bytes(&mut &[id][..]).map(drop),
+        "CalcResponse" => CalcResponse::from_bytes(&mut &[id][..]).map(drop),
+        ty => panic!("Unknown type {ty}"),
+    };
+
+    match res {
+        Ok(()) => true,
+        Err(e) => e.to_string() != format!("Unknown id: {id} when trying to decode {ty}"),
+    }
+}
+
+#[test]
+fn golden_bytes() {
+    let failures: Vec<String> = cases()
+        .unwrap()
+        .iter()
+        .filter_map(|case| {
+            run(case)
+                .err()
+                .map(|e| format!("line {} ({}): {e}", case.line, case.ty))
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn every_variant_is_covered() {
+    let cases = cases().unwrap();
+    let mut missing = Vec::new();
+
+    for ty in TYPES {
+        for id in (0..=u8::MAX).filter(|id| is_variant(ty, *id)) {
+            let covered = cases.iter().any(|case| {
+                case.ty == ty && case.expected.is_some() && case.bytes.first() == Some(&id)
+            });
+            if !covered {
+                missing.push(format!("{ty} {id}"));
+            }
+        }
+    }
+
+    assert!(
+        missing.is_empty(),
+        "No golden bytes for {}",
+        missing.join(", ")
+    );
+}
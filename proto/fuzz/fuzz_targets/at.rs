@@ -0,0 +1,12 @@
+//! Lines sent in text mode
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use middlesp_proto::at;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(line) = std::str::from_utf8(data) {
+        let _ = at::parse(line);
+    }
+});
@@ -0,0 +1,15 @@
+//! What the module decodes from the calculator, anything it decodes has to
+//! encode again and decode back
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use middlesp_proto::{CalcRequest, Deserialise, Serialise};
+
+fuzz_target!(|data: &[u8]| {
+    let mut src = data;
+    if let Ok(req) = CalcRequest::from_bytes(&mut src) {
+        let bytes = req.to_bytes().expect("A decoded request should encode");
+        CalcRequest::from_bytes(&mut bytes.as_slice()).expect("An encoded request should decode");
+    }
+});
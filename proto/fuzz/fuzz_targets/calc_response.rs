@@ -0,0 +1,15 @@
+//! What the calculator (or host client) decodes from the module, anything it
+//! decodes has to encode again and decode back
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use middlesp_proto::{CalcResponse, Deserialise, Serialise};
+
+fuzz_target!(|data: &[u8]| {
+    let mut src = data;
+    if let Ok(resp) = CalcResponse::from_bytes(&mut src) {
+        let bytes = resp.to_bytes().expect("A decoded response should encode");
+        CalcResponse::from_bytes(&mut bytes.as_slice()).expect("An encoded response should decode");
+    }
+});
@@ -0,0 +1,10 @@
+//! Capture files loaded by the host client
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use middlesp_proto::{trace::TraceRecord, Deserialise};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Vec::<TraceRecord>::from_bytes(&mut &data[..]);
+});
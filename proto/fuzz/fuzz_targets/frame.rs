@@ -0,0 +1,14 @@
+//! Splitting whatever arrives over the uart into frames, which has to use up
+//! the buffer rather than loop or panic
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use middlesp_proto::frame::take_frame;
+
+fuzz_target!(|data: &[u8]| {
+    for stale in [false, true] {
+        let mut rx = data.to_vec();
+        while take_frame(&mut rx, stale).is_some() {}
+    }
+});
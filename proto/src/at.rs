@@ -0,0 +1,550 @@
+//! The line based text protocol, for calculator programs (e.g. TI-BASIC)
+//! which can only send and receive strings.
+//!
+//! Every command is a line such as `AT+SCAN` or `AT+GET="http://example.com"`
+//! and is answered with zero or more `+NAME:...` lines followed by `OK`,
+//! `ERROR` or `ERROR:<code>`. Strings are quoted with `\"`, `\\`, `\n`, `\r`
+//! and `\xNN` escapes, every other byte which is not printable ASCII is sent
+//! as `\xNN`. Data (e.g. `AT+TCPWRITE`'s) is taken byte for byte, text (e.g.
+//! a url) has any bytes which are not UTF-8 replaced with U+FFFD. The only lines sent without being asked for are
+//! `+ACCEPT:...` ones, when a client connects to a listening socket.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+
+use super::{
+    fetch::{FetchActions, FetchResponse, Line},
+    http::{Convert, HttpReq, JsonValue, MethodWithArgs},
+    mdns::{MdnsActions, MdnsResponse},
+    mqtt::{MqttActions, MqttConfig, MqttResponse, Qos},
+    sse::{SseActions, SseResponse},
+    tcp::{Handle, TcpActions, TcpResponse},
+    udp::{UdpActions, UdpResponse},
+    wifi::{AuthMethod, WifiActions, WifiConfig, WifiResponse},
+    ws::{WsActions, WsMessage, WsResponse},
+    CalcRequest, CalcResponse, Mode,
+};
+
+/// Turns a line into the requests it stands for, most are a single request
+/// but `AT+CONNECT="ssid","pass"` sets the config and then connects. The
+/// requests are run in order, and once one fails (see [is_error]) the rest
+/// are dropped so the command still ends with a single `OK` or `ERROR`
+pub fn parse(line: &str) -> anyhow::Result<Vec<CalcRequest>> {
+    let line = line.trim();
+    let cmd = line
+        .get(..2)
+        .filter(|at| at.eq_ignore_ascii_case("AT"))
+        .map(|_| &line[2..])
+        .ok_or_else(|| anyhow!("Line does not start with AT"))?;
+
+    if cmd.is_empty() {
+        return Ok(vec![CalcRequest::Ping]);
+    }
+
+    let cmd = cmd
+        .strip_prefix('+')
+        .ok_or_else(|| anyhow!("Expected + after AT"))?;
+    let (name, raw) = match cmd.split_once('=') {
+        Some((name, args)) => (name, parse_args(args)?),
+        None => (cmd, Vec::new()),
+    };
+    let args: Vec<String> = raw
+        .iter()
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    let data = |i: usize| raw[i].clone();
+
+    let http = |url: &String, method| {
+        vec![CalcRequest::Http(HttpReq::new(
+            url.clone(),
+            false,
+            method,
+            Convert::Raw,
+        ))]
+    };
+    let get = |url: &String, convert| {
+        vec![CalcRequest::Http(HttpReq::new(
+            url.clone(),
+            false,
+            MethodWithArgs::Get,
+            convert,
+        ))]
+    };
+
+    Ok(
+        match (name.to_ascii_uppercase().as_str(), args.as_slice()) {
+            ("STARTED?", []) => vec![CalcRequest::Wifi(WifiActions::IsStarted)],
+            ("CONNECTED?", []) => vec![CalcRequest::Wifi(WifiActions::IsConnected)],
+            ("CAPS?", []) => vec![CalcRequest::Wifi(WifiActions::GetCapabilities)],
+            ("START", []) => vec![CalcRequest::Wifi(WifiActions::Start)],
+            ("STOP", []) => vec![CalcRequest::Wifi(WifiActions::Stop)],
+            ("SCAN", []) => vec![CalcRequest::Wifi(WifiActions::Scan)],
+            ("CONNECT", []) => vec![CalcRequest::Wifi(WifiActions::Connect)],
+            ("CONNECT", [ssid]) => connect(ssid, "")?,
+            ("CONNECT", [ssid, pass]) => connect(ssid, pass)?,
+            ("DISCONNECT", []) => vec![CalcRequest::Wifi(WifiActions::Disconnect)],
+            ("GET", [url]) => http(url, MethodWithArgs::Get),
+            ("GETTEXT", [url]) => get(url, Convert::Text),
+            ("GETJSON", [url, selectors @ ..]) if !selectors.is_empty() => {
+                get(url, Convert::Json(selectors.to_vec()))
+            }
+            ("DELETE", [url]) => http(url, MethodWithArgs::Delete),
+            ("HEAD", [url]) => http(url, MethodWithArgs::Head(Vec::new())),
+            ("POST", [url, body]) => http(url, MethodWithArgs::Post(Vec::new(), body.clone())),
+            ("PUT", [url]) => http(url, MethodWithArgs::Put(Vec::new())),
+            ("HOSTNAME", [name]) => vec![CalcRequest::Mdns(MdnsActions::SetHostname(name.clone()))],
+            ("BROWSE", [service]) => vec![CalcRequest::Mdns(MdnsActions::Browse(service.clone()))],
+            ("TCPOPEN", [host, port]) => tcp_open(host, port, "0")?,
+            ("TCPOPEN", [host, port, tls]) => tcp_open(host, port, tls)?,
+            ("TCPWRITE", [h, _]) => vec![CalcRequest::Tcp(TcpActions::Write(handle(h)?, data(1)))],
+            ("TCPREAD", [h, max]) => vec![CalcRequest::Tcp(TcpActions::Read(
+                handle(h)?,
+                number(max, "length")?,
+            ))],
+            ("TCPCLOSE", [h]) => vec![CalcRequest::Tcp(TcpActions::Close(handle(h)?))],
+            ("TCPLISTEN", [port]) => {
+                vec![CalcRequest::Tcp(TcpActions::Listen(number(port, "port")?))]
+            }
+            ("UDPBIND", [port]) => vec![CalcRequest::Udp(UdpActions::Bind(number(port, "port")?))],
+            ("UDPSEND", [h, host, port, _]) => vec![CalcRequest::Udp(UdpActions::Send {
+                handle: handle(h)?,
+                host: host.clone(),
+                port: number(port, "port")?,
+                data: data(3),
+            })],
+            ("UDPRECV", [h]) => vec![CalcRequest::Udp(UdpActions::Recv(handle(h)?))],
+            ("UDPCLOSE", [h]) => vec![CalcRequest::Udp(UdpActions::Close(handle(h)?))],
+            ("WSCONNECT", [url]) => vec![CalcRequest::Ws(WsActions::Connect {
+                url: url.clone(),
+                headers: Vec::new(),
+            })],
+            ("WSSEND", [h, text]) => vec![CalcRequest::Ws(WsActions::Send(
+                handle(h)?,
+                WsMessage::Text(text.clone()),
+            ))],
+            ("WSRECV", [h]) => vec![CalcRequest::Ws(WsActions::Recv(handle(h)?))],
+            ("WSCLOSE", [h]) => vec![CalcRequest::Ws(WsActions::Close(handle(h)?))],
+            ("MQTTCONNECT", [url]) => mqtt_connect(url, "", "")?,
+            ("MQTTCONNECT", [url, user, pass]) => mqtt_connect(url, user, pass)?,
+            ("MQTTPUB", [h, topic, _]) => mqtt_publish(h, topic, data(2), "0")?,
+            ("MQTTPUB", [h, topic, _, qos]) => mqtt_publish(h, topic, data(2), qos)?,
+            ("MQTTSUB", [h, filter]) => mqtt_subscribe(h, filter, "0")?,
+            ("MQTTSUB", [h, filter, qos]) => mqtt_subscribe(h, filter, qos)?,
+            ("MQTTUNSUB", [h, filter]) => vec![CalcRequest::Mqtt(MqttActions::Unsubscribe {
+                handle: handle(h)?,
+                filter: filter.clone(),
+            })],
+            ("MQTTRECV", [h, filter]) => vec![CalcRequest::Mqtt(MqttActions::Recv {
+                handle: handle(h)?,
+                filter: filter.clone(),
+            })],
+            ("MQTTCLOSE", [h]) => vec![CalcRequest::Mqtt(MqttActions::Close(handle(h)?))],
+            ("SSESUB", [url]) => vec![CalcRequest::Sse(SseActions::Subscribe {
+                url: url.clone(),
+                headers: Vec::new(),
+            })],
+            ("SSERECV", [h]) => vec![CalcRequest::Sse(SseActions::Recv(handle(h)?))],
+            ("SSECLOSE", [h]) => vec![CalcRequest::Sse(SseActions::Close(handle(h)?))],
+            ("FETCH", [url]) => fetch(url, ""),
+            ("FETCH", [url, input]) => fetch(url, input),
+            ("FORGET", [host]) => vec![CalcRequest::Fetch(FetchActions::Forget(host.clone()))],
+            ("MODE", [mode]) => match mode.to_ascii_uppercase().as_str() {
+                "BIN" => vec![CalcRequest::SetMode(Mode::Binary)],
+                "TEXT" => vec![CalcRequest::SetMode(Mode::Text)],
+                _ => bail!("Unknown mode {mode}"),
+            },
+            (name, args) => bail!("Unknown command {name} with {} args", args.len()),
+        },
+    )
+}
+
+fn connect(ssid: &str, pass: &str) -> anyhow::Result<Vec<CalcRequest>> {
+    let config = WifiConfig {
+        ssid: ssid.try_into().map_err(|_| anyhow!("SSID is too long"))?,
+        password: pass
+            .try_into()
+            .map_err(|_| anyhow!("Password is too long"))?,
+        // WPA2 is only the least we accept, WPA3 networks still work
+        auth_method: if pass.is_empty() {
+            AuthMethod::None
+        } else {
+            AuthMethod::WPA2Personal
+        },
+    };
+
+    Ok(vec![
+        CalcRequest::Wifi(WifiActions::SetConfig(config)),
+        CalcRequest::Wifi(WifiActions::Connect),
+    ])
+}
+
+fn tcp_open(host: &str, port: &str, tls: &str) -> anyhow::Result<Vec<CalcRequest>> {
+    let tls = match tls {
+        "0" => false,
+        "1" => true,
+        _ => bail!("Expected 0 or 1 for tls, got {tls}"),
+    };
+
+    Ok(vec![CalcRequest::Tcp(TcpActions::Open {
+        host: host.to_string(),
+        port: number(port, "port")?,
+        tls,
+    })])
+}
+
+fn mqtt_connect(url: &str, user: &str, pass: &str) -> anyhow::Result<Vec<CalcRequest>> {
+    Ok(vec![CalcRequest::Mqtt(MqttActions::Connect(MqttConfig {
+        url: url.to_string(),
+        client_id: String::new(),
+        username: user.to_string(),
+        password: pass.to_string(),
+    }))])
+}
+
+fn mqtt_publish(
+    h: &str,
+    topic: &str,
+    payload: Vec<u8>,
+    qos: &str,
+) -> anyhow::Result<Vec<CalcRequest>> {
+    Ok(vec![CalcRequest::Mqtt(MqttActions::Publish {
+        handle: handle(h)?,
+        topic: topic.to_string(),
+        payload,
+        qos: qos_level(qos)?,
+        retain: false,
+    })])
+}
+
+fn mqtt_subscribe(h: &str, filter: &str, qos: &str) -> anyhow::Result<Vec<CalcRequest>> {
+    Ok(vec![CalcRequest::Mqtt(MqttActions::Subscribe {
+        handle: handle(h)?,
+        filter: filter.to_string(),
+        qos: qos_level(qos)?,
+    })])
+}
+
+fn fetch(url: &str, input: &str) -> Vec<CalcRequest> {
+    vec![CalcRequest::Fetch(FetchActions::Get {
+        url: url.to_string(),
+        input: input.to_string(),
+    })]
+}
+
+fn qos_level(arg: &str) -> anyhow::Result<Qos> {
+    Qos::try_from(number::<u8>(arg, "qos")?)
+        .map_err(|level| anyhow!("Expected 0, 1 or 2 for the qos, got {level}"))
+}
+
+fn handle(arg: &str) -> anyhow::Result<Handle> {
+    number(arg, "handle")
+}
+
+fn number<T: FromStr>(arg: &str, what: &str) -> anyhow::Result<T> {
+    arg.parse()
+        .map_err(|_| anyhow!("Expected a number for the {what}, got {arg}"))
+}
+
+/// Splits `"a\"b",12,c` into `a"b`, `12` and `c`, as bytes since `\xNN`
+/// escapes can be anything
+fn parse_args(args: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut res = Vec::new();
+    let mut bytes = args.trim().bytes().peekable();
+
+    loop {
+        let mut arg = Vec::new();
+        if bytes.peek() == Some(&b'"') {
+            bytes.next();
+            loop {
+                match bytes.next() {
+                    Some(b'"') => break,
+                    Some(b'\\') => match bytes.next() {
+                        Some(b'n') => arg.push(b'\n'),
+                        Some(b'r') => arg.push(b'\r'),
+                        Some(b'x') => {
+                            let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+                            if hex.len() != 2 || !hex.iter().all(u8::is_ascii_hexdigit) {
+                                bail!("Expected two hex digits after \\x");
+                            }
+                            let hex = std::str::from_utf8(&hex)?;
+                            arg.push(u8::from_str_radix(hex, 16)?);
+                        }
+                        Some(b) => arg.push(b),
+                        None => bail!("Unfinished escape"),
+                    },
+                    Some(b) => arg.push(b),
+                    None => bail!("Unfinished string"),
+                }
+            }
+        } else {
+            while let Some(b) = bytes.next_if(|b| *b != b',') {
+                arg.push(b);
+            }
+            // Whole characters, as it stops at an ASCII `,`
+            arg = String::from_utf8_lossy(&arg).trim().into();
+        }
+
+        res.push(arg);
+
+        match bytes.next() {
+            Some(b',') => {}
+            None => return Ok(res),
+            Some(b) => bail!("Expected , but found {}", b as char),
+        }
+    }
+}
+
+/// Whether the lines [format] gave end the command with an error
+pub fn is_error(lines: &[String]) -> bool {
+    lines.last().is_some_and(|line| line.starts_with("ERROR"))
+}
+
+/// The lines a response is sent back as
+pub fn format(resp: CalcResponse) -> Vec<String> {
+    let ok = || "OK".to_string();
+    let error = |code: i32| format!("ERROR:{code}");
+
+    match resp {
+        CalcResponse::Wifi(resp) => match resp {
+            WifiResponse::Error(code) => vec![error(code)],
+            WifiResponse::IsStarted(b) => vec![format!("+STARTED:{}", b as u8), ok()],
+            WifiResponse::IsConnected(b) => vec![format!("+CONNECTED:{}", b as u8), ok()],
+            WifiResponse::AccessPoints(points) => points
+                .into_iter()
+                .map(|ap| {
+                    format!(
+                        "+AP:{},{},{}",
+                        quote(&ap.ssid),
+                        ap.signal_strength,
+                        ap.channel
+                    )
+                })
+                .chain([ok()])
+                .collect(),
+            WifiResponse::Capabilities(caps) => vec![format!("+CAPS:{caps}"), ok()],
+            // Only ever sent first by `AT+CONNECT="ssid","pass"`, which ends
+            // with the answer to the connect, or with the error if setting
+            // the config failed
+            WifiResponse::Configured => vec!["+CONFIGURED".to_string()],
+            WifiResponse::Started
+            | WifiResponse::Stopped
+            | WifiResponse::Connected
+            | WifiResponse::Disconnected => vec![ok()],
+        },
+        // Selectors answer with their values alone, as the body is empty
+        CalcResponse::Http(Ok(resp)) if !resp.values().is_empty() => resp
+            .values()
+            .iter()
+            .map(format_value)
+            .chain([ok()])
+            .collect(),
+        CalcResponse::Http(Ok(resp)) => {
+            let mut lines = vec![format!(
+                "+HTTP:{},{}",
+                resp.body().len(),
+                quote(resp.body())
+            )];
+            lines.extend(
+                resp.links()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, link)| format!("+HREF:{},{}", i + 1, quote(link))),
+            );
+            lines.push(ok());
+            lines
+        }
+        CalcResponse::Http(Err(e)) => vec![error(e)],
+        CalcResponse::Mdns(resp) => match resp {
+            MdnsResponse::Error(code) => vec![error(code)],
+            MdnsResponse::Hostname(name) => vec![format!("+HOSTNAME:{}", quote(&name)), ok()],
+            MdnsResponse::Services(services) => services
+                .into_iter()
+                .map(|s| {
+                    let [a, b, c, d] = s.addr;
+                    format!(
+                        "+SERVICE:{},{},{a}.{b}.{c}.{d},{}",
+                        quote(&s.instance),
+                        quote(&s.host),
+                        s.port
+                    )
+                })
+                .chain([ok()])
+                .collect(),
+        },
+        CalcResponse::Tcp(resp) => match resp {
+            TcpResponse::Error(code) => vec![error(code)],
+            TcpResponse::Opened(handle) => vec![format!("+TCP:{handle}"), ok()],
+            TcpResponse::Data(data) => vec![format!("+DATA:{},{}", data.len(), quote(&data)), ok()],
+            TcpResponse::Closed => vec!["+CLOSED".to_string(), ok()],
+            TcpResponse::Written => vec![ok()],
+            TcpResponse::Listening { handle, port } => {
+                vec![format!("+LISTEN:{handle},{port}"), ok()]
+            }
+            // Not the answer to a command, so there is nothing to finish
+            TcpResponse::Accepted {
+                listener,
+                handle,
+                addr: [a, b, c, d],
+                port,
+            } => vec![format!(
+                "+ACCEPT:{listener},{handle},{a}.{b}.{c}.{d},{port}"
+            )],
+        },
+        CalcResponse::Udp(resp) => match resp {
+            UdpResponse::Error(code) => vec![error(code)],
+            UdpResponse::Bound { handle, port } => vec![format!("+UDP:{handle},{port}"), ok()],
+            UdpResponse::Received(d) => {
+                let [a, b, c, e] = d.addr;
+                vec![
+                    format!(
+                        "+DGRAM:{a}.{b}.{c}.{e},{},{},{}",
+                        d.port,
+                        d.data.len(),
+                        quote(&d.data)
+                    ),
+                    ok(),
+                ]
+            }
+            UdpResponse::Closed => vec!["+CLOSED".to_string(), ok()],
+            UdpResponse::Sent | UdpResponse::Empty => vec![ok()],
+        },
+        CalcResponse::Ws(resp) => match resp {
+            WsResponse::Error(code) => vec![error(code)],
+            WsResponse::Connected(handle) => vec![format!("+WS:{handle}"), ok()],
+            WsResponse::Message(msg) => {
+                let data = match msg {
+                    WsMessage::Text(text) => text.into_bytes(),
+                    WsMessage::Binary(data) => data,
+                };
+                vec![format!("+WSMSG:{},{}", data.len(), quote(&data)), ok()]
+            }
+            WsResponse::Closed => vec!["+CLOSED".to_string(), ok()],
+            WsResponse::Sent | WsResponse::Empty => vec![ok()],
+        },
+        CalcResponse::Mqtt(resp) => match resp {
+            MqttResponse::Error(code) => vec![error(code)],
+            MqttResponse::Connected(handle) => vec![format!("+MQTT:{handle}"), ok()],
+            MqttResponse::Message(msg) => vec![
+                format!(
+                    "+MQTTMSG:{},{},{}",
+                    quote(&msg.topic),
+                    msg.payload.len(),
+                    quote(&msg.payload)
+                ),
+                ok(),
+            ],
+            MqttResponse::Closed => vec!["+CLOSED".to_string(), ok()],
+            MqttResponse::Published
+            | MqttResponse::Subscribed
+            | MqttResponse::Unsubscribed
+            | MqttResponse::Empty => vec![ok()],
+        },
+        CalcResponse::Sse(resp) => match resp {
+            SseResponse::Error(code) => vec![error(code)],
+            SseResponse::Subscribed(handle) => vec![format!("+SSE:{handle}"), ok()],
+            SseResponse::Event(event) => vec![
+                format!(
+                    "+EVENT:{},{},{},{}",
+                    quote(&event.event),
+                    quote(&event.id),
+                    event.data.len(),
+                    quote(&event.data)
+                ),
+                ok(),
+            ],
+            SseResponse::Closed => vec!["+CLOSED".to_string(), ok()],
+            SseResponse::Empty => vec![ok()],
+        },
+        CalcResponse::Fetch(resp) => match resp {
+            FetchResponse::Error(code) => vec![error(code)],
+            FetchResponse::Page(page) => page
+                .lines
+                .into_iter()
+                .map(format_line)
+                .chain(page.truncated.then(|| "+TRUNCATED".to_string()))
+                .chain([ok()])
+                .collect(),
+            FetchResponse::Input { prompt, sensitive } => {
+                vec![
+                    format!("+INPUT:{},{}", sensitive as u8, quote(&prompt)),
+                    ok(),
+                ]
+            }
+            FetchResponse::Redirect(url) => vec![format!("+REDIRECT:{}", quote(&url)), ok()],
+            FetchResponse::Failed { status, meta } => vec![
+                format!("+FAILED:{status},{}", quote(&meta)),
+                "ERROR".to_string(),
+            ],
+            FetchResponse::CertChanged(host) => vec![
+                format!("+CERTCHANGED:{}", quote(&host)),
+                "ERROR".to_string(),
+            ],
+            FetchResponse::Forgotten => vec![ok()],
+        },
+        CalcResponse::Busy => vec!["BUSY".to_string()],
+        CalcResponse::Cancel(true) => vec![ok()],
+        CalcResponse::Cancel(false) => vec!["ERROR".to_string()],
+        CalcResponse::Cancelled => vec!["CANCELLED".to_string()],
+        CalcResponse::Uart(settings) => vec![format!("+UART:{}", settings.baud), ok()],
+        CalcResponse::Pong
+        | CalcResponse::MaxFrame(_)
+        | CalcResponse::Fragment { .. }
+        | CalcResponse::Mode(_)
+        | CalcResponse::Trace(_) => vec![ok()],
+    }
+}
+
+fn format_line(line: Line) -> String {
+    match line {
+        Line::Text(text) => format!("+TEXT:{}", quote(&text)),
+        Line::Link { target, label } => format!("+LINK:{},{}", quote(&target), quote(&label)),
+        Line::Heading { level, text } => format!("+HEADING:{level},{}", quote(&text)),
+        Line::ListItem(text) => format!("+ITEM:{}", quote(&text)),
+        Line::Quote(text) => format!("+QUOTE:{}", quote(&text)),
+        Line::Preformatted(text) => format!("+PRE:{}", quote(&text)),
+        Line::Search { target, label } => {
+            format!("+SEARCH:{},{}", quote(&target), quote(&label))
+        }
+    }
+}
+
+fn format_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Missing => "+MISSING".to_string(),
+        JsonValue::Null => "+NULL".to_string(),
+        JsonValue::Bool(b) => format!("+BOOL:{}", *b as u8),
+        JsonValue::Number(n) => format!("+NUM:{n}"),
+        JsonValue::String(s) => format!("+STR:{}", quote(s)),
+        // The count first, so a program knows how big a list to make
+        JsonValue::Numbers(numbers) => {
+            let mut line = format!("+NUMS:{}", numbers.len());
+            for n in numbers {
+                line.push_str(&format!(",{n}"));
+            }
+            line
+        }
+        JsonValue::Other => "+OTHER".to_string(),
+    }
+}
+
+/// Quotes text or data byte for byte, so the length sent alongside it is
+/// still right
+fn quote(s: impl AsRef<[u8]>) -> String {
+    let s = s.as_ref();
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for &b in s {
+        match b {
+            b'"' => res.push_str("\\\""),
+            b'\\' => res.push_str("\\\\"),
+            b'\n' => res.push_str("\\n"),
+            b'\r' => res.push_str("\\r"),
+            b' '..=b'~' => res.push(b as char),
+            b => res.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    res.push('"');
+
+    res
+}
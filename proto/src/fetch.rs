@@ -0,0 +1,87 @@
+//! Gemini and Gopher, line oriented text protocols which suit a calculator's
+//! screen far better than HTML. Pages are parsed on the module into lines the
+//! calculator can show one by one, with links already turned into full urls
+//! it can fetch next.
+//!
+//! Gemini servers mostly use self signed certificates, so the module trusts
+//! whichever certificate a host shows first and remembers it (in NVS), then
+//! refuses the host with [FetchResponse::CertChanged] if it ever changes.
+
+use middlesp_derive::{Deserialise, Serialise};
+
+/// The url is not a `gemini://` or `gopher://` one we can fetch
+pub const ERR_BAD_URL: i32 = -6;
+/// The resource is not text, so there are no lines to send
+pub const ERR_NOT_TEXT: i32 = -7;
+/// There was no certificate trusted for the host to forget
+pub const ERR_NOT_TRUSTED: i32 = -9;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum FetchActions {
+    /// Fetches a `gemini://` or `gopher://` url. `input` answers a
+    /// [FetchResponse::Input] prompt or fills in a [Line::Search], and is
+    /// left empty otherwise.
+    #[wire(id = 0)]
+    Get { url: String, input: String },
+    /// Forgets the certificate trusted for a Gemini host, so whichever it
+    /// shows next is trusted instead. Answered with [ERR_NOT_TRUSTED] if
+    /// none was
+    #[wire(id = 1)]
+    Forget(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub enum Line {
+    #[wire(id = 0)]
+    Text(String),
+    /// `target` is a full url, which need not be one we can fetch (e.g. an
+    /// `https://` one)
+    #[wire(id = 1)]
+    Link { target: String, label: String },
+    /// Level 1 to 3
+    #[wire(id = 2)]
+    Heading { level: u8, text: String },
+    #[wire(id = 3)]
+    ListItem(String),
+    #[wire(id = 4)]
+    Quote(String),
+    /// Shown as is, without wrapping
+    #[wire(id = 5)]
+    Preformatted(String),
+    /// A Gopher search, fetched with the query as the input
+    #[wire(id = 6)]
+    Search { target: String, label: String },
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct Page {
+    pub lines: Vec<Line>,
+    /// Whether the page was longer than the 8 KiB we read of it
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum FetchResponse {
+    /// An errno from the connection, one of the `ERR_` codes here or in
+    /// [crate::tcp]
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Page(Page),
+    /// The server wants input, fetch the same url again with it
+    #[wire(id = 2)]
+    Input { prompt: String, sensitive: bool },
+    /// Moved to the given full url, which is not followed for you
+    #[wire(id = 3)]
+    Redirect(String),
+    /// A Gemini failure status (40 to 69) with the server's explanation
+    #[wire(id = 4)]
+    Failed { status: u8, meta: String },
+    /// The host showed a different certificate to the one trusted for it,
+    /// which may be an attack (or just a renewed certificate, in which case
+    /// [FetchActions::Forget] it)
+    #[wire(id = 5)]
+    CertChanged(String),
+    #[wire(id = 6)]
+    Forgotten,
+}
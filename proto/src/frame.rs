@@ -0,0 +1,166 @@
+//! Splitting the uart byte stream up into frames.
+//!
+//! Every frame is `[0x7E][kind: u8][seq: u8][len: u16 BE][payload; len][crc: u16 BE]`
+//! where the crc is CRC-16/CCITT-FALSE over everything between the start byte
+//! and the crc. `kind` is one of:
+//!
+//! - `0` data: for requests the payload is `[id: u8][CalcRequest]` and for
+//!   responses `[id: u8][CalcResponse]` where the id is whatever the
+//!   calculator gave the request. Every data frame is answered with an ack.
+//! - `1` ack: the data frame `seq` arrived, there is no payload.
+//! - `2` nak: a frame arrived corrupt and should be resent, `seq` is the
+//!   (possibly corrupt) sequence number it had.
+//! - `3` reset: the sender is starting a new session, numbering its data
+//!   frames from `seq` again, so the receiver forgets which it has seen. It
+//!   has no payload and is acked like a data frame.
+//!
+//! Both sides number their data frames independently. Data frames which are
+//! not acked in time are resent with the same `seq`, so the receiver acks but
+//! otherwise ignores any `seq` it has recently seen (see [Seen]). The host
+//! sends a reset whenever it opens the link, so a new session starting at
+//! `seq` 0 again is not taken for resends of the last one.
+
+use std::collections::VecDeque;
+
+/// Marks the start of every frame, used to find our place again after garbage
+pub const START: u8 = 0x7E;
+/// `[START][kind][seq][len: u16 BE]`
+pub const HEADER_SIZE: usize = 5;
+pub const CRC_SIZE: usize = 2;
+/// Anything claiming to be bigger than this must have a corrupt length
+pub const MAX_PAYLOAD: usize = 8 * 1024;
+/// How many recently received sequence numbers are remembered to spot resends
+pub const SEEN_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Data = 0,
+    Ack = 1,
+    Nak = 2,
+    Reset = 3,
+}
+
+#[derive(Debug)]
+pub enum Parsed {
+    Frame(Kind, u8, Vec<u8>),
+    Corrupt(u8),
+}
+
+/// Takes the next frame off the front of `rx`, skipping anything before it.
+///
+/// `stale` is whether nothing has arrived for long enough that a half
+/// received frame is never going to finish, in which case it is corrupt.
+pub fn take_frame(rx: &mut Vec<u8>, stale: bool) -> Option<Parsed> {
+    // Skip anything before the next start byte
+    match rx.iter().position(|b| *b == START) {
+        Some(i) => {
+            rx.drain(..i);
+        }
+        None => {
+            rx.clear();
+            return None;
+        }
+    }
+
+    if rx.len() < HEADER_SIZE {
+        return None;
+    }
+
+    let seq = rx[2];
+    let len = u16::from_be_bytes([rx[3], rx[4]]) as usize;
+    let end = HEADER_SIZE + len + CRC_SIZE;
+
+    if len > MAX_PAYLOAD {
+        return Some(skip_corrupt(rx, seq));
+    }
+
+    if rx.len() < end {
+        // Whatever started this frame never finished, maybe the start
+        // byte was really just garbage
+        if stale {
+            return Some(skip_corrupt(rx, seq));
+        }
+
+        return None;
+    }
+
+    let crc = u16::from_be_bytes([rx[end - 2], rx[end - 1]]);
+    if crc16(&rx[1..end - CRC_SIZE]) != crc {
+        return Some(skip_corrupt(rx, seq));
+    }
+
+    let kind = match rx[1] {
+        0 => Kind::Data,
+        1 => Kind::Ack,
+        2 => Kind::Nak,
+        3 => Kind::Reset,
+        _ => return Some(skip_corrupt(rx, seq)),
+    };
+
+    let payload = rx[HEADER_SIZE..end - CRC_SIZE].to_vec();
+    rx.drain(..end);
+
+    Some(Parsed::Frame(kind, seq, payload))
+}
+
+/// The sequence numbers of the last [SEEN_SIZE] data frames received
+#[derive(Debug, Default)]
+pub struct Seen(VecDeque<u8>);
+
+impl Seen {
+    /// Whether the data frame `seq` is new rather than a resend, remembering
+    /// it if so
+    pub fn is_new(&mut self, seq: u8) -> bool {
+        if self.0.contains(&seq) {
+            return false;
+        }
+
+        if self.0.len() >= SEEN_SIZE {
+            self.0.pop_front();
+        }
+        self.0.push_back(seq);
+
+        true
+    }
+
+    /// Forgets every sequence number, for when the other side starts again
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Drops just the start byte, the real next frame could well be somewhere
+/// in what we thought was this one
+fn skip_corrupt(rx: &mut Vec<u8>, seq: u8) -> Parsed {
+    rx.remove(0);
+
+    Parsed::Corrupt(seq)
+}
+
+pub fn encode(kind: Kind, seq: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_SIZE + payload.len() + CRC_SIZE);
+
+    frame.extend([START, kind as u8, seq]);
+    frame.extend((payload.len() as u16).to_be_bytes());
+    frame.extend(payload);
+    frame.extend(crc16(&frame[1..]).to_be_bytes());
+
+    frame
+}
+
+/// CRC-16/CCITT-FALSE, simple enough to do on the calculator side too
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for &b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use middlesp_derive::{Deserialise, Serialise};
+
+pub type Headers = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum MethodWithArgs {
+    #[wire(id = 0)]
+    Delete,
+    #[wire(id = 1)]
+    Get,
+    #[wire(id = 2)]
+    Head(Headers),
+    #[wire(id = 3)]
+    Post(Headers, String),
+    #[wire(id = 4)]
+    Put(Headers),
+}
+
+/// What the module does with the body before sending it back
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub enum Convert {
+    /// Sent as is
+    #[wire(id = 0)]
+    Raw,
+    /// HTML turned into plain text, without scripts or styles and with each
+    /// link numbered (e.g. `Home [1]`). Much more of the page is read than
+    /// of a raw body, as only the text has to fit.
+    #[wire(id = 1)]
+    Text,
+    /// Only the values the selectors pick out of a JSON document, which is
+    /// read as it arrives so it can be far bigger than a raw body. Each
+    /// selector is a JSON pointer (`/current/temp`) or JSONPath without
+    /// wildcards or filters (`$.hourly.temp[0]`).
+    #[wire(id = 2)]
+    Json(Vec<String>),
+}
+
+/// A decimal number, `mantissa * 10^exponent`, which keeps what the document
+/// said exactly (up to 9 significant digits) and suits calculators' own
+/// decimal floats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialise, Deserialise)]
+pub struct Number {
+    pub mantissa: i32,
+    pub exponent: i8,
+}
+
+/// Plain decimals where they are short enough, e.g. `-0.25`, and `15E-20`
+/// otherwise
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+
+        match self.exponent {
+            0 => write!(f, "{}", self.mantissa),
+            e @ 1..=9 => write!(f, "{}{}", self.mantissa, "0".repeat(e as usize)),
+            e @ -15..=-1 => {
+                let point = digits.len() as i32 + e as i32;
+                if point > 0 {
+                    let (int, frac) = digits.split_at(point as usize);
+                    write!(f, "{sign}{int}.{frac}")
+                } else {
+                    write!(f, "{sign}0.{}{digits}", "0".repeat(-point as usize))
+                }
+            }
+            e => write!(f, "{}E{e}", self.mantissa),
+        }
+    }
+}
+
+/// What a selector picked out of a JSON document
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub enum JsonValue {
+    /// Nothing in the document matched
+    #[wire(id = 0)]
+    Missing,
+    #[wire(id = 1)]
+    Null,
+    #[wire(id = 2)]
+    Bool(bool),
+    #[wire(id = 3)]
+    Number(Number),
+    /// Cut off past 1 KiB
+    #[wire(id = 4)]
+    String(String),
+    /// An array of numbers, only the first 256 of which are kept
+    #[wire(id = 5)]
+    Numbers(Vec<Number>),
+    /// An object, or an array holding more than numbers, which has no field
+    /// to go in
+    #[wire(id = 6)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct HttpReq {
+    pub url: String,
+    /// Sends `Connection: close` and does not keep the connection around
+    pub close: bool,
+    pub extra: MethodWithArgs,
+    pub convert: Convert,
+}
+
+impl HttpReq {
+    pub fn new(url: String, close: bool, extra: MethodWithArgs, convert: Convert) -> Self {
+        Self {
+            url,
+            close,
+            extra,
+            convert,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct HttpResp {
+    raw: Vec<u8>,
+    /// Full url of each link numbered in a [Convert::Text] body, the first
+    /// being `[1]`, which can be requested next to follow it
+    links: Vec<String>,
+    /// A value for each of the [Convert::Json] selectors, in order, with the
+    /// body left empty
+    values: Vec<JsonValue>,
+}
+
+impl HttpResp {
+    pub fn new(raw: Vec<u8>) -> Self {
+        Self {
+            raw,
+            links: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn text(text: String, links: Vec<String>) -> Self {
+        Self {
+            raw: text.into_bytes(),
+            links,
+            values: Vec::new(),
+        }
+    }
+
+    pub fn json(values: Vec<JsonValue>) -> Self {
+        Self {
+            raw: Vec::new(),
+            links: Vec::new(),
+            values,
+        }
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.raw
+    }
+
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+
+    pub fn values(&self) -> &[JsonValue] {
+        &self.values
+    }
+}
@@ -0,0 +1,156 @@
+//! The messages sent between the calculator and the module, and the frames
+//! they are sent in. Shared by the firmware and the host tools so both sides
+//! always agree on the bytes.
+
+// Lets the derives name this crate the same way from inside it as from outside
+extern crate self as middlesp_proto;
+
+use fetch::{FetchActions, FetchResponse};
+use http::{HttpReq, HttpResp};
+use mdns::{MdnsActions, MdnsResponse};
+use middlesp_derive::{Deserialise, Serialise};
+use mqtt::{MqttActions, MqttResponse};
+use sse::{SseActions, SseResponse};
+use tcp::{TcpActions, TcpResponse};
+use trace::{TraceActions, TraceResponse};
+use uart::UartSettings;
+use udp::{UdpActions, UdpResponse};
+use wifi::{WifiActions, WifiResponse};
+use ws::{WsActions, WsResponse};
+
+pub mod at;
+pub mod fetch;
+pub mod frame;
+pub mod http;
+pub mod mdns;
+pub mod mqtt;
+mod safe_read;
+mod serialise;
+pub mod sse;
+pub mod tcp;
+pub mod trace;
+pub mod uart;
+pub mod udp;
+pub mod wifi;
+pub mod ws;
+
+/// Used by the derives for [Deserialise]
+pub use embedded_io::Read;
+pub use safe_read::SafeRead;
+pub use serialise::{Deserialise, Serialise, Varint, MAX_DEPTH, MAX_LEN};
+
+/// Used by the derives for [Deserialise]
+#[doc(hidden)]
+pub use serialise::Nested;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum CalcRequest {
+    #[wire(id = 0)]
+    Wifi(WifiActions),
+    #[wire(id = 1)]
+    Http(HttpReq),
+    #[wire(id = 2)]
+    Mdns(MdnsActions),
+    /// Drops the queued or running request with the given id, which is then
+    /// answered with [CalcResponse::Cancelled]
+    #[wire(id = 3)]
+    Cancel(u8),
+    /// Answered straight away with [CalcResponse::Pong], even while other
+    /// requests are running
+    #[wire(id = 4)]
+    Ping,
+    /// The largest frame the calculator can receive, header and crc
+    /// included, `0` for no limit. Responses which do not fit are sent as
+    /// [CalcResponse::Fragment]s.
+    #[wire(id = 5)]
+    SetMaxFrame(u16),
+    /// Switches the uart to new settings, answered at the old settings with
+    /// [CalcResponse::Uart]. The switch happens once that answer is acked,
+    /// and the settings are kept if the calculator talks to us with them
+    /// within a few seconds (otherwise we go back to the old ones).
+    #[wire(id = 6)]
+    SetUart(UartSettings),
+    /// Switches between the binary protocol and the text one in [at], once
+    /// the answer has gone out
+    #[wire(id = 7)]
+    SetMode(Mode),
+    /// Records what goes over the uart, see [trace]
+    #[wire(id = 8)]
+    Trace(TraceActions),
+    #[wire(id = 9)]
+    Tcp(TcpActions),
+    #[wire(id = 10)]
+    Udp(UdpActions),
+    #[wire(id = 11)]
+    Ws(WsActions),
+    #[wire(id = 12)]
+    Mqtt(MqttActions),
+    #[wire(id = 13)]
+    Sse(SseActions),
+    #[wire(id = 14)]
+    Fetch(FetchActions),
+}
+
+/// Which protocol we talk to the calculator with, picked at boot from the
+/// first byte we get (the frame start byte or the `A` of `AT`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialise, Deserialise)]
+pub enum Mode {
+    #[wire(id = 0)]
+    Binary,
+    #[wire(id = 1)]
+    Text,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum CalcResponse {
+    #[wire(id = 0)]
+    Wifi(WifiResponse),
+    /// The body of the response, or the esp error code the request failed with
+    #[wire(id = 1)]
+    Http(Result<HttpResp, i32>),
+    #[wire(id = 2)]
+    Mdns(MdnsResponse),
+    /// The queue was full so the request was dropped without being run
+    #[wire(id = 3)]
+    Busy,
+    /// Answer to a [CalcRequest::Cancel], whether there was anything to cancel
+    #[wire(id = 4)]
+    Cancel(bool),
+    /// The request was cancelled before it finished
+    #[wire(id = 5)]
+    Cancelled,
+    #[wire(id = 6)]
+    Pong,
+    /// The max frame size now in use, `0` for no limit
+    #[wire(id = 7)]
+    MaxFrame(u16),
+    /// One piece of a response too big for a single frame, the pieces'
+    /// `data` joined in order is the serialised [CalcResponse]
+    #[wire(id = 8)]
+    Fragment {
+        index: Varint,
+        count: Varint,
+        data: Vec<u8>,
+    },
+    /// The uart settings in use once any switch is done, unchanged from
+    /// before if the requested ones were invalid
+    #[wire(id = 9)]
+    Uart(UartSettings),
+    /// The mode we are switching to
+    #[wire(id = 10)]
+    Mode(Mode),
+    #[wire(id = 11)]
+    Trace(TraceResponse),
+    #[wire(id = 12)]
+    Tcp(TcpResponse),
+    #[wire(id = 13)]
+    Udp(UdpResponse),
+    #[wire(id = 14)]
+    Ws(WsResponse),
+    #[wire(id = 15)]
+    Mqtt(MqttResponse),
+    #[wire(id = 16)]
+    Sse(SseResponse),
+    #[wire(id = 17)]
+    Fetch(FetchResponse),
+}
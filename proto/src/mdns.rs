@@ -0,0 +1,34 @@
+use middlesp_derive::{Deserialise, Serialise};
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum MdnsActions {
+    /// [esp_idf_svc::mdns::EspMdns::set_hostname], an empty name restores
+    /// the default `calc-<mac>` hostname
+    #[wire(id = 0)]
+    SetHostname(String),
+    /// [esp_idf_svc::mdns::EspMdns::query_ptr] for a service such as
+    /// `_http._tcp`
+    #[wire(id = 1)]
+    Browse(String),
+}
+
+/// A single service instance found while browsing
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct ServiceInstance {
+    pub instance: String,
+    pub host: String,
+    /// The first IPv4 address of the host, `0.0.0.0` if none was given
+    pub addr: [u8; 4],
+    pub port: u16,
+    pub txt: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum MdnsResponse {
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Hostname(String),
+    #[wire(id = 2)]
+    Services(Vec<ServiceInstance>),
+}
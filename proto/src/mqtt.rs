@@ -0,0 +1,110 @@
+//! MQTT 3.1.1 clients, e.g. for reading lab sensors which publish to a local
+//! broker. Messages for each subscription are queued on the module until the
+//! calculator takes them. Clients share their handles and error codes with
+//! [crate::tcp].
+
+use middlesp_derive::{Deserialise, Serialise};
+
+use crate::tcp::Handle;
+
+/// There is no subscription to the given filter
+pub const ERR_NOT_SUBSCRIBED: i32 = -5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialise, Deserialise)]
+pub enum Qos {
+    #[wire(id = 0)]
+    AtMostOnce,
+    #[wire(id = 1)]
+    AtLeastOnce,
+    #[wire(id = 2)]
+    ExactlyOnce,
+}
+
+impl TryFrom<u8> for Qos {
+    type Error = u8;
+
+    fn try_from(level: u8) -> Result<Self, u8> {
+        match level {
+            0 => Ok(Self::AtMostOnce),
+            1 => Ok(Self::AtLeastOnce),
+            2 => Ok(Self::ExactlyOnce),
+            _ => Err(level),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct MqttConfig {
+    /// `mqtt://host[:port]`, or `mqtts://` for TLS
+    pub url: String,
+    /// Left empty for one made up from the MAC address
+    pub client_id: String,
+    /// Left empty to connect without credentials
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum MqttActions {
+    /// Answered with [MqttResponse::Connected] once the broker has accepted
+    /// us
+    #[wire(id = 0)]
+    Connect(MqttConfig),
+    #[wire(id = 1)]
+    Publish {
+        handle: Handle,
+        topic: String,
+        payload: Vec<u8>,
+        qos: Qos,
+        retain: bool,
+    },
+    /// Subscribes to a topic filter, which may use the `+` and `#` wildcards.
+    /// Messages matching it are queued (up to 16, dropping the oldest) until
+    /// taken with [MqttActions::Recv].
+    #[wire(id = 2)]
+    Subscribe {
+        handle: Handle,
+        filter: String,
+        qos: Qos,
+    },
+    /// Throws away anything still queued for the filter
+    #[wire(id = 3)]
+    Unsubscribe { handle: Handle, filter: String },
+    /// Takes the oldest message queued for a filter, given exactly as it was
+    /// subscribed to, waiting a little while for one if none have arrived
+    #[wire(id = 4)]
+    Recv { handle: Handle, filter: String },
+    #[wire(id = 5)]
+    Close(Handle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum MqttResponse {
+    /// An errno from the connection, [ERR_NOT_SUBSCRIBED] or one of the
+    /// `ERR_` codes in [crate::tcp]
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Connected(Handle),
+    #[wire(id = 2)]
+    Published,
+    #[wire(id = 3)]
+    Subscribed,
+    #[wire(id = 4)]
+    Unsubscribed,
+    #[wire(id = 5)]
+    Message(MqttMessage),
+    /// Nothing arrived in time
+    #[wire(id = 6)]
+    Empty,
+    /// The connection is closed, either by us or by the broker once every
+    /// message queued for the filter has been taken
+    #[wire(id = 7)]
+    Closed,
+}
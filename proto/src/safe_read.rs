@@ -0,0 +1,72 @@
+use anyhow::bail;
+use embedded_io::Read;
+
+pub trait SafeRead {
+    fn try_next(&mut self) -> anyhow::Result<u8> {
+        Ok(self.try_read::<1>()?[0])
+    }
+
+    /// Reads an unsigned LEB128 varint, which has to fit in a u32
+    fn try_varint(&mut self) -> anyhow::Result<u32> {
+        let mut n = 0_u32;
+        for i in 0..5 {
+            let b = self.try_next()?;
+
+            // Only the bottom 4 bits of the 5th byte are left for a u32
+            if i == 4 && b > 0x0F {
+                break;
+            }
+
+            n |= ((b & 0x7F) as u32) << (7 * i);
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+
+        bail!("Varint does not fit in a u32")
+    }
+
+    fn try_read<const N: usize>(&mut self) -> anyhow::Result<[u8; N]>;
+    fn try_read_dyn(&mut self, n: usize) -> anyhow::Result<Vec<u8>>;
+}
+
+impl<R: Read> SafeRead for R {
+    fn try_read<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        let mut buf = [0_u8; N];
+        let size_read = match self.read(&mut buf) {
+            Ok(s) => s,
+            Err(e) => bail!("Read Error: {e:?}"),
+        };
+
+        if size_read != N {
+            bail!("Size mismatch between read size ({size_read}) and expected {N}");
+        }
+
+        Ok(buf)
+    }
+
+    fn try_read_dyn(&mut self, n: usize) -> anyhow::Result<Vec<u8>> {
+        // Grown as the bytes arrive, so a corrupt length only costs as much
+        // as is actually there
+        let mut buf = Vec::new();
+        let mut chunk = [0_u8; 256];
+
+        while buf.len() < n {
+            let want = (n - buf.len()).min(chunk.len());
+            let size_read = match self.read(&mut chunk[..want]) {
+                Ok(s) => s,
+                Err(e) => bail!("Read Error: {e:?}"),
+            };
+
+            if size_read == 0 {
+                bail!(
+                    "Size mismatch between read size ({}) and expected {n}",
+                    buf.len()
+                );
+            }
+            buf.extend_from_slice(&chunk[..size_read]);
+        }
+
+        Ok(buf)
+    }
+}
@@ -0,0 +1,255 @@
+use std::cell::Cell;
+use std::mem::size_of;
+
+use anyhow::{anyhow, bail};
+use embedded_io::Read;
+
+use crate::safe_read::SafeRead;
+
+/// Most elements or bytes a single list or string can claim to have, so a
+/// corrupt length is an error rather than an allocation of gigabytes
+pub const MAX_LEN: usize = 64 * 1024;
+
+/// How deep structs and enums can be nested inside each other, so a message
+/// of a recursive type can not overflow the stack
+pub const MAX_DEPTH: usize = 32;
+
+/// Most bytes reserved up front for a list, the rest is only allocated once
+/// the elements have actually been read
+const MAX_PREALLOC: usize = 1024;
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+pub trait Serialise {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>>;
+}
+
+pub trait Deserialise: Sized {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self>;
+}
+
+/// Held while decoding a struct or enum, erroring once they are nested more
+/// than [MAX_DEPTH] deep
+pub struct Nested(());
+
+impl Nested {
+    pub fn enter() -> anyhow::Result<Self> {
+        let depth = DEPTH.with(|d| d.get());
+        if depth >= MAX_DEPTH {
+            bail!("Nested more than {MAX_DEPTH} deep");
+        }
+
+        DEPTH.with(|d| d.set(depth + 1));
+        Ok(Self(()))
+    }
+}
+
+impl Drop for Nested {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
+
+/// Reads a length or count, which has to be at most [MAX_LEN]
+fn read_len<R: Read>(src: &mut R) -> anyhow::Result<usize> {
+    let len = src.try_varint()? as usize;
+    if len > MAX_LEN {
+        bail!("Length {len} is over the limit of {MAX_LEN}");
+    }
+
+    Ok(len)
+}
+
+/// Appends `n` as an unsigned LEB128 varint, which every length and count on
+/// the wire is sent as
+pub fn write_varint(v: &mut Vec<u8>, mut n: u32) {
+    while n >= 0x80 {
+        v.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    v.push(n as u8);
+}
+
+/// Appends a length or count, which has to fit in a u32
+pub fn write_len(v: &mut Vec<u8>, len: usize) -> anyhow::Result<()> {
+    let Ok(len) = u32::try_from(len) else {
+        bail!("Length {len} does not fit in a u32");
+    };
+
+    write_varint(v, len);
+    Ok(())
+}
+
+/// A u32 sent as a varint rather than as 4 bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Varint(pub u32);
+
+impl Serialise for Varint {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = Vec::with_capacity(5);
+        write_varint(&mut v, self.0);
+
+        Ok(v)
+    }
+}
+
+impl Deserialise for Varint {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        Ok(Self(src.try_varint()?))
+    }
+}
+
+/// Fixed width numbers are sent big endian
+macro_rules! impl_int {
+    ($($t:ty),*) => {$(
+        impl Serialise for $t {
+            fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+                Ok(self.to_be_bytes().to_vec())
+            }
+        }
+
+        impl Deserialise for $t {
+            fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+                Ok(Self::from_be_bytes(src.try_read()?))
+            }
+        }
+    )*};
+}
+
+impl_int!(u8, u16, u32, i8, i32);
+
+impl Serialise for bool {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        Ok(vec![self as u8])
+    }
+}
+
+impl Deserialise for bool {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        match src.try_next()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => bail!("Expected 0 or 1 for a bool, got {b}"),
+        }
+    }
+}
+
+impl<const N: usize> Serialise for [u8; N] {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+}
+
+impl<const N: usize> Deserialise for [u8; N] {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        src.try_read::<N>()
+    }
+}
+
+impl<T: Serialise + Sized> Serialise for Vec<T> {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = Vec::new();
+        write_len(&mut v, self.len())?;
+
+        for t in self {
+            v.extend(t.to_bytes()?);
+        }
+
+        Ok(v)
+    }
+}
+
+impl<A: Serialise, B: Serialise> Serialise for (A, B) {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = self.0.to_bytes()?;
+        v.extend(self.1.to_bytes()?);
+
+        Ok(v)
+    }
+}
+
+impl Serialise for String {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = Vec::with_capacity(self.len() + 5);
+
+        write_len(&mut v, self.len())?;
+        v.extend(self.into_bytes());
+
+        Ok(v)
+    }
+}
+
+impl<const N: usize> Serialise for heapless::String<N> {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = Vec::with_capacity(self.len() + 5);
+
+        write_len(&mut v, self.len())?;
+        v.extend(self.as_bytes());
+
+        Ok(v)
+    }
+}
+
+/// `[0][T]` for `Ok` and `[1][E]` for `Err`
+impl<T: Serialise, E: Serialise> Serialise for Result<T, E> {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let (id, res) = match self {
+            Ok(t) => (0, t.to_bytes()?),
+            Err(e) => (1, e.to_bytes()?),
+        };
+
+        let mut vec = Vec::with_capacity(res.len() + 1);
+        vec.push(id);
+        vec.extend(res);
+
+        Ok(vec)
+    }
+}
+
+impl<T: Deserialise, E: Deserialise> Deserialise for Result<T, E> {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        Ok(match src.try_next()? {
+            0 => Ok(T::from_bytes(src)?),
+            1 => Err(E::from_bytes(src)?),
+            i => bail!("Unknown id: {i} when trying to decode Result"),
+        })
+    }
+}
+
+impl<A: Deserialise, B: Deserialise> Deserialise for (A, B) {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        Ok((A::from_bytes(src)?, B::from_bytes(src)?))
+    }
+}
+
+impl<T: Deserialise> Deserialise for Vec<T> {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        let len = read_len(src)?;
+        let mut res = Vec::with_capacity(len.min(MAX_PREALLOC / size_of::<T>().max(1)));
+
+        for _ in 0..len {
+            res.push(T::from_bytes(src)?)
+        }
+
+        Ok(res)
+    }
+}
+
+impl Deserialise for String {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        let len = read_len(src)?;
+
+        Ok(String::from_utf8(src.try_read_dyn(len)?)?)
+    }
+}
+
+impl<const N: usize> Deserialise for heapless::String<N> {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        let s = String::from_bytes(src)?;
+
+        heapless::String::try_from(s.as_str())
+            .map_err(|_| anyhow!("String of {} bytes does not fit in {N}", s.len()))
+    }
+}
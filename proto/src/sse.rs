@@ -0,0 +1,52 @@
+//! Server-Sent Events (`text/event-stream`), for APIs which push updates
+//! such as live scoreboards. The module keeps the stream open, reconnecting
+//! with `Last-Event-ID` if it drops, and queues events until the calculator
+//! takes them. Subscriptions share their handles and error codes with
+//! [crate::tcp].
+
+use middlesp_derive::{Deserialise, Serialise};
+
+use crate::{http::Headers, tcp::Handle};
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum SseActions {
+    /// Opens the stream with a GET, sending the extra headers. Answered with
+    /// [SseResponse::Subscribed] once the server has answered.
+    #[wire(id = 0)]
+    Subscribe { url: String, headers: Headers },
+    /// Takes the oldest event (up to 32 are queued, dropping the oldest),
+    /// waiting a little while for one if none have arrived
+    #[wire(id = 1)]
+    Recv(Handle),
+    #[wire(id = 2)]
+    Close(Handle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub struct SseEvent {
+    /// `message` unless the server named it
+    pub event: String,
+    /// Each `data:` line joined with `\n`, anything past 4 KiB is cut off
+    pub data: String,
+    /// The last id the server sent, which need not be from this event
+    pub id: String,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum SseResponse {
+    /// An errno from the connection, or one of the `ERR_` codes in
+    /// [crate::tcp]
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Subscribed(Handle),
+    #[wire(id = 2)]
+    Event(SseEvent),
+    /// Nothing arrived in time
+    #[wire(id = 3)]
+    Empty,
+    /// The stream is closed, either by us or because reconnecting kept
+    /// failing, once every queued event has been taken
+    #[wire(id = 4)]
+    Closed,
+}
@@ -0,0 +1,72 @@
+//! Raw TCP sockets, for talking to services which are not HTTP (e.g. line
+//! based game servers) or for being a server. Each open socket is known by a
+//! handle the module picks, which stays valid until the socket is closed.
+
+use middlesp_derive::{Deserialise, Serialise};
+
+/// Picks out one open socket, TCP and [crate::udp] sockets share handles
+/// and error codes
+pub type Handle = u8;
+
+/// No socket of the right kind is open with the given handle
+pub const ERR_NO_SOCKET: i32 = -1;
+/// As many sockets are open as the module allows
+pub const ERR_TOO_MANY: i32 = -2;
+/// The module was built without TLS
+pub const ERR_NO_TLS: i32 = -3;
+/// Failed without an errno, e.g. the host name could not be resolved
+pub const ERR_OTHER: i32 = -4;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum TcpActions {
+    /// Connects to `host:port`, answered with [TcpResponse::Opened]
+    #[wire(id = 0)]
+    Open { host: String, port: u16, tls: bool },
+    /// Sends all of the bytes, answered with [TcpResponse::Written]
+    #[wire(id = 1)]
+    Write(Handle, Vec<u8>),
+    /// Reads up to the given number of bytes (at most 4 KiB), waiting a
+    /// little while for some to arrive if none have
+    #[wire(id = 2)]
+    Read(Handle, u16),
+    /// Closes a connection, or stops listening (connections already accepted
+    /// stay open)
+    #[wire(id = 3)]
+    Close(Handle),
+    /// Listens on a local port (`0` for any free one), answered with
+    /// [TcpResponse::Listening]. Every client which connects afterwards is
+    /// announced with a [TcpResponse::Accepted] sent with this request's id.
+    #[wire(id = 4)]
+    Listen(u16),
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum TcpResponse {
+    /// An errno from the socket, or one of the `ERR_` codes (which are all
+    /// negative)
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Opened(Handle),
+    #[wire(id = 2)]
+    Written,
+    /// Whatever had arrived, empty if nothing did in time
+    #[wire(id = 3)]
+    Data(Vec<u8>),
+    /// The socket has been closed, either as asked or (in answer to a read)
+    /// because the other end closed it. Its handle is no longer valid.
+    #[wire(id = 4)]
+    Closed,
+    /// The listening socket's handle and the local port it is on
+    #[wire(id = 5)]
+    Listening { handle: Handle, port: u16 },
+    /// Sent unasked whenever a client connects to a listening socket, with
+    /// the handle for the new connection and where it came from
+    #[wire(id = 6)]
+    Accepted {
+        listener: Handle,
+        handle: Handle,
+        addr: [u8; 4],
+        port: u16,
+    },
+}
@@ -0,0 +1,51 @@
+//! Recording everything which goes over the uart, so a session with a
+//! misbehaving calculator program can be looked at (or replayed) afterwards.
+//!
+//! A capture file is a serialised `Vec<TraceRecord>`, the same as the
+//! records in [TraceResponse::Records].
+
+use middlesp_derive::{Deserialise, Serialise};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialise, Deserialise)]
+pub enum Direction {
+    /// From the calculator
+    #[wire(id = 0)]
+    In,
+    /// To the calculator
+    #[wire(id = 1)]
+    Out,
+}
+
+/// Bytes read from or written to the uart in one go, these are raw so may
+/// hold part of a frame or several of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub struct TraceRecord {
+    /// Milliseconds since the module booted
+    pub at_ms: u32,
+    pub dir: Direction,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum TraceActions {
+    /// Throws away anything recorded so far and starts recording
+    #[wire(id = 0)]
+    Start,
+    #[wire(id = 1)]
+    Stop,
+    /// Hands over everything recorded so far, which is then forgotten.
+    /// Recording carries on if it was started.
+    #[wire(id = 2)]
+    Take,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum TraceResponse {
+    #[wire(id = 0)]
+    Started,
+    #[wire(id = 1)]
+    Stopped,
+    /// Oldest first, the oldest are dropped once the buffer is full
+    #[wire(id = 2)]
+    Records(Vec<TraceRecord>),
+}
@@ -0,0 +1,113 @@
+use embedded_io::Read;
+
+use crate::{Deserialise, SafeRead, Serialise};
+
+/// Marks an unused rts/cts pin
+const NO_PIN: u8 = 0xFF;
+const MIN_BAUD: u32 = 1200;
+const MAX_BAUD: u32 = 5_000_000;
+/// Highest GPIO on the ESP32, 34 and up can only be inputs
+const MAX_PIN: u8 = 39;
+const MAX_OUTPUT_PIN: u8 = 33;
+/// GPIOs the ESP32 does not have
+const MISSING_PINS: [u8; 6] = [20, 24, 28, 29, 30, 31];
+/// GPIOs wired to the SPI flash, taking one over crashes the module
+const FLASH_PINS: std::ops::RangeInclusive<u8> = 6..=11;
+
+/// How the uart to the calculator is set up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UartSettings {
+    pub baud: u32,
+    /// 0 none, 1 even, 2 odd
+    pub parity: u8,
+    /// 1 or 2
+    pub stop_bits: u8,
+    pub tx: u8,
+    pub rx: u8,
+    /// Flow control is turned on for whichever of these are set
+    pub rts: Option<u8>,
+    pub cts: Option<u8>,
+}
+
+impl Default for UartSettings {
+    fn default() -> Self {
+        Self {
+            baud: 115_200,
+            parity: 0,
+            stop_bits: 1,
+            tx: 5,
+            rx: 4,
+            rts: None,
+            cts: None,
+        }
+    }
+}
+
+impl UartSettings {
+    /// Whether these are settings we could actually run with
+    pub fn is_valid(&self) -> bool {
+        (MIN_BAUD..=MAX_BAUD).contains(&self.baud)
+            && self.parity <= 2
+            && matches!(self.stop_bits, 1 | 2)
+            && self.pins_are_valid()
+    }
+
+    /// The first pin which is wired to the flash, if any are
+    pub fn flash_pin(&self) -> Option<u8> {
+        [Some(self.tx), Some(self.rx), self.rts, self.cts]
+            .into_iter()
+            .flatten()
+            .find(|p| FLASH_PINS.contains(p))
+    }
+
+    /// Every pin has to exist, not be used twice and not be a flash pin, and
+    /// tx and rts have to be able to drive their line
+    fn pins_are_valid(&self) -> bool {
+        let outputs = [Some(self.tx), self.rts];
+        let inputs = [Some(self.rx), self.cts];
+        let pins: Vec<u8> = outputs.iter().chain(&inputs).flatten().copied().collect();
+
+        let usable = |p: &u8| *p <= MAX_PIN && !MISSING_PINS.contains(p);
+        let distinct = pins.iter().enumerate().all(|(i, p)| !pins[..i].contains(p));
+
+        pins.iter().all(usable)
+            && self.flash_pin().is_none()
+            && outputs.iter().flatten().all(|p| *p <= MAX_OUTPUT_PIN)
+            && distinct
+    }
+}
+
+impl Serialise for UartSettings {
+    fn to_bytes(self) -> anyhow::Result<Vec<u8>> {
+        let mut v = Vec::with_capacity(10);
+
+        v.extend(self.baud.to_be_bytes());
+        v.extend([
+            self.parity,
+            self.stop_bits,
+            self.tx,
+            self.rx,
+            self.rts.unwrap_or(NO_PIN),
+            self.cts.unwrap_or(NO_PIN),
+        ]);
+
+        Ok(v)
+    }
+}
+
+impl Deserialise for UartSettings {
+    fn from_bytes<R: Read>(src: &mut R) -> anyhow::Result<Self> {
+        let baud = u32::from_be_bytes(src.try_read::<4>()?);
+        let [parity, stop_bits, tx, rx, rts, cts] = src.try_read::<6>()?;
+
+        Ok(Self {
+            baud,
+            parity,
+            stop_bits,
+            tx,
+            rx,
+            rts: (rts != NO_PIN).then_some(rts),
+            cts: (cts != NO_PIN).then_some(cts),
+        })
+    }
+}
@@ -0,0 +1,56 @@
+//! UDP sockets, e.g. for calculators finding each other on the LAN with
+//! broadcasts. They share their handles and error codes with [crate::tcp].
+
+use middlesp_derive::{Deserialise, Serialise};
+
+use crate::tcp::Handle;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum UdpActions {
+    /// Binds a socket to a local port (`0` for any free one), answered with
+    /// [UdpResponse::Bound]. Broadcasts are allowed on every socket.
+    #[wire(id = 0)]
+    Bind(u16),
+    /// Sends one datagram, `host` may be `255.255.255.255` to broadcast
+    #[wire(id = 1)]
+    Send {
+        handle: Handle,
+        host: String,
+        port: u16,
+        data: Vec<u8>,
+    },
+    /// Takes the oldest datagram which has arrived, waiting a little while
+    /// for one if none have
+    #[wire(id = 2)]
+    Recv(Handle),
+    #[wire(id = 3)]
+    Close(Handle),
+}
+
+/// A datagram which arrived, anything past 1472 bytes is cut off
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub struct Datagram {
+    /// The IPv4 address it came from
+    pub addr: [u8; 4],
+    pub port: u16,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum UdpResponse {
+    /// An errno from the socket, or one of the `ERR_` codes in [crate::tcp]
+    #[wire(id = 0)]
+    Error(i32),
+    /// The socket's handle and the local port it is bound to
+    #[wire(id = 1)]
+    Bound { handle: Handle, port: u16 },
+    #[wire(id = 2)]
+    Sent,
+    #[wire(id = 3)]
+    Received(Datagram),
+    /// Nothing arrived in time
+    #[wire(id = 4)]
+    Empty,
+    #[wire(id = 5)]
+    Closed,
+}
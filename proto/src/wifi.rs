@@ -0,0 +1,100 @@
+use middlesp_derive::{Deserialise, Serialise};
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum WifiActions {
+    /// [esp_idf_svc::wifi::AsyncWifi::is_started]
+    #[wire(id = 0)]
+    IsStarted,
+    /// [esp_idf_svc::wifi::AsyncWifi::is_connected]
+    #[wire(id = 1)]
+    IsConnected,
+    /// [esp_idf_svc::wifi::AsyncWifi::get_capabilities]
+    #[wire(id = 2)]
+    GetCapabilities,
+    /// [esp_idf_svc::wifi::AsyncWifi::start]
+    #[wire(id = 3)]
+    Start,
+    /// [esp_idf_svc::wifi::AsyncWifi::stop]
+    #[wire(id = 4)]
+    Stop,
+    /// [esp_idf_svc::wifi::AsyncWifi::scan]
+    #[wire(id = 5)]
+    Scan,
+    /// [esp_idf_svc::wifi::AsyncWifi::connect]
+    #[wire(id = 6)]
+    Connect,
+    /// [esp_idf_svc::wifi::AsyncWifi::disconnect]
+    #[wire(id = 7)]
+    Disconnect,
+    /// [esp_idf_svc::wifi::AsyncWifi::set_configuration]
+    #[wire(id = 8)]
+    SetConfig(WifiConfig),
+}
+
+/// The parts of a client configuration the calculator gets to pick
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialise, Deserialise)]
+pub struct WifiConfig {
+    pub ssid: heapless::String<32>,
+    pub password: heapless::String<64>,
+    /// The least secure auth method we accept
+    pub auth_method: AuthMethod,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialise, Deserialise)]
+pub enum AuthMethod {
+    #[wire(id = 0)]
+    None,
+    #[wire(id = 1)]
+    WEP,
+    #[wire(id = 2)]
+    WPA,
+    #[default]
+    #[wire(id = 3)]
+    WPA2Personal,
+    #[wire(id = 4)]
+    WPAWPA2Personal,
+    #[wire(id = 5)]
+    WPA2Enterprise,
+    #[wire(id = 6)]
+    WPA3Personal,
+    #[wire(id = 7)]
+    WPA2WPA3Personal,
+    #[wire(id = 8)]
+    WAPIPersonal,
+}
+
+/// An access point found by a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub struct AccessPoint {
+    pub ssid: String,
+    pub bssid: [u8; 6],
+    pub channel: u8,
+    pub signal_strength: i8,
+}
+
+/// Bit 0 client, bit 1 access point, bit 2 both at once
+pub type Capabilities = u8;
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum WifiResponse {
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    IsStarted(bool),
+    #[wire(id = 2)]
+    IsConnected(bool),
+    #[wire(id = 3)]
+    AccessPoints(Vec<AccessPoint>),
+    #[wire(id = 4)]
+    Capabilities(Capabilities),
+    #[wire(id = 5)]
+    Started,
+    #[wire(id = 6)]
+    Stopped,
+    #[wire(id = 7)]
+    Connected,
+    #[wire(id = 8)]
+    Disconnected,
+    #[wire(id = 9)]
+    Configured,
+}
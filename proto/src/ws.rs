@@ -0,0 +1,56 @@
+//! WebSocket clients, e.g. for live chat or quizzes without polling a web
+//! server. Messages which arrive are kept on the module until the calculator
+//! asks for them. They share their handles and error codes with [crate::tcp].
+
+use middlesp_derive::{Deserialise, Serialise};
+
+use crate::{http::Headers, tcp::Handle};
+
+/// A message arrived which was too long for the module to keep, so it was
+/// dropped
+pub const ERR_TOO_BIG: i32 = -8;
+
+#[derive(Debug, Clone, Serialise, Deserialise)]
+pub enum WsActions {
+    /// Connects to a `ws://` or `wss://` url, sending the extra headers with
+    /// the upgrade request. Answered with [WsResponse::Connected].
+    #[wire(id = 0)]
+    Connect { url: String, headers: Headers },
+    #[wire(id = 1)]
+    Send(Handle, WsMessage),
+    /// Takes the oldest message which has arrived, waiting a little while for
+    /// one if none have
+    #[wire(id = 2)]
+    Recv(Handle),
+    #[wire(id = 3)]
+    Close(Handle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialise, Deserialise)]
+pub enum WsMessage {
+    #[wire(id = 0)]
+    Text(String),
+    #[wire(id = 1)]
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug, Serialise, Deserialise)]
+pub enum WsResponse {
+    /// An errno from the connection, [ERR_TOO_BIG] or one of the `ERR_` codes
+    /// in [crate::tcp]
+    #[wire(id = 0)]
+    Error(i32),
+    #[wire(id = 1)]
+    Connected(Handle),
+    #[wire(id = 2)]
+    Sent,
+    #[wire(id = 3)]
+    Message(WsMessage),
+    /// Nothing arrived in time
+    #[wire(id = 4)]
+    Empty,
+    /// The connection is closed, either by us or by the server once every
+    /// message it sent has been taken
+    #[wire(id = 5)]
+    Closed,
+}
@@ -0,0 +1,512 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use middlesp_proto::{
+    fetch::{FetchActions, FetchResponse, Line, Page, ERR_BAD_URL, ERR_NOT_TEXT, ERR_NOT_TRUSTED},
+    tcp::ERR_NO_TLS,
+};
+
+use crate::{error_code, tcp, Stream};
+
+/// Most bytes of a page we read, past this it is cut off
+const MAX_PAGE: usize = 8192;
+/// Longest url a Gemini server has to accept
+const MAX_GEMINI_URL: usize = 1024;
+const GEMINI_PORT: u16 = 1965;
+const GOPHER_PORT: u16 = 70;
+
+/// SHA-256 of the certificate a server showed, in DER
+pub type Fingerprint = [u8; 32];
+
+/// Opens a TLS connection to `host:port` without checking the certificate
+/// against any CA, returning the fingerprint of whichever one it showed.
+/// The timeout is for connecting and for each read or write.
+pub type GeminiConnect = fn(&str, u16, Duration) -> io::Result<(Box<dyn Stream>, Fingerprint)>;
+
+/// Where the certificate first seen for each Gemini host is kept
+pub trait CertStore: Send {
+    fn get(&self, host: &str) -> Option<Fingerprint>;
+    fn set(&mut self, host: &str, fingerprint: Fingerprint);
+    /// Returns false if nothing was trusted for the host
+    fn forget(&mut self, host: &str) -> bool;
+}
+
+/// What fetching Gemini and Gopher pages needs
+pub struct Fetcher {
+    /// How long connecting, or a single read or write, may take
+    timeout: Duration,
+    gemini: Option<GeminiConnect>,
+    certs: Box<dyn CertStore>,
+}
+
+impl Fetcher {
+    pub fn new(
+        timeout: Duration,
+        gemini: Option<GeminiConnect>,
+        certs: Box<dyn CertStore>,
+    ) -> Self {
+        Self {
+            timeout,
+            gemini,
+            certs,
+        }
+    }
+}
+
+/// Runs a fetch request, blocking until it is done. The fetcher is only
+/// locked while checking certificates, not while waiting on servers.
+pub fn run_fetch(fetcher: &Mutex<Fetcher>, action: FetchActions) -> FetchResponse {
+    let res = match action {
+        FetchActions::Get { url, input } => match Url::parse(&url) {
+            Some(parsed) if parsed.scheme.eq_ignore_ascii_case("gemini") => {
+                gemini(fetcher, &parsed, &input)
+            }
+            Some(parsed) if parsed.scheme.eq_ignore_ascii_case("gopher") => {
+                gopher(fetcher, &parsed, &input)
+            }
+            _ => Ok(FetchResponse::Error(ERR_BAD_URL)),
+        },
+        FetchActions::Forget(host) => {
+            let host = host.to_ascii_lowercase();
+            if fetcher.lock().unwrap().certs.forget(&host) {
+                Ok(FetchResponse::Forgotten)
+            } else {
+                Ok(FetchResponse::Error(ERR_NOT_TRUSTED))
+            }
+        }
+    };
+
+    res.unwrap_or_else(|e| FetchResponse::Error(error_code(&e)))
+}
+
+fn gemini(fetcher: &Mutex<Fetcher>, url: &Url, input: &str) -> io::Result<FetchResponse> {
+    let url = if input.is_empty() {
+        url.to_string()
+    } else {
+        format!("{}?{}", url.without_query(), percent_encode(input))
+    };
+    if url.len() > MAX_GEMINI_URL {
+        return Ok(FetchResponse::Error(ERR_BAD_URL));
+    }
+    let Some(parsed) = Url::parse(&url) else {
+        return Ok(FetchResponse::Error(ERR_BAD_URL));
+    };
+    let host = parsed.host.to_ascii_lowercase();
+
+    let (timeout, connect) = {
+        let fetcher = fetcher.lock().unwrap();
+        (fetcher.timeout, fetcher.gemini)
+    };
+    let Some(connect) = connect else {
+        return Ok(FetchResponse::Error(ERR_NO_TLS));
+    };
+
+    println!("-> GEMINI {url}");
+    let (mut stream, fingerprint) = connect(&host, parsed.port.unwrap_or(GEMINI_PORT), timeout)?;
+
+    // Trust on first use
+    {
+        let mut fetcher = fetcher.lock().unwrap();
+        match fetcher.certs.get(&host) {
+            Some(known) if known != fingerprint => {
+                println!("Certificate for {host} has changed");
+                return Ok(FetchResponse::CertChanged(host));
+            }
+            Some(_) => {}
+            None => {
+                println!("Trusting the certificate {host} showed");
+                fetcher.certs.set(&host, fingerprint);
+            }
+        }
+    }
+
+    stream.write_all(format!("{url}\r\n").as_bytes())?;
+    stream.flush()?;
+
+    // The header is at most a status, a space and 1024 bytes of meta
+    let (resp, truncated) = read_page(&mut *stream, MAX_PAGE + 1029)?;
+    let Some(end) = resp.iter().position(|&b| b == b'\n') else {
+        return Err(ErrorKind::InvalidData.into());
+    };
+    let header = String::from_utf8_lossy(&resp[..end]);
+    let header = header.trim_end_matches('\r');
+    let (status, meta) = header.split_once(' ').unwrap_or((header, ""));
+    let Some(status) = status.parse::<u8>().ok().filter(|s| (10..70).contains(s)) else {
+        return Err(ErrorKind::InvalidData.into());
+    };
+    let meta = meta.trim().to_string();
+
+    Ok(match status / 10 {
+        1 => FetchResponse::Input {
+            prompt: meta,
+            sensitive: status == 11,
+        },
+        2 => {
+            let body = &resp[end + 1..];
+            let text = String::from_utf8_lossy(&body[..body.len().min(MAX_PAGE)]);
+            let truncated = truncated || body.len() > MAX_PAGE;
+            let mime = meta.to_ascii_lowercase();
+
+            // No mime type means gemtext
+            let lines = if mime.is_empty() || mime.starts_with("text/gemini") {
+                parse_gemtext(&url, &text)
+            } else if mime.starts_with("text/") {
+                text.lines().map(|l| Line::Text(l.to_string())).collect()
+            } else {
+                return Ok(FetchResponse::Error(ERR_NOT_TEXT));
+            };
+
+            FetchResponse::Page(Page { lines, truncated })
+        }
+        3 => FetchResponse::Redirect(resolve(&url, &meta)),
+        _ => FetchResponse::Failed { status, meta },
+    })
+}
+
+fn gopher(fetcher: &Mutex<Fetcher>, url: &Url, input: &str) -> io::Result<FetchResponse> {
+    // The path is the item type followed by the selector, a menu if empty
+    let path = percent_decode(url.path.split_once('?').map_or(url.path, |(p, _)| p));
+    let path = path.strip_prefix('/').unwrap_or(&path);
+    let mut chars = path.chars();
+    let item = chars.next().unwrap_or('1');
+    let mut selector = chars.as_str().to_string();
+    if !input.is_empty() {
+        selector.push('\t');
+        selector.push_str(input);
+    }
+
+    if !matches!(item, '0' | '1' | '7') {
+        return Ok(FetchResponse::Error(ERR_NOT_TEXT));
+    }
+
+    let timeout = fetcher.lock().unwrap().timeout;
+    println!("-> GOPHER {url}");
+    let mut stream = tcp::connect(url.host, url.port.unwrap_or(GOPHER_PORT), timeout)?;
+    stream.write_all(format!("{selector}\r\n").as_bytes())?;
+    stream.flush()?;
+
+    let (body, truncated) = read_page(&mut stream, MAX_PAGE)?;
+    let text = String::from_utf8_lossy(&body);
+
+    let lines = if item == '0' {
+        text.lines()
+            .take_while(|l| *l != ".")
+            .map(|l| Line::Text(l.to_string()))
+            .collect()
+    } else {
+        parse_gophermap(&text)
+    };
+
+    Ok(FetchResponse::Page(Page { lines, truncated }))
+}
+
+/// Reads until the server hangs up or `max` bytes have arrived, returning
+/// whether there was more
+fn read_page(stream: &mut dyn Read, max: usize) -> io::Result<(Vec<u8>, bool)> {
+    let mut page = Vec::new();
+    let mut buf = [0; 512];
+
+    while page.len() <= max {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(size) => page.extend_from_slice(&buf[..size]),
+            // TLS servers often hang up without saying goodbye properly
+            Err(e) if !page.is_empty() && e.kind() != ErrorKind::TimedOut => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    let truncated = page.len() > max;
+    page.truncate(max);
+
+    Ok((page, truncated))
+}
+
+/// Splits a `text/gemini` page into lines, with link targets resolved
+/// against the page's url
+pub fn parse_gemtext(base: &str, text: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut preformatted = false;
+
+    for line in text.lines() {
+        // Whatever follows the backticks is alt text, which we have no use for
+        if line.starts_with("```") {
+            preformatted = !preformatted;
+            continue;
+        }
+        if preformatted {
+            lines.push(Line::Preformatted(line.to_string()));
+            continue;
+        }
+
+        let line = if let Some(link) = line.strip_prefix("=>") {
+            let link = link.trim();
+            let (target, label) = link
+                .split_once(char::is_whitespace)
+                .map_or((link, ""), |(t, l)| (t, l.trim()));
+            if target.is_empty() {
+                continue;
+            }
+
+            Line::Link {
+                target: resolve(base, target),
+                label: if label.is_empty() { target } else { label }.to_string(),
+            }
+        } else if let Some(text) = line.strip_prefix("###") {
+            heading(3, text)
+        } else if let Some(text) = line.strip_prefix("##") {
+            heading(2, text)
+        } else if let Some(text) = line.strip_prefix('#') {
+            heading(1, text)
+        } else if let Some(item) = line.strip_prefix("* ") {
+            Line::ListItem(item.to_string())
+        } else if let Some(quote) = line.strip_prefix('>') {
+            Line::Quote(quote.trim_start().to_string())
+        } else {
+            Line::Text(line.to_string())
+        };
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn heading(level: u8, text: &str) -> Line {
+    Line::Heading {
+        level,
+        text: text.trim().to_string(),
+    }
+}
+
+/// Splits a Gopher menu into lines, with each item turned into a url
+pub fn parse_gophermap(text: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        if line == "." {
+            break;
+        }
+
+        let mut chars = line.chars();
+        let item = chars.next().unwrap_or('i');
+        let mut fields = chars.as_str().split('\t');
+        let label = fields.next().unwrap_or_default().to_string();
+        let (Some(selector), Some(host), Some(port)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            lines.push(Line::Text(label));
+            continue;
+        };
+        let port = port.trim().parse().unwrap_or(GOPHER_PORT);
+
+        lines.push(match item {
+            'i' | '3' => Line::Text(label),
+            'h' if selector.starts_with("URL:") => Line::Link {
+                target: selector[4..].to_string(),
+                label,
+            },
+            '7' => Line::Search {
+                target: gopher_url(host, port, item, selector),
+                label,
+            },
+            '8' | 'T' => Line::Link {
+                target: format!("telnet://{host}:{port}"),
+                label,
+            },
+            _ => Line::Link {
+                target: gopher_url(host, port, item, selector),
+                label,
+            },
+        });
+    }
+
+    lines
+}
+
+fn gopher_url(host: &str, port: u16, item: char, selector: &str) -> String {
+    let port = if port == GOPHER_PORT {
+        String::new()
+    } else {
+        format!(":{port}")
+    };
+
+    format!(
+        "gopher://{host}{port}/{item}{}",
+        percent_encode_path(selector)
+    )
+}
+
+/// The pieces of an absolute url we care about
+struct Url<'a> {
+    scheme: &'a str,
+    host: &'a str,
+    port: Option<u16>,
+    /// Everything after the host, including any query but not the fragment
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> Option<Self> {
+        let url = url.split_once('#').map_or(url, |(u, _)| u);
+        let (scheme, rest) = url.split_once("://")?;
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(end);
+        // Credentials have no place in either protocol
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (authority, None),
+        };
+
+        if !is_scheme(scheme) || host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            scheme,
+            host,
+            port,
+            path,
+        })
+    }
+
+    fn origin(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}://{}:{port}", self.scheme, self.host),
+            None => format!("{}://{}", self.scheme, self.host),
+        }
+    }
+
+    fn without_query(&self) -> String {
+        let path = self.path.split_once('?').map_or(self.path, |(p, _)| p);
+        let path = if path.is_empty() { "/" } else { path };
+
+        format!("{}{path}", self.origin())
+    }
+}
+
+impl std::fmt::Display for Url<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { self.path };
+        write!(f, "{}{path}", self.origin())
+    }
+}
+
+fn is_scheme(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Turns a link on the page at `base` into a full url
+pub fn resolve(base: &str, target: &str) -> String {
+    if target
+        .split_once(':')
+        .is_some_and(|(scheme, _)| is_scheme(scheme))
+    {
+        return target.to_string();
+    }
+    let Some(base) = Url::parse(base) else {
+        return target.to_string();
+    };
+
+    if let Some(rest) = target.strip_prefix("//") {
+        return format!("{}://{rest}", base.scheme);
+    }
+
+    let base_path = base.path.split_once('?').map_or(base.path, |(p, _)| p);
+    let path = if target.starts_with('/') {
+        target.to_string()
+    } else if target.starts_with('?') || target.is_empty() {
+        format!("{base_path}{target}")
+    } else {
+        // Relative to the directory the page is in
+        let dir = base_path.rfind('/').map_or("/", |i| &base_path[..=i]);
+        format!("{dir}{target}")
+    };
+
+    format!("{}{}", base.origin(), remove_dot_segments(&path))
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+    let segments: Vec<_> = path.split('/').skip(1).collect();
+    let mut out = Vec::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        match *segment {
+            "." => {}
+            ".." => {
+                out.pop();
+            }
+            segment => {
+                out.push(segment);
+                continue;
+            }
+        }
+        // `a/..` still names a directory
+        if last {
+            out.push("");
+        }
+    }
+
+    let mut res = format!("/{}", out.join("/"));
+    if let Some(query) = query {
+        res.push('?');
+        res.push_str(query);
+    }
+
+    res
+}
+
+fn percent_encode(s: &str) -> String {
+    encode(s, |b| b.is_ascii_alphanumeric() || b"-._~".contains(&b))
+}
+
+/// Like [percent_encode] but leaves slashes alone
+fn percent_encode_path(s: &str) -> String {
+    encode(s, |b| b.is_ascii_alphanumeric() || b"-._~/".contains(&b))
+}
+
+fn encode(s: &str, keep: impl Fn(u8) -> bool) -> String {
+    let mut res = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if keep(b) {
+            res.push(b as char);
+        } else {
+            res.push_str(&format!("%{b:02X}"));
+        }
+    }
+
+    res
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = s
+            .get(i + 1..i + 3)
+            .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                res.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                res.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&res).into_owned()
+}
@@ -0,0 +1,473 @@
+use crate::fetch::resolve;
+
+/// Ends of the elements whose content is never shown
+const HIDDEN: [&str; 6] = [
+    "</script",
+    "</style",
+    "</noscript",
+    "</template",
+    "</svg",
+    "</title",
+];
+/// Elements which start on a line of their own
+const BLOCKS: [&str; 20] = [
+    "address", "article", "aside", "dd", "div", "dl", "dt", "fieldset", "figure", "footer", "form",
+    "header", "main", "nav", "section", "table", "tbody", "thead", "tr", "caption",
+];
+/// Longest tag we wait for the end of, anything longer is dropped
+const MAX_TAG: usize = 2048;
+/// Longest entity we wait for the end of, e.g. `&#x1F600;`
+const MAX_ENTITY: usize = 10;
+
+/// Turns an HTML page into plain text as it arrives, so only the text has to
+/// be kept rather than the whole page. Scripts and styles are dropped,
+/// headings, paragraphs and lists are kept on lines of their own, and each
+/// link is numbered (e.g. `Home [1]`) with its full url kept apart.
+pub struct HtmlText {
+    /// Url of the page, which links are resolved against
+    base: String,
+    max: usize,
+    text: String,
+    links: Vec<String>,
+    /// Bytes which can not be handled until more arrive, such as half a tag
+    pending: Vec<u8>,
+    /// What ends the hidden element or comment we are in, which everything
+    /// is dropped until
+    hidden: Option<&'static str>,
+    /// Target of the link we are in, numbered once the link ends
+    href: Option<String>,
+    /// How many `pre` elements we are in, whose whitespace is kept
+    pre: usize,
+    /// The lists we are in, with the number of the last item for ordered ones
+    lists: Vec<Option<u32>>,
+    full: bool,
+}
+
+impl HtmlText {
+    /// Keeps at most `max` bytes of text
+    pub fn new(base: &str, max: usize) -> Self {
+        Self {
+            base: base.to_string(),
+            max,
+            text: String::new(),
+            links: Vec::new(),
+            pending: Vec::new(),
+            hidden: None,
+            href: None,
+            pre: 0,
+            lists: Vec::new(),
+            full: false,
+        }
+    }
+
+    pub fn feed(&mut self, html: &[u8]) {
+        if self.full {
+            return;
+        }
+
+        self.pending.extend_from_slice(html);
+        self.process(false);
+    }
+
+    /// Whether there is as much text as we keep, so the rest of the page need
+    /// not be read
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// The text, and the full url of each numbered link in order
+    pub fn finish(mut self) -> (String, Vec<String>) {
+        self.process(true);
+        self.end_link();
+
+        let len = self.text.trim_end().len();
+        self.text.truncate(len);
+
+        (self.text, self.links)
+    }
+
+    /// Handles as much of what is pending as we can, or all of it at the end
+    /// of the page
+    fn process(&mut self, last: bool) {
+        let buf = std::mem::take(&mut self.pending);
+        let mut i = 0;
+
+        while i < buf.len() && !self.full {
+            let rest = &buf[i..];
+
+            if let Some(end) = self.hidden {
+                match find(rest, end.as_bytes()) {
+                    // The closing tag itself is handled below, a comment has
+                    // nothing more to it
+                    Some(at) if end == "-->" => i += at + end.len(),
+                    Some(at) => i += at,
+                    None if last => i = buf.len(),
+                    None => {
+                        // Keep back what could be the start of the end
+                        i += rest.len().saturating_sub(end.len());
+                        break;
+                    }
+                }
+                self.hidden = None;
+                continue;
+            }
+
+            if rest[0] == b'<' {
+                if rest.starts_with(b"<!--") {
+                    self.hidden = Some("-->");
+                    i += 4;
+                    continue;
+                }
+                match rest.get(1) {
+                    Some(b) if b.is_ascii_alphabetic() || b"/!?".contains(b) => {}
+                    // A lone `<` is just text
+                    Some(_) => {
+                        self.push_text("<");
+                        i += 1;
+                        continue;
+                    }
+                    None => break,
+                }
+
+                match tag_end(rest) {
+                    Some(end) => {
+                        self.tag(&String::from_utf8_lossy(&rest[1..end]));
+                        i += end + 1;
+                    }
+                    None if last || rest.len() > MAX_TAG => i = buf.len(),
+                    None => break,
+                }
+                continue;
+            }
+
+            let end = rest.iter().position(|&b| b == b'<').unwrap_or(rest.len());
+            let text = if end == rest.len() && !last {
+                &rest[..complete_len(rest)]
+            } else {
+                &rest[..end]
+            };
+            if text.is_empty() {
+                break;
+            }
+
+            self.push_text(&decode_entities(&String::from_utf8_lossy(text)));
+            i += text.len();
+        }
+
+        if !self.full {
+            self.pending = buf[i..].to_vec();
+        }
+    }
+
+    /// Handles a tag, given what is between the `<` and `>`
+    fn tag(&mut self, tag: &str) {
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = tag[..end].to_ascii_lowercase();
+        let attrs = &tag[end..];
+
+        // `<svg/>` has nothing to hide
+        if !closing && !attrs.trim_end().ends_with('/') {
+            if let Some(end) = HIDDEN.iter().find(|end| end[2..] == name) {
+                self.hidden = Some(end);
+                return;
+            }
+        }
+
+        match (name.as_str(), closing) {
+            ("br", _) => {
+                self.trim_spaces();
+                if !self.text.ends_with("\n\n") {
+                    self.push("\n");
+                }
+            }
+            ("p" | "blockquote", _) => self.block(2),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block(2);
+                // Past three levels are all much the same size
+                let level = (name.as_bytes()[1] - b'0').min(3) as usize;
+                self.push(&format!("{} ", "#".repeat(level)));
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.block(2),
+            ("ul", false) => {
+                self.block(1);
+                self.lists.push(None);
+            }
+            ("ol", false) => {
+                self.block(1);
+                let start = attr(attrs, "start").and_then(|s| s.trim().parse().ok());
+                self.lists
+                    .push(Some(start.unwrap_or(1u32).saturating_sub(1)));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.block(1);
+            }
+            ("li", false) => {
+                self.block(1);
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{n}.")
+                    }
+                    _ => "*".to_string(),
+                };
+                self.push(&format!("{indent}{marker} "));
+            }
+            ("li", true) => self.block(1),
+            ("pre", false) => {
+                self.block(1);
+                self.pre += 1;
+            }
+            ("pre", true) => {
+                self.pre = self.pre.saturating_sub(1);
+                self.block(1);
+            }
+            ("hr", _) => {
+                self.block(1);
+                self.push("----");
+                self.block(1);
+            }
+            ("td" | "th", false) => self.push_text(" "),
+            ("a", false) => {
+                self.end_link();
+                self.href = attr(attrs, "href")
+                    .map(|href| decode_entities(href.trim()))
+                    .filter(|href| {
+                        !href.is_empty()
+                            && !href.starts_with('#')
+                            && !href.to_ascii_lowercase().starts_with("javascript:")
+                    })
+                    .map(|href| resolve(&self.base, &href));
+            }
+            ("a", true) => self.end_link(),
+            (name, _) if BLOCKS.contains(&name) => self.block(1),
+            _ => {}
+        }
+    }
+
+    /// Numbers the link we are in, if any
+    fn end_link(&mut self) {
+        let Some(href) = self.href.take() else {
+            return;
+        };
+        if self.full {
+            return;
+        }
+
+        self.links.push(href);
+        self.trim_spaces();
+        self.push(&format!(" [{}]", self.links.len()));
+    }
+
+    /// Adds text from the page, with runs of whitespace turned into a single
+    /// space outside of `pre`
+    fn push_text(&mut self, text: &str) {
+        let mut out = String::with_capacity(text.len());
+        let mut last = self.text.chars().last();
+
+        for c in text.chars() {
+            let c = match c {
+                '\r' => continue,
+                c if self.pre > 0 => c,
+                c if c.is_whitespace() => {
+                    if matches!(last, None | Some(' ' | '\n')) {
+                        continue;
+                    }
+                    ' '
+                }
+                c => c,
+            };
+            out.push(c);
+            last = Some(c);
+        }
+
+        self.push(&out);
+    }
+
+    /// Starts a new line, leaving `lines - 1` blank lines before it
+    fn block(&mut self, lines: usize) {
+        self.trim_spaces();
+        if self.text.is_empty() {
+            return;
+        }
+
+        let have = self.text.len() - self.text.trim_end_matches('\n').len();
+        for _ in have..lines {
+            self.push("\n");
+        }
+    }
+
+    fn trim_spaces(&mut self) {
+        let len = self.text.trim_end_matches(' ').len();
+        self.text.truncate(len);
+    }
+
+    fn push(&mut self, s: &str) {
+        if self.full {
+            return;
+        }
+        if self.text.len() + s.len() <= self.max {
+            self.text.push_str(s);
+            return;
+        }
+
+        let mut end = self.max - self.text.len();
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.text.push_str(&s[..end]);
+        self.full = true;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle))
+}
+
+/// Where the `>` ending the tag at the start of `html` is, skipping over any
+/// in quoted attributes
+fn tag_end(html: &[u8]) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, &b) in html.iter().enumerate().skip(1) {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'>') => return Some(i),
+            (Some(q), b) if q == b => quote = None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The value of an attribute, given everything after the tag's name
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let key = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, left) = match after.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let after = &after[1..];
+                        let end = after.find(q).unwrap_or(after.len());
+                        (&after[..end], after.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        after.split_at(end)
+                    }
+                };
+                rest = left;
+                value
+            }
+            None => "",
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+    }
+}
+
+/// How much of some text can be handled now, leaving any entity or character
+/// cut off at the end for when the rest arrives
+fn complete_len(text: &[u8]) -> usize {
+    let mut end = text.len();
+    if let Some(amp) = text.iter().rposition(|&b| b == b'&') {
+        if end - amp < MAX_ENTITY && !text[amp..].contains(&b';') {
+            end = amp;
+        }
+    }
+
+    match std::str::from_utf8(&text[..end]) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => end,
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest
+            .get(1..MAX_ENTITY.min(rest.len()))
+            .and_then(|s| s.split_once(';'))
+            .and_then(|(name, _)| Some((entity(name)?, name.len() + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        // Kept as a plain space, a calculator has no use for the difference
+        "nbsp" => ' ',
+        "copy" => '©',
+        "reg" => '®',
+        "deg" => '°',
+        "times" => '×',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        _ => return None,
+    })
+}
@@ -0,0 +1,483 @@
+use middlesp_proto::http::{JsonValue, Number};
+
+/// Longest string kept, anything past this is cut off
+const MAX_STRING: usize = 1024;
+/// Most numbers kept from an array, anything past this is dropped
+const MAX_NUMBERS: usize = 256;
+/// Deepest nesting we follow, past which the document is given up on
+const MAX_DEPTH: usize = 64;
+/// Most significant digits an `i32` mantissa always has room for
+const DIGITS: usize = 9;
+
+/// One step of a selector, a key for objects or an index for arrays. A JSON
+/// pointer's `/0` could be either, so it has both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    key: Option<String>,
+    index: Option<usize>,
+}
+
+/// Where the parser is in the document
+enum Frame {
+    /// `key` is `None` until the key arrives, or if it was too long to keep
+    Object {
+        key: Option<String>,
+        want_key: bool,
+    },
+    Array {
+        index: usize,
+    },
+}
+
+/// The token being read
+enum Lex {
+    Between,
+    Str {
+        escaped: bool,
+    },
+    /// A number, `true`, `false` or `null`
+    Bare,
+}
+
+/// An array being collected for a selector
+struct Capture {
+    selector: usize,
+    /// How deep the array's items are
+    depth: usize,
+    numbers: Vec<Number>,
+    /// Whether everything in it so far was a number
+    numbers_only: bool,
+}
+
+/// Picks values out of a JSON document as it arrives, so only the values
+/// have to be kept rather than the whole document. Selectors are JSON
+/// pointers (`/current/temp`) or JSONPath without wildcards or filters
+/// (`$.current.temp`, `$.hourly['temp'][0]`).
+pub struct JsonSelect {
+    selectors: Vec<Vec<Step>>,
+    values: Vec<Option<JsonValue>>,
+    stack: Vec<Frame>,
+    captures: Vec<Capture>,
+    lex: Lex,
+    token: Vec<u8>,
+    /// Whether the token was longer than we keep
+    long: bool,
+    failed: bool,
+}
+
+impl JsonSelect {
+    /// `None` if there are no selectors or any of them is not one we can use
+    pub fn new(selectors: &[String]) -> Option<Self> {
+        if selectors.is_empty() {
+            return None;
+        }
+        let selectors = selectors
+            .iter()
+            .map(|s| parse_selector(s))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            values: vec![None; selectors.len()],
+            selectors,
+            stack: Vec::new(),
+            captures: Vec::new(),
+            lex: Lex::Between,
+            token: Vec::new(),
+            long: false,
+            failed: false,
+        })
+    }
+
+    pub fn feed(&mut self, json: &[u8]) {
+        for &b in json {
+            if self.is_done() {
+                return;
+            }
+            self.byte(b);
+        }
+    }
+
+    /// Whether every selector has its value (or the document is broken), so
+    /// the rest need not be read
+    pub fn is_done(&self) -> bool {
+        self.failed || self.values.iter().all(Option::is_some)
+    }
+
+    /// The value for each selector in order, [JsonValue::Missing] for any
+    /// which matched nothing
+    pub fn finish(mut self) -> Vec<JsonValue> {
+        if matches!(self.lex, Lex::Bare) {
+            self.bare();
+        }
+
+        self.values
+            .into_iter()
+            .map(|v| v.unwrap_or(JsonValue::Missing))
+            .collect()
+    }
+
+    fn byte(&mut self, b: u8) {
+        match self.lex {
+            Lex::Str { escaped: true } => {
+                self.push(b);
+                self.lex = Lex::Str { escaped: false };
+            }
+            Lex::Str { escaped: false } => match b {
+                b'"' => {
+                    self.lex = Lex::Between;
+                    let s = unescape(&self.token);
+                    self.string(s);
+                }
+                b'\\' => {
+                    self.push(b);
+                    self.lex = Lex::Str { escaped: true };
+                }
+                b => self.push(b),
+            },
+            Lex::Bare if b.is_ascii_alphanumeric() || b"+-.".contains(&b) => self.push(b),
+            Lex::Bare => {
+                self.bare();
+                self.lex = Lex::Between;
+                self.byte(b);
+            }
+            Lex::Between => match b {
+                b'"' => self.start(Lex::Str { escaped: false }, None),
+                b'-' | b'0'..=b'9' | b'a'..=b'z' => self.start(Lex::Bare, Some(b)),
+                b'{' => {
+                    self.value(Start::Object);
+                    self.enter(Frame::Object {
+                        key: None,
+                        want_key: true,
+                    });
+                }
+                b'[' => {
+                    self.value(Start::Array);
+                    self.enter(Frame::Array { index: 0 });
+                }
+                b'}' | b']' => self.leave(),
+                b',' => match self.stack.last_mut() {
+                    Some(Frame::Object { key, want_key }) => {
+                        *key = None;
+                        *want_key = true;
+                    }
+                    Some(Frame::Array { index }) => *index += 1,
+                    None => {}
+                },
+                // Colons and whitespace need no more than skipping over
+                _ => {}
+            },
+        }
+    }
+
+    fn start(&mut self, lex: Lex, first: Option<u8>) {
+        self.lex = lex;
+        self.token.clear();
+        self.long = false;
+        self.token.extend(first);
+    }
+
+    fn push(&mut self, b: u8) {
+        if self.token.len() < MAX_STRING {
+            self.token.push(b);
+        } else {
+            self.long = true;
+        }
+    }
+
+    fn string(&mut self, s: String) {
+        if let Some(Frame::Object { key, want_key }) = self.stack.last_mut() {
+            if *want_key {
+                *want_key = false;
+                // A cut off key must not match a selector for a shorter one
+                *key = (!self.long).then_some(s);
+                return;
+            }
+        }
+
+        self.value(Start::Scalar(JsonValue::String(s)));
+    }
+
+    /// Ends a number, `true`, `false` or `null`
+    fn bare(&mut self) {
+        let value = match &self.token[..] {
+            b"true" => JsonValue::Bool(true),
+            b"false" => JsonValue::Bool(false),
+            b"null" => JsonValue::Null,
+            token => std::str::from_utf8(token)
+                .ok()
+                .and_then(parse_number)
+                .map_or(JsonValue::Other, JsonValue::Number),
+        };
+
+        self.value(Start::Scalar(value));
+    }
+
+    fn enter(&mut self, frame: Frame) {
+        if self.stack.len() >= MAX_DEPTH {
+            self.failed = true;
+            return;
+        }
+        self.stack.push(frame);
+    }
+
+    /// Ends an object or array
+    fn leave(&mut self) {
+        let depth = self.stack.len();
+        self.stack.pop();
+
+        // More than one selector can pick out the same array
+        while let Some(i) = self.captures.iter().position(|c| c.depth == depth) {
+            let capture = self.captures.remove(i);
+            self.values[capture.selector] = Some(if capture.numbers_only {
+                JsonValue::Numbers(capture.numbers)
+            } else {
+                JsonValue::Other
+            });
+        }
+    }
+
+    /// Starts a value at the current position in the document
+    fn value(&mut self, start: Start) {
+        let depth = self.stack.len();
+        for capture in &mut self.captures {
+            if capture.depth != depth {
+                continue;
+            }
+            match &start {
+                Start::Scalar(JsonValue::Number(n)) if capture.numbers.len() < MAX_NUMBERS => {
+                    capture.numbers.push(*n)
+                }
+                Start::Scalar(JsonValue::Number(_)) => {}
+                _ => capture.numbers_only = false,
+            }
+        }
+
+        for i in 0..self.selectors.len() {
+            let capturing = self.captures.iter().any(|c| c.selector == i);
+            // Only the first match counts, should a key be repeated
+            if self.values[i].is_some() || capturing || !self.at(&self.selectors[i]) {
+                continue;
+            }
+
+            match &start {
+                Start::Scalar(value) => self.values[i] = Some(value.clone()),
+                Start::Array => self.captures.push(Capture {
+                    selector: i,
+                    depth: depth + 1,
+                    numbers: Vec::new(),
+                    numbers_only: true,
+                }),
+                Start::Object => self.values[i] = Some(JsonValue::Other),
+            }
+        }
+    }
+
+    /// Whether the current position is the one a selector picks out
+    fn at(&self, selector: &[Step]) -> bool {
+        selector.len() == self.stack.len()
+            && selector
+                .iter()
+                .zip(&self.stack)
+                .all(|(step, frame)| match frame {
+                    Frame::Object { key, .. } => key.is_some() && step.key == *key,
+                    Frame::Array { index } => step.index == Some(*index),
+                })
+    }
+}
+
+enum Start {
+    Scalar(JsonValue),
+    Object,
+    Array,
+}
+
+fn parse_selector(s: &str) -> Option<Vec<Step>> {
+    if s.is_empty() || s.starts_with('/') {
+        return Some(parse_pointer(s));
+    }
+
+    let mut rest = s.strip_prefix('$')?;
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return None;
+            }
+            steps.push(key(&after[..end]));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (step, left) = match after.chars().next()? {
+                q @ ('\'' | '"') => {
+                    let end = after[1..].find(q)? + 1;
+                    (key(&after[1..end]), after[end + 1..].strip_prefix(']')?)
+                }
+                _ => {
+                    let (index, left) = after.split_once(']')?;
+                    (
+                        Step {
+                            key: None,
+                            index: Some(index.trim().parse().ok()?),
+                        },
+                        left,
+                    )
+                }
+            };
+            steps.push(step);
+            rest = left;
+        } else {
+            return None;
+        }
+    }
+
+    Some(steps)
+}
+
+/// RFC 6901, where `~1` stands for `/` and `~0` for `~`
+fn parse_pointer(s: &str) -> Vec<Step> {
+    s.split('/')
+        .skip(1)
+        .map(|token| {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            let index = match token.as_bytes() {
+                [b'0'] => Some(0),
+                [b'1'..=b'9', ..] => token.parse().ok(),
+                _ => None,
+            };
+
+            Step {
+                key: Some(token),
+                index,
+            }
+        })
+        .collect()
+}
+
+fn key(name: &str) -> Step {
+    Step {
+        key: Some(name.to_string()),
+        index: None,
+    }
+}
+
+/// Decodes a string's escapes, given everything between the quotes
+fn unescape(raw: &[u8]) -> String {
+    let raw = String::from_utf8_lossy(raw);
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('u') => {
+                let mut code = hex4(&mut chars);
+                // Characters past the first plane come as surrogate pairs
+                if let Some(high @ 0xD800..=0xDBFF) = code {
+                    let rest = chars.as_str();
+                    if let Some(low @ 0xDC00..=0xDFFF) =
+                        rest.strip_prefix("\\u").and_then(|r| hex4(&mut r.chars()))
+                    {
+                        code = Some(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+                        chars = rest[6..].chars();
+                    }
+                }
+                out.push(code.and_then(char::from_u32).unwrap_or('\u{FFFD}'));
+            }
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+
+    out
+}
+
+fn hex4(chars: &mut std::str::Chars) -> Option<u32> {
+    let hex: String = chars.take(4).collect();
+    if hex.len() != 4 {
+        return None;
+    }
+
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+/// Turns a JSON number into a [Number], rounding to the 9 significant
+/// digits an `i32` mantissa holds. `None` if it is not a number, or too big
+/// to send.
+fn parse_number(s: &str) -> Option<Number> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (s, exponent) = match s.split_once(['e', 'E']) {
+        Some((s, e)) => {
+            let e = e.strip_prefix('+').unwrap_or(e);
+            let e = match e.parse::<i64>() {
+                Ok(e) => e,
+                // Only fails for huge exponents, given it is all digits
+                Err(_)
+                    if e.trim_start_matches('-')
+                        .bytes()
+                        .all(|b| b.is_ascii_digit()) =>
+                {
+                    if e.starts_with('-') {
+                        i64::MIN / 2
+                    } else {
+                        i64::MAX / 2
+                    }
+                }
+                Err(_) => return None,
+            };
+            (s, e)
+        }
+        None => (s, 0),
+    };
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    if int.is_empty() || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = format!("{int}{frac}");
+    let digits = digits.trim_start_matches('0');
+    let mut exponent = exponent - frac.len() as i64;
+    if digits.is_empty() {
+        return Some(Number {
+            mantissa: 0,
+            exponent: 0,
+        });
+    }
+
+    let kept = digits.len().min(DIGITS);
+    exponent += (digits.len() - kept) as i64;
+    let mut mantissa: i64 = digits[..kept].parse().ok()?;
+    if digits.as_bytes().get(kept).is_some_and(|&d| d >= b'5') {
+        mantissa += 1;
+    }
+
+    while mantissa % 10 == 0 {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    // Far too small to matter
+    while exponent < i8::MIN as i64 && mantissa != 0 {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    if mantissa == 0 {
+        exponent = 0;
+    }
+
+    Some(Number {
+        mantissa: (if negative { -mantissa } else { mantissa }) as i32,
+        exponent: exponent.try_into().ok()?,
+    })
+}
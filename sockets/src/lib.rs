@@ -0,0 +1,271 @@
+//! The sockets the calculator opens through the module. Plain sockets only
+//! need std, so this builds (and is tested) on the PC as well as the module,
+//! with TLS, WebSockets, MQTT and event streams handed in by the firmware.
+//! Gemini and Gopher pages are fetched here too, as both are little more than
+//! a line sent down a socket, and HTML pages are turned into text (and JSON
+//! documents picked apart) here so that can be tested on the PC too.
+
+pub use fetch::{
+    parse_gemtext, parse_gophermap, resolve, run_fetch, CertStore, Fetcher, Fingerprint,
+    GeminiConnect,
+};
+pub use html::HtmlText;
+pub use json::JsonSelect;
+pub use mqtt::{run_mqtt, topic_matches, MqttClient, MqttConnect, Subscriptions};
+pub use sse::{run_sse, EventStream, SseConnect, SseParser};
+pub use tcp::{run_tcp, Stream, TlsConnect};
+pub use udp::run_udp;
+pub use ws::{run_ws, Inbox, Opcode, WsConnect, WsSender};
+
+mod fetch;
+mod html;
+mod json;
+mod mqtt;
+mod sse;
+mod tcp;
+mod udp;
+mod ws;
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use middlesp_proto::tcp::{Handle, TcpResponse, ERR_OTHER};
+use mqtt::Mqtt;
+use sse::Sse;
+use ws::WebSocket;
+
+/// Stack for threads which block in http (and TLS), which need quite a bit
+/// more than the default pthread stack
+pub const STACK_SIZE: usize = 12 * 1024;
+
+/// A TCP stream, shared between the table and whichever requests are using it
+type SharedStream = Arc<Mutex<Box<dyn Stream>>>;
+
+enum Socket {
+    Tcp(SharedStream),
+    /// Only ever polled without blocking, so used with the table locked
+    Listener {
+        listener: TcpListener,
+        /// Id of the request which opened it, which new connections are
+        /// announced with
+        id: u8,
+    },
+    /// Sending and receiving only need `&self`, so no lock is needed
+    Udp(Arc<UdpSocket>),
+    Ws(Arc<WebSocket>),
+    Mqtt(Arc<Mqtt>),
+    /// Read by a thread of its own, which stops once this is dropped
+    Sse(Arc<Sse>),
+}
+
+impl Socket {
+    /// Whether TCP requests can close this
+    fn is_tcp(&self) -> bool {
+        matches!(self, Self::Tcp(_) | Self::Listener { .. })
+    }
+
+    fn is_udp(&self) -> bool {
+        matches!(self, Self::Udp(_))
+    }
+
+    fn is_ws(&self) -> bool {
+        matches!(self, Self::Ws(_))
+    }
+
+    fn is_mqtt(&self) -> bool {
+        matches!(self, Self::Mqtt(_))
+    }
+
+    fn is_sse(&self) -> bool {
+        matches!(self, Self::Sse(_))
+    }
+}
+
+/// Every socket the calculator has open, each known by a [Handle]. TCP, UDP,
+/// WebSocket and MQTT connections, and event streams, share the handles and
+/// the limit.
+pub struct Sockets {
+    open: Vec<(Handle, Socket)>,
+    next: Handle,
+    max: usize,
+    /// How long connecting, or a single read or write, may take
+    timeout: Duration,
+    connectors: Connectors,
+}
+
+/// Opens the connections which need more than std, any left as `None` are
+/// answered with [ERR_NO_TLS](middlesp_proto::tcp::ERR_NO_TLS) or
+/// [ERR_OTHER]
+#[derive(Clone, Copy, Default)]
+pub struct Connectors {
+    pub tls: Option<TlsConnect>,
+    pub ws: Option<WsConnect>,
+    pub mqtt: Option<MqttConnect>,
+    pub sse: Option<SseConnect>,
+}
+
+impl Sockets {
+    pub fn new(max: usize, timeout: Duration, connectors: Connectors) -> Self {
+        Self {
+            open: Vec::new(),
+            next: 0,
+            max,
+            timeout,
+            connectors,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.open.len() >= self.max
+    }
+
+    /// Adds a socket, returning `None` if there are already as many open as
+    /// we allow
+    fn insert(&mut self, socket: Socket) -> Option<Handle> {
+        if self.is_full() {
+            return None;
+        }
+
+        // Hand out handles in turn so a stale one is unlikely to pick out a
+        // newer socket
+        let mut handle = self.next;
+        while self.open.iter().any(|(h, _)| *h == handle) {
+            handle = handle.wrapping_add(1);
+        }
+        self.next = handle.wrapping_add(1);
+
+        self.open.push((handle, socket));
+        Some(handle)
+    }
+
+    fn get(&self, handle: Handle) -> Option<&Socket> {
+        self.open.iter().find(|(h, _)| *h == handle).map(|(_, s)| s)
+    }
+
+    /// The TCP socket with the given handle, which is only locked while it is
+    /// in use so the others are free in the meantime
+    fn tcp(&self, handle: Handle) -> Option<SharedStream> {
+        match self.get(handle)? {
+            Socket::Tcp(stream) => Some(stream.clone()),
+            _ => None,
+        }
+    }
+
+    fn udp(&self, handle: Handle) -> Option<Arc<UdpSocket>> {
+        match self.get(handle)? {
+            Socket::Udp(socket) => Some(socket.clone()),
+            _ => None,
+        }
+    }
+
+    fn ws(&self, handle: Handle) -> Option<Arc<WebSocket>> {
+        match self.get(handle)? {
+            Socket::Ws(ws) => Some(ws.clone()),
+            _ => None,
+        }
+    }
+
+    fn mqtt(&self, handle: Handle) -> Option<Arc<Mqtt>> {
+        match self.get(handle)? {
+            Socket::Mqtt(mqtt) => Some(mqtt.clone()),
+            _ => None,
+        }
+    }
+
+    fn sse(&self, handle: Handle) -> Option<Arc<Sse>> {
+        match self.get(handle)? {
+            Socket::Sse(sse) => Some(sse.clone()),
+            _ => None,
+        }
+    }
+
+    /// Forgets a socket if it is of the right kind, which is closed once
+    /// nothing is using it. Returns false if there was no such socket.
+    fn remove(&mut self, handle: Handle, is_kind: fn(&Socket) -> bool) -> bool {
+        let Some(i) = self
+            .open
+            .iter()
+            .position(|(h, s)| *h == handle && is_kind(s))
+        else {
+            return false;
+        };
+
+        self.open.remove(i);
+        true
+    }
+
+    /// Takes on any clients waiting to connect to a listening socket, giving
+    /// each a handle. Returns what to tell the calculator about them, along
+    /// with the id of the request which started the listening. Never blocks,
+    /// so it can be called every time round the main loop.
+    pub fn accept(&mut self) -> Vec<(u8, TcpResponse)> {
+        let mut waiting = Vec::new();
+        for (handle, socket) in &self.open {
+            let Socket::Listener { listener, id } = socket else {
+                continue;
+            };
+
+            loop {
+                match listener.accept() {
+                    Ok((stream, addr)) => waiting.push((*handle, *id, stream, addr)),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        println!("Failed to accept on socket {handle}: {e:?}");
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut accepted = Vec::new();
+        for (listener, id, stream, addr) in waiting {
+            let SocketAddr::V4(addr) = addr else {
+                continue;
+            };
+            if let Err(e) = tcp::configure(&stream, self.timeout) {
+                println!("Failed to set up the connection from {addr}: {e:?}");
+                continue;
+            }
+
+            // Dropping the stream turns the client away
+            let socket = Socket::Tcp(Arc::new(Mutex::new(Box::new(stream))));
+            let Some(handle) = self.insert(socket) else {
+                println!("Too many sockets are open, turning away {addr}");
+                continue;
+            };
+
+            println!("Accepted {addr} on socket {listener} as socket {handle}");
+            accepted.push((
+                id,
+                TcpResponse::Accepted {
+                    listener,
+                    handle,
+                    addr: addr.ip().octets(),
+                    port: addr.port(),
+                },
+            ));
+        }
+
+        accepted
+    }
+
+    /// Closes every socket
+    pub fn clear(&mut self) {
+        self.open.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+}
+
+/// The code an io error is sent to the calculator as
+fn error_code(e: &io::Error) -> i32 {
+    e.raw_os_error().unwrap_or(ERR_OTHER)
+}
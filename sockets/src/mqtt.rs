@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use middlesp_proto::{
+    mqtt::{MqttActions, MqttConfig, MqttMessage, MqttResponse, Qos, ERR_NOT_SUBSCRIBED},
+    tcp::{Handle, ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+};
+
+use crate::{error_code, Socket, Sockets};
+
+/// Most messages queued for each subscription, past this the oldest are
+/// dropped
+const MAX_QUEUED: usize = 16;
+/// Most subscriptions each client may have
+const MAX_SUBSCRIPTIONS: usize = 8;
+
+/// Talks to the broker on an open connection, which is closed when this is
+/// dropped
+pub trait MqttClient: Send {
+    fn publish(&mut self, topic: &str, payload: &[u8], qos: Qos, retain: bool) -> io::Result<()>;
+    fn subscribe(&mut self, filter: &str, qos: Qos) -> io::Result<()>;
+    fn unsubscribe(&mut self, filter: &str) -> io::Result<()>;
+}
+
+/// Connects to a broker, giving up after the timeout. Messages which arrive
+/// afterwards are pushed into the [Subscriptions].
+pub type MqttConnect =
+    fn(&MqttConfig, Duration, Arc<Subscriptions>) -> io::Result<Box<dyn MqttClient>>;
+
+/// The messages queued for each of a client's subscriptions
+#[derive(Default)]
+pub struct Subscriptions {
+    state: Mutex<SubscriptionsState>,
+    arrived: Condvar,
+}
+
+#[derive(Default)]
+struct SubscriptionsState {
+    /// Each filter subscribed to, with what has arrived for it
+    queues: Vec<(String, VecDeque<MqttMessage>)>,
+    /// Whether the broker has gone away, nothing more will arrive
+    closed: bool,
+}
+
+/// Why nothing could be taken from a queue
+enum Missing {
+    NotSubscribed,
+    Closed,
+}
+
+impl Subscriptions {
+    /// Queues a message for every subscription it matches
+    pub fn push(&self, msg: MqttMessage) {
+        let mut state = self.state.lock().unwrap();
+        for (filter, queue) in &mut state.queues {
+            if !topic_matches(filter, &msg.topic) {
+                continue;
+            }
+
+            if queue.len() >= MAX_QUEUED {
+                queue.pop_front();
+            }
+            queue.push_back(msg.clone());
+        }
+
+        self.arrived.notify_all();
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.arrived.notify_all();
+    }
+
+    /// Starts queueing for a filter, returning whether it is new or `None` if
+    /// there are already as many subscriptions as we allow
+    fn add(&self, filter: &str) -> Option<bool> {
+        let mut state = self.state.lock().unwrap();
+        if state.queues.iter().any(|(f, _)| f == filter) {
+            return Some(false);
+        }
+        if state.queues.len() >= MAX_SUBSCRIPTIONS {
+            return None;
+        }
+
+        state.queues.push((filter.to_string(), VecDeque::new()));
+        Some(true)
+    }
+
+    fn remove(&self, filter: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let before = state.queues.len();
+        state.queues.retain(|(f, _)| f != filter);
+
+        state.queues.len() != before
+    }
+
+    /// Takes the oldest message queued for `filter`, waiting up to `timeout`
+    /// for one
+    fn take(&self, filter: &str, timeout: Duration) -> Result<Option<MqttMessage>, Missing> {
+        let state = self.state.lock().unwrap();
+        let (mut state, _) = self
+            .arrived
+            .wait_timeout_while(state, timeout, |s| {
+                let empty = s
+                    .queues
+                    .iter()
+                    .find(|(f, _)| f == filter)
+                    .is_some_and(|(_, q)| q.is_empty());
+
+                empty && !s.closed
+            })
+            .unwrap();
+
+        let closed = state.closed;
+        let Some((_, queue)) = state.queues.iter_mut().find(|(f, _)| f == filter) else {
+            return Err(Missing::NotSubscribed);
+        };
+
+        match queue.pop_front() {
+            Some(msg) => Ok(Some(msg)),
+            None if closed => Err(Missing::Closed),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Whether `topic` matches a subscription's filter, where `+` stands for any
+/// one level and a trailing `#` for any number of them (including none)
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    // Wildcards at the start of a filter do not match the `$SYS` style topics
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+
+    let mut topic = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(t)) if level == t => {}
+            _ => return false,
+        }
+    }
+
+    topic.next().is_none()
+}
+
+/// An open MQTT connection in the socket table
+pub(crate) struct Mqtt {
+    client: Mutex<Box<dyn MqttClient>>,
+    subscriptions: Arc<Subscriptions>,
+}
+
+/// Runs an MQTT request, blocking until it is done. The table is only locked
+/// while looking sockets up, not while waiting on them.
+pub fn run_mqtt(sockets: &Mutex<Sockets>, action: MqttActions) -> MqttResponse {
+    let res = match action {
+        MqttActions::Connect(config) => connect(sockets, &config),
+        MqttActions::Publish {
+            handle,
+            topic,
+            payload,
+            qos,
+            retain,
+        } => with_client(sockets, handle, |mqtt| {
+            let mut client = mqtt.client.lock().unwrap();
+            client.publish(&topic, &payload, qos, retain)?;
+
+            Ok(MqttResponse::Published)
+        }),
+        MqttActions::Subscribe {
+            handle,
+            filter,
+            qos,
+        } => with_client(sockets, handle, |mqtt| {
+            // Queue first so retained messages sent straight back are kept
+            let Some(new) = mqtt.subscriptions.add(&filter) else {
+                return Ok(MqttResponse::Error(ERR_TOO_MANY));
+            };
+            if let Err(e) = mqtt.client.lock().unwrap().subscribe(&filter, qos) {
+                if new {
+                    mqtt.subscriptions.remove(&filter);
+                }
+                return Err(e);
+            }
+
+            Ok(MqttResponse::Subscribed)
+        }),
+        MqttActions::Unsubscribe { handle, filter } => with_client(sockets, handle, |mqtt| {
+            if !mqtt.subscriptions.remove(&filter) {
+                return Ok(MqttResponse::Error(ERR_NOT_SUBSCRIBED));
+            }
+            mqtt.client.lock().unwrap().unsubscribe(&filter)?;
+
+            Ok(MqttResponse::Unsubscribed)
+        }),
+        MqttActions::Recv { handle, filter } => recv(sockets, handle, &filter),
+        MqttActions::Close(handle) => {
+            Ok(if sockets.lock().unwrap().remove(handle, Socket::is_mqtt) {
+                MqttResponse::Closed
+            } else {
+                MqttResponse::Error(ERR_NO_SOCKET)
+            })
+        }
+    };
+
+    res.unwrap_or_else(|e| MqttResponse::Error(error_code(&e)))
+}
+
+fn connect(sockets: &Mutex<Sockets>, config: &MqttConfig) -> io::Result<MqttResponse> {
+    let (timeout, connect_mqtt) = {
+        let sockets = sockets.lock().unwrap();
+        if sockets.is_full() {
+            return Ok(MqttResponse::Error(ERR_TOO_MANY));
+        }
+
+        (sockets.timeout, sockets.connectors.mqtt)
+    };
+    let Some(connect_mqtt) = connect_mqtt else {
+        return Ok(MqttResponse::Error(ERR_OTHER));
+    };
+
+    println!("Connecting to mqtt broker {}", config.url);
+    let subscriptions = Arc::new(Subscriptions::default());
+    let client = connect_mqtt(config, timeout, subscriptions.clone())?;
+
+    // Another socket may have been opened while we were connecting
+    let socket = Socket::Mqtt(Arc::new(Mqtt {
+        client: Mutex::new(client),
+        subscriptions,
+    }));
+    Ok(match sockets.lock().unwrap().insert(socket) {
+        Some(handle) => MqttResponse::Connected(handle),
+        None => MqttResponse::Error(ERR_TOO_MANY),
+    })
+}
+
+fn recv(sockets: &Mutex<Sockets>, handle: Handle, filter: &str) -> io::Result<MqttResponse> {
+    let timeout = sockets.lock().unwrap().timeout;
+    let res = with_client(sockets, handle, |mqtt| {
+        Ok(match mqtt.subscriptions.take(filter, timeout) {
+            Ok(Some(msg)) => MqttResponse::Message(msg),
+            Ok(None) => MqttResponse::Empty,
+            Err(Missing::NotSubscribed) => MqttResponse::Error(ERR_NOT_SUBSCRIBED),
+            Err(Missing::Closed) => MqttResponse::Closed,
+        })
+    })?;
+
+    if let MqttResponse::Closed = res {
+        println!("Mqtt client {handle} was disconnected by the broker");
+        sockets.lock().unwrap().remove(handle, Socket::is_mqtt);
+    }
+
+    Ok(res)
+}
+
+/// Runs `f` on the MQTT client with the given handle
+fn with_client(
+    sockets: &Mutex<Sockets>,
+    handle: Handle,
+    f: impl FnOnce(&Mqtt) -> io::Result<MqttResponse>,
+) -> io::Result<MqttResponse> {
+    let Some(mqtt) = sockets.lock().unwrap().mqtt(handle) else {
+        return Ok(MqttResponse::Error(ERR_NO_SOCKET));
+    };
+
+    f(&mqtt)
+}
@@ -0,0 +1,373 @@
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use middlesp_proto::{
+    http::Headers,
+    sse::{SseActions, SseEvent, SseResponse},
+    tcp::{Handle, ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+};
+
+use crate::{error_code, Socket, Sockets, STACK_SIZE};
+
+/// Most events queued for each stream, past this the oldest are dropped
+const MAX_QUEUED: usize = 32;
+/// Most bytes of data kept for an event, anything past this is cut off
+const MAX_DATA: usize = 4096;
+/// Longest line kept, so a server which never ends one can not use up the
+/// memory
+const MAX_LINE: usize = MAX_DATA + 16;
+/// How long to wait before reconnecting, unless the server says otherwise
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+/// Reconnects tried in a row before giving up
+const MAX_RECONNECTS: usize = 5;
+
+/// The body of a response to a GET for an event stream
+pub type EventStream = Box<dyn Read + Send>;
+
+/// Opens an event stream with a GET to the url with the extra headers, and
+/// `Last-Event-ID` if given. Fails unless the server answers with a 200.
+/// Reads should time out after the given timeout so closing is noticed.
+pub type SseConnect = fn(&str, &Headers, Option<&str>, Duration) -> io::Result<EventStream>;
+
+/// Splits an event stream up into events, as laid out in the HTML spec
+#[derive(Default)]
+pub struct SseParser {
+    /// Bytes of the line so far
+    line: Vec<u8>,
+    /// Whether the last byte was a `\r`, which a `\n` may follow
+    after_cr: bool,
+    /// Fields of the event so far
+    event: String,
+    data: String,
+    id: String,
+    /// The id as of the last event dispatched, which a half sent one does
+    /// not change
+    last_id: String,
+    retry: Option<Duration>,
+}
+
+impl SseParser {
+    /// Parses the next bytes of the stream, returning any events they finish
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &b in bytes {
+            let after_cr = std::mem::replace(&mut self.after_cr, b == b'\r');
+            match b {
+                b'\n' if after_cr => {}
+                b'\r' | b'\n' => {
+                    let line = std::mem::take(&mut self.line);
+                    events.extend(self.line_done(&String::from_utf8_lossy(&line)));
+                }
+                _ if self.line.len() < MAX_LINE => self.line.push(b),
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    /// The id to reconnect with, empty if the server never sent one
+    pub fn last_id(&self) -> &str {
+        &self.last_id
+    }
+
+    /// How long the server asked us to wait before reconnecting
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Throws away the event being built up, as a stream which drops part way
+    /// through one never finished it
+    pub fn discard_pending(&mut self) {
+        self.line.clear();
+        self.after_cr = false;
+        self.event.clear();
+        self.data.clear();
+        self.id.clone_from(&self.last_id);
+    }
+
+    fn line_done(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        let (field, value) = match line.split_once(':') {
+            // A comment, often sent to keep the connection open
+            Some(("", _)) => return None,
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            // Anything past the limit is cut off when the event is sent
+            "data" if self.data.len() <= MAX_DATA => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.id = value.to_string(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok().map(Duration::from_millis);
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+        self.last_id.clone_from(&self.id);
+        if data.is_empty() {
+            return None;
+        }
+
+        data.pop();
+        if data.len() > MAX_DATA {
+            let mut end = MAX_DATA;
+            while !data.is_char_boundary(end) {
+                end -= 1;
+            }
+            data.truncate(end);
+        }
+
+        Some(SseEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            data,
+            id: self.last_id.clone(),
+        })
+    }
+}
+
+/// Where the thread reading a stream leaves events for the calculator
+#[derive(Default)]
+struct Queue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    events: VecDeque<SseEvent>,
+    /// Whether reconnecting has been given up on, nothing more will arrive
+    closed: bool,
+    /// Whether the calculator has closed the stream, so the thread should
+    /// stop
+    stopped: bool,
+}
+
+impl Queue {
+    fn push(&self, event: SseEvent) {
+        let mut state = self.state.lock().unwrap();
+        if state.events.len() >= MAX_QUEUED {
+            state.events.pop_front();
+        }
+        state.events.push_back(event);
+
+        self.changed.notify_all();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.changed.notify_all();
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.state.lock().unwrap().stopped
+    }
+
+    /// Waits for `delay` or for the stream to be stopped, returning false if
+    /// it was
+    fn sleep(&self, delay: Duration) -> bool {
+        let state = self.state.lock().unwrap();
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, delay, |s| !s.stopped)
+            .unwrap();
+
+        !state.stopped
+    }
+
+    /// Takes the oldest event, waiting up to `timeout` for one. `Err` means
+    /// nothing is left and nothing more will arrive.
+    fn take(&self, timeout: Duration) -> Result<Option<SseEvent>, ()> {
+        let state = self.state.lock().unwrap();
+        let (mut state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |s| s.events.is_empty() && !s.closed)
+            .unwrap();
+
+        match state.events.pop_front() {
+            Some(event) => Ok(Some(event)),
+            None if state.closed => Err(()),
+            None => Ok(None),
+        }
+    }
+}
+
+/// An event stream in the socket table, which stops the thread reading it
+/// once it is dropped
+pub(crate) struct Sse {
+    queue: Arc<Queue>,
+}
+
+impl Drop for Sse {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().stopped = true;
+        self.queue.changed.notify_all();
+    }
+}
+
+/// What the thread reading a stream needs to reconnect
+struct Source {
+    connect: SseConnect,
+    url: String,
+    headers: Headers,
+    timeout: Duration,
+}
+
+/// Runs an SSE request, blocking until it is done. The table is only locked
+/// while looking streams up, not while waiting on them.
+pub fn run_sse(sockets: &Mutex<Sockets>, action: SseActions) -> SseResponse {
+    let res = match action {
+        SseActions::Subscribe { url, headers } => subscribe(sockets, url, headers),
+        SseActions::Recv(handle) => recv(sockets, handle),
+        SseActions::Close(handle) => {
+            Ok(if sockets.lock().unwrap().remove(handle, Socket::is_sse) {
+                SseResponse::Closed
+            } else {
+                SseResponse::Error(ERR_NO_SOCKET)
+            })
+        }
+    };
+
+    res.unwrap_or_else(|e| SseResponse::Error(error_code(&e)))
+}
+
+fn subscribe(sockets: &Mutex<Sockets>, url: String, headers: Headers) -> io::Result<SseResponse> {
+    let (timeout, connect) = {
+        let sockets = sockets.lock().unwrap();
+        if sockets.is_full() {
+            return Ok(SseResponse::Error(ERR_TOO_MANY));
+        }
+
+        (sockets.timeout, sockets.connectors.sse)
+    };
+    let Some(connect) = connect else {
+        return Ok(SseResponse::Error(ERR_OTHER));
+    };
+
+    println!("Opening event stream {url}");
+    let stream = connect(&url, &headers, None, timeout)?;
+
+    let queue = Arc::new(Queue::default());
+    let source = Source {
+        connect,
+        url,
+        headers,
+        timeout,
+    };
+    let sse = Sse {
+        queue: queue.clone(),
+    };
+
+    // Another socket may have been opened while we were connecting
+    let Some(handle) = sockets.lock().unwrap().insert(Socket::Sse(Arc::new(sse))) else {
+        return Ok(SseResponse::Error(ERR_TOO_MANY));
+    };
+
+    if let Err(e) = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || read_stream(stream, &source, &queue))
+    {
+        sockets.lock().unwrap().remove(handle, Socket::is_sse);
+        return Err(e);
+    }
+
+    Ok(SseResponse::Subscribed(handle))
+}
+
+/// Queues events from the stream until it is stopped, reconnecting whenever
+/// it drops
+fn read_stream(mut stream: EventStream, source: &Source, queue: &Queue) {
+    let mut parser = SseParser::default();
+    let mut buf = [0; 512];
+
+    while !queue.is_stopped() {
+        match stream.read(&mut buf) {
+            Ok(0) => {}
+            Ok(size) => {
+                for event in parser.feed(&buf[..size]) {
+                    queue.push(event);
+                }
+                continue;
+            }
+            // Servers may stay quiet for a long time
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue;
+            }
+            Err(e) => println!("Event stream {} failed: {e:?}", source.url),
+        }
+
+        parser.discard_pending();
+        match reconnect(source, &parser, queue) {
+            Some(new) => stream = new,
+            None => break,
+        }
+    }
+
+    queue.close();
+}
+
+/// Opens the stream again from where it dropped, `None` if it was stopped
+/// or every try failed
+fn reconnect(source: &Source, parser: &SseParser, queue: &Queue) -> Option<EventStream> {
+    let delay = parser.retry().unwrap_or(DEFAULT_RETRY);
+    let last_id = Some(parser.last_id()).filter(|id| !id.is_empty());
+
+    for _ in 0..MAX_RECONNECTS {
+        if !queue.sleep(delay) {
+            return None;
+        }
+
+        println!("Reconnecting to event stream {}", source.url);
+        match (source.connect)(&source.url, &source.headers, last_id, source.timeout) {
+            Ok(stream) => return Some(stream),
+            Err(e) => println!("Failed to reconnect: {e:?}"),
+        }
+    }
+
+    println!("Giving up on event stream {}", source.url);
+    None
+}
+
+fn recv(sockets: &Mutex<Sockets>, handle: Handle) -> io::Result<SseResponse> {
+    let (timeout, sse) = {
+        let sockets = sockets.lock().unwrap();
+        (sockets.timeout, sockets.sse(handle))
+    };
+    let Some(sse) = sse else {
+        return Ok(SseResponse::Error(ERR_NO_SOCKET));
+    };
+
+    let res = match sse.queue.take(timeout) {
+        Ok(Some(event)) => SseResponse::Event(event),
+        Ok(None) => SseResponse::Empty,
+        Err(()) => SseResponse::Closed,
+    };
+
+    if let SseResponse::Closed = res {
+        println!("Event stream {handle} could not be reconnected");
+        sockets.lock().unwrap().remove(handle, Socket::is_sse);
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,157 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use middlesp_proto::tcp::{
+    Handle, TcpActions, TcpResponse, ERR_NO_SOCKET, ERR_NO_TLS, ERR_TOO_MANY,
+};
+
+use crate::{error_code, Socket, Sockets};
+
+/// Most bytes a single read hands back
+const MAX_READ: usize = 4096;
+
+/// A connected socket, plain TCP or TLS
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Opens a TLS connection to `host:port`, with the given timeout for
+/// connecting and for each read or write
+pub type TlsConnect = fn(&str, u16, Duration) -> io::Result<Box<dyn Stream>>;
+
+/// Runs a TCP request, blocking until it is done. The table is only locked
+/// while looking sockets up, not while waiting on them. `id` is the id the
+/// request came with, which connections to a listening socket are announced
+/// with (see [Sockets::accept]).
+pub fn run_tcp(sockets: &Mutex<Sockets>, id: u8, action: TcpActions) -> TcpResponse {
+    let res = match action {
+        TcpActions::Open { host, port, tls } => open(sockets, &host, port, tls),
+        TcpActions::Write(handle, bytes) => with_socket(sockets, handle, |s| {
+            s.write_all(&bytes)?;
+            s.flush()?;
+
+            Ok(TcpResponse::Written)
+        }),
+        TcpActions::Read(handle, max) => read(sockets, handle, max),
+        TcpActions::Close(handle) => {
+            Ok(if sockets.lock().unwrap().remove(handle, Socket::is_tcp) {
+                TcpResponse::Closed
+            } else {
+                TcpResponse::Error(ERR_NO_SOCKET)
+            })
+        }
+        TcpActions::Listen(port) => listen(sockets, id, port),
+    };
+
+    res.unwrap_or_else(|e| TcpResponse::Error(error_code(&e)))
+}
+
+fn open(sockets: &Mutex<Sockets>, host: &str, port: u16, tls: bool) -> io::Result<TcpResponse> {
+    let (timeout, connect_tls) = {
+        let sockets = sockets.lock().unwrap();
+        if sockets.is_full() {
+            return Ok(TcpResponse::Error(ERR_TOO_MANY));
+        }
+
+        (sockets.timeout, sockets.connectors.tls)
+    };
+
+    println!("Opening socket to {host}:{port}");
+    let stream: Box<dyn Stream> = match (tls, connect_tls) {
+        (false, _) => Box::new(connect(host, port, timeout)?),
+        (true, Some(connect_tls)) => connect_tls(host, port, timeout)?,
+        (true, None) => return Ok(TcpResponse::Error(ERR_NO_TLS)),
+    };
+
+    // Another socket may have been opened while we were connecting
+    let socket = Socket::Tcp(Arc::new(Mutex::new(stream)));
+    Ok(match sockets.lock().unwrap().insert(socket) {
+        Some(handle) => TcpResponse::Opened(handle),
+        None => TcpResponse::Error(ERR_TOO_MANY),
+    })
+}
+
+/// Connects to the first address `host` resolves to which answers
+pub(crate) fn connect(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(ErrorKind::NotFound, "Host has no addresses");
+
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                configure(&stream, timeout)?;
+                return Ok(stream);
+            }
+            Err(e) => last = e,
+        }
+    }
+
+    Err(last)
+}
+
+/// Sets up a connected stream the way the requests expect it
+pub(crate) fn configure(stream: &TcpStream, timeout: Duration) -> io::Result<()> {
+    // Accepted streams could inherit non-blocking from their listener
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.set_nodelay(true)
+}
+
+fn listen(sockets: &Mutex<Sockets>, id: u8, port: u16) -> io::Result<TcpResponse> {
+    let mut sockets = sockets.lock().unwrap();
+    if sockets.is_full() {
+        return Ok(TcpResponse::Error(ERR_TOO_MANY));
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+    // Polled from the main loop, which must never wait on it
+    listener.set_nonblocking(true)?;
+    let port = listener.local_addr()?.port();
+
+    println!("Listening on port {port}");
+    Ok(match sockets.insert(Socket::Listener { listener, id }) {
+        Some(handle) => TcpResponse::Listening { handle, port },
+        None => TcpResponse::Error(ERR_TOO_MANY),
+    })
+}
+
+fn read(sockets: &Mutex<Sockets>, handle: Handle, max: u16) -> io::Result<TcpResponse> {
+    let res = with_socket(sockets, handle, |s| {
+        let mut buf = vec![0; (max as usize).min(MAX_READ)];
+        match s.read(&mut buf) {
+            // Asking for nothing is not the same as the other end closing
+            Ok(0) if !buf.is_empty() => Ok(TcpResponse::Closed),
+            Ok(size) => {
+                buf.truncate(size);
+                Ok(TcpResponse::Data(buf))
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                Ok(TcpResponse::Data(Vec::new()))
+            }
+            Err(e) => Err(e),
+        }
+    })?;
+
+    if let TcpResponse::Closed = res {
+        println!("Socket {handle} was closed by the other end");
+        sockets.lock().unwrap().remove(handle, Socket::is_tcp);
+    }
+
+    Ok(res)
+}
+
+/// Runs `f` on the socket with the given handle
+fn with_socket(
+    sockets: &Mutex<Sockets>,
+    handle: Handle,
+    f: impl FnOnce(&mut dyn Stream) -> io::Result<TcpResponse>,
+) -> io::Result<TcpResponse> {
+    let Some(socket) = sockets.lock().unwrap().tcp(handle) else {
+        return Ok(TcpResponse::Error(ERR_NO_SOCKET));
+    };
+    let mut socket = socket.lock().unwrap();
+
+    f(&mut **socket)
+}
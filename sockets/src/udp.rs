@@ -0,0 +1,104 @@
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+
+use middlesp_proto::{
+    tcp::{Handle, ERR_NO_SOCKET, ERR_TOO_MANY},
+    udp::{Datagram, UdpActions, UdpResponse},
+};
+
+use crate::{error_code, Socket, Sockets};
+
+/// The most a datagram can carry without being fragmented on ethernet,
+/// anything longer is cut off
+const MAX_DATAGRAM: usize = 1472;
+
+/// Runs a UDP request, blocking until it is done. The table is only locked
+/// while looking sockets up, not while waiting on them.
+pub fn run_udp(sockets: &Mutex<Sockets>, action: UdpActions) -> UdpResponse {
+    let res = match action {
+        UdpActions::Bind(port) => bind(sockets, port),
+        UdpActions::Send {
+            handle,
+            host,
+            port,
+            data,
+        } => with_socket(sockets, handle, |s| {
+            let addr = resolve(&host, port)?;
+            s.send_to(&data, addr)?;
+
+            Ok(UdpResponse::Sent)
+        }),
+        UdpActions::Recv(handle) => with_socket(sockets, handle, |s| {
+            let mut buf = [0; MAX_DATAGRAM];
+            match s.recv_from(&mut buf) {
+                Ok((size, SocketAddr::V4(from))) => Ok(UdpResponse::Received(Datagram {
+                    addr: from.ip().octets(),
+                    port: from.port(),
+                    data: buf[..size].to_vec(),
+                })),
+                // We only bind to IPv4 addresses
+                Ok((_, SocketAddr::V6(_))) => Ok(UdpResponse::Empty),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    Ok(UdpResponse::Empty)
+                }
+                Err(e) => Err(e),
+            }
+        }),
+        UdpActions::Close(handle) => {
+            Ok(if sockets.lock().unwrap().remove(handle, Socket::is_udp) {
+                UdpResponse::Closed
+            } else {
+                UdpResponse::Error(ERR_NO_SOCKET)
+            })
+        }
+    };
+
+    res.unwrap_or_else(|e| UdpResponse::Error(error_code(&e)))
+}
+
+fn bind(sockets: &Mutex<Sockets>, port: u16) -> io::Result<UdpResponse> {
+    let mut sockets = sockets.lock().unwrap();
+    if sockets.is_full() {
+        return Ok(UdpResponse::Error(ERR_TOO_MANY));
+    }
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+    socket.set_broadcast(true)?;
+    socket.set_read_timeout(Some(sockets.timeout))?;
+    socket.set_write_timeout(Some(sockets.timeout))?;
+
+    let port = socket.local_addr()?.port();
+    println!("Bound udp socket to port {port}");
+
+    Ok(match sockets.insert(Socket::Udp(Arc::new(socket))) {
+        Some(handle) => UdpResponse::Bound { handle, port },
+        None => UdpResponse::Error(ERR_TOO_MANY),
+    })
+}
+
+/// The first IPv4 address of `host`
+fn resolve(host: &str, port: u16) -> io::Result<SocketAddr> {
+    // Skips a DNS lookup for the common case of an address (or broadcast)
+    if let Ok(ip) = host.parse::<Ipv4Addr>() {
+        return Ok(SocketAddr::new(IpAddr::V4(ip), port));
+    }
+
+    (host, port)
+        .to_socket_addrs()?
+        .find(SocketAddr::is_ipv4)
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Host has no IPv4 addresses"))
+}
+
+/// Runs `f` on the socket with the given handle
+fn with_socket(
+    sockets: &Mutex<Sockets>,
+    handle: Handle,
+    f: impl FnOnce(&UdpSocket) -> io::Result<UdpResponse>,
+) -> io::Result<UdpResponse> {
+    let Some(socket) = sockets.lock().unwrap().udp(handle) else {
+        return Ok(UdpResponse::Error(ERR_NO_SOCKET));
+    };
+
+    f(&socket)
+}
@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use middlesp_proto::{
+    http::Headers,
+    tcp::{Handle, ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+    ws::{WsActions, WsMessage, WsResponse, ERR_TOO_BIG},
+};
+
+use crate::{error_code, Socket, Sockets};
+
+/// Most bytes of messages kept for each connection, past this the oldest
+/// are dropped
+const MAX_BUFFERED: usize = 16 * 1024;
+/// Longest message put back together from its pieces, anything longer would
+/// not fit in the buffer anyway
+const MAX_MESSAGE: usize = MAX_BUFFERED;
+
+/// Sends messages on an open WebSocket, which is closed when this is dropped
+pub trait WsSender: Send {
+    fn send(&mut self, msg: &WsMessage) -> io::Result<()>;
+}
+
+/// Connects to a WebSocket url with the given extra headers, giving up after
+/// the timeout. Whatever arrives afterwards is pushed into the [Inbox].
+pub type WsConnect = fn(&str, &Headers, Duration, Arc<Inbox>) -> io::Result<Box<dyn WsSender>>;
+
+/// Messages which have arrived on a WebSocket but not been taken yet
+#[derive(Default)]
+pub struct Inbox {
+    state: Mutex<InboxState>,
+    arrived: Condvar,
+}
+
+#[derive(Default)]
+struct InboxState {
+    messages: VecDeque<WsMessage>,
+    /// Bytes held across all of `messages`
+    size: usize,
+    /// Whether the server has gone away, nothing more will arrive
+    closed: bool,
+    /// The message whose pieces are still arriving
+    partial: Option<Partial>,
+    /// Whether a message has been dropped for being over [MAX_MESSAGE]
+    /// since the calculator last asked for one
+    dropped: bool,
+}
+
+/// What a frame holds, going by its opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+}
+
+/// A message which has only partly arrived
+struct Partial {
+    text: bool,
+    data: Vec<u8>,
+    too_big: bool,
+}
+
+impl Inbox {
+    pub fn push(&self, msg: WsMessage) {
+        let mut state = self.state.lock().unwrap();
+        state.size += len(&msg);
+        state.messages.push_back(msg);
+
+        while state.size > MAX_BUFFERED {
+            let Some(old) = state.messages.pop_front() else {
+                break;
+            };
+            state.size -= len(&old);
+        }
+
+        self.arrived.notify_all();
+    }
+
+    /// Adds a piece of a message, for clients which hand long frames over in
+    /// pieces. `data` starts `offset` bytes into a frame of `frame_len` bytes,
+    /// and `fin` is whether that frame is the last of its message. Messages
+    /// over [MAX_MESSAGE] are dropped, which the next [WsActions::Recv] is
+    /// answered with [ERR_TOO_BIG] for. Text which is not UTF-8 is kept as
+    /// binary.
+    pub fn push_piece(
+        &self,
+        opcode: Opcode,
+        fin: bool,
+        frame_len: usize,
+        offset: usize,
+        data: &[u8],
+    ) {
+        let mut state = self.state.lock().unwrap();
+
+        // Pieces after the first in a frame still have the frame's opcode
+        if opcode != Opcode::Continuation && offset == 0 {
+            if state.partial.is_some() {
+                println!("Websocket message never finished, dropping it");
+            }
+            state.partial = Some(Partial {
+                text: opcode == Opcode::Text,
+                data: Vec::new(),
+                too_big: false,
+            });
+        }
+
+        // Whatever this continues was never started
+        let Some(partial) = state.partial.as_mut() else {
+            return;
+        };
+
+        if partial.data.len() + data.len() > MAX_MESSAGE {
+            partial.too_big = true;
+            partial.data = Vec::new();
+        } else if !partial.too_big {
+            partial.data.extend_from_slice(data);
+        }
+
+        if !fin || offset + data.len() < frame_len {
+            return;
+        }
+
+        let partial = state.partial.take().unwrap();
+        if partial.too_big {
+            println!("Websocket message was over {MAX_MESSAGE} bytes, dropping it");
+            state.dropped = true;
+            self.arrived.notify_all();
+            return;
+        }
+        drop(state);
+
+        self.push(if partial.text {
+            match String::from_utf8(partial.data) {
+                Ok(text) => WsMessage::Text(text),
+                Err(e) => WsMessage::Binary(e.into_bytes()),
+            }
+        } else {
+            WsMessage::Binary(partial.data)
+        });
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.arrived.notify_all();
+    }
+
+    /// Takes the oldest message, waiting up to `timeout` for one, after
+    /// saying if any were dropped for being too long
+    fn take(&self, timeout: Duration) -> WsResponse {
+        let state = self.state.lock().unwrap();
+        let (mut state, _) = self
+            .arrived
+            .wait_timeout_while(state, timeout, |s| {
+                s.messages.is_empty() && !s.closed && !s.dropped
+            })
+            .unwrap();
+
+        if state.dropped {
+            state.dropped = false;
+            return WsResponse::Error(ERR_TOO_BIG);
+        }
+
+        match state.messages.pop_front() {
+            Some(msg) => {
+                state.size -= len(&msg);
+                WsResponse::Message(msg)
+            }
+            None if state.closed => WsResponse::Closed,
+            None => WsResponse::Empty,
+        }
+    }
+}
+
+fn len(msg: &WsMessage) -> usize {
+    match msg {
+        WsMessage::Text(text) => text.len(),
+        WsMessage::Binary(data) => data.len(),
+    }
+}
+
+/// An open WebSocket in the socket table
+pub(crate) struct WebSocket {
+    sender: Mutex<Box<dyn WsSender>>,
+    inbox: Arc<Inbox>,
+}
+
+/// Runs a WebSocket request, blocking until it is done. The table is only
+/// locked while looking sockets up, not while waiting on them.
+pub fn run_ws(sockets: &Mutex<Sockets>, action: WsActions) -> WsResponse {
+    let res = match action {
+        WsActions::Connect { url, headers } => connect(sockets, &url, &headers),
+        WsActions::Send(handle, msg) => with_socket(sockets, handle, |ws| {
+            ws.sender.lock().unwrap().send(&msg)?;
+            Ok(WsResponse::Sent)
+        }),
+        WsActions::Recv(handle) => recv(sockets, handle),
+        WsActions::Close(handle) => Ok(if sockets.lock().unwrap().remove(handle, Socket::is_ws) {
+            WsResponse::Closed
+        } else {
+            WsResponse::Error(ERR_NO_SOCKET)
+        }),
+    };
+
+    res.unwrap_or_else(|e| WsResponse::Error(error_code(&e)))
+}
+
+fn connect(sockets: &Mutex<Sockets>, url: &str, headers: &Headers) -> io::Result<WsResponse> {
+    let (timeout, connect_ws) = {
+        let sockets = sockets.lock().unwrap();
+        if sockets.is_full() {
+            return Ok(WsResponse::Error(ERR_TOO_MANY));
+        }
+
+        (sockets.timeout, sockets.connectors.ws)
+    };
+    let Some(connect_ws) = connect_ws else {
+        return Ok(WsResponse::Error(ERR_OTHER));
+    };
+
+    println!("Opening websocket to {url}");
+    let inbox = Arc::new(Inbox::default());
+    let sender = connect_ws(url, headers, timeout, inbox.clone())?;
+
+    // Another socket may have been opened while we were connecting
+    let socket = Socket::Ws(Arc::new(WebSocket {
+        sender: Mutex::new(sender),
+        inbox,
+    }));
+    Ok(match sockets.lock().unwrap().insert(socket) {
+        Some(handle) => WsResponse::Connected(handle),
+        None => WsResponse::Error(ERR_TOO_MANY),
+    })
+}
+
+fn recv(sockets: &Mutex<Sockets>, handle: Handle) -> io::Result<WsResponse> {
+    let timeout = sockets.lock().unwrap().timeout;
+    let res = with_socket(sockets, handle, |ws| Ok(ws.inbox.take(timeout)))?;
+
+    if let WsResponse::Closed = res {
+        println!("Websocket {handle} was closed by the server");
+        sockets.lock().unwrap().remove(handle, Socket::is_ws);
+    }
+
+    Ok(res)
+}
+
+/// Runs `f` on the WebSocket with the given handle
+fn with_socket(
+    sockets: &Mutex<Sockets>,
+    handle: Handle,
+    f: impl FnOnce(&WebSocket) -> io::Result<WsResponse>,
+) -> io::Result<WsResponse> {
+    let Some(ws) = sockets.lock().unwrap().ws(handle) else {
+        return Ok(WsResponse::Error(ERR_NO_SOCKET));
+    };
+
+    f(&ws)
+}
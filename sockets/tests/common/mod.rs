@@ -0,0 +1,66 @@
+//! Helpers shared by the socket tests
+
+// Each test only uses some of them
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, UdpSocket};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use middlesp_sockets::{Connectors, Sockets};
+
+/// Id the requests are sent with
+pub const ID: u8 = 1;
+
+pub const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// A table with room for `max` sockets, which can only open plain ones
+pub fn sockets(max: usize) -> Mutex<Sockets> {
+    sockets_with(max, Connectors::default())
+}
+
+pub fn sockets_with(max: usize, connectors: Connectors) -> Mutex<Sockets> {
+    Mutex::new(Sockets::new(max, TIMEOUT, connectors))
+}
+
+/// Starts a server on a free local port which sends back whatever it gets,
+/// returning the port
+pub fn echo_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            thread::spawn(move || {
+                let mut buf = [0; 256];
+                loop {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(size) => stream.write_all(&buf[..size]).unwrap(),
+                    }
+                }
+            });
+        }
+    });
+
+    port
+}
+
+/// Starts a server on a free local port which sends every datagram back to
+/// where it came from, returning the port
+pub fn udp_echo_server() -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let mut buf = [0; 2048];
+        while let Ok((size, from)) = socket.recv_from(&mut buf) {
+            socket.send_to(&buf[..size], from).unwrap();
+        }
+    });
+
+    port
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use middlesp_proto::{
+    fetch::{FetchActions, FetchResponse, Line, Page, ERR_BAD_URL, ERR_NOT_TEXT, ERR_NOT_TRUSTED},
+    tcp::ERR_NO_TLS,
+};
+use middlesp_sockets::{
+    parse_gemtext, parse_gophermap, resolve, run_fetch, CertStore, Fetcher, Fingerprint, Stream,
+};
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct Certs(HashMap<String, Fingerprint>);
+
+impl CertStore for Certs {
+    fn get(&self, host: &str) -> Option<Fingerprint> {
+        self.0.get(host).copied()
+    }
+
+    fn set(&mut self, host: &str, fingerprint: Fingerprint) {
+        self.0.insert(host.to_string(), fingerprint);
+    }
+
+    fn forget(&mut self, host: &str) -> bool {
+        self.0.remove(host).is_some()
+    }
+}
+
+/// Stands in for TLS, with the server naming its certificate on the first
+/// line it sends
+fn connect_plain(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+) -> io::Result<(Box<dyn Stream>, Fingerprint)> {
+    let mut stream = TcpStream::connect((host, port))?;
+    stream.set_read_timeout(Some(timeout))?;
+
+    let mut fingerprint = [0; 32];
+    let mut byte = [0];
+    for b in &mut fingerprint {
+        stream.read_exact(&mut byte)?;
+        if byte[0] == b'\n' {
+            break;
+        }
+        *b = byte[0];
+    }
+
+    Ok((Box::new(stream), fingerprint))
+}
+
+fn fetcher() -> Mutex<Fetcher> {
+    Mutex::new(Fetcher::new(
+        TIMEOUT,
+        Some(connect_plain),
+        Box::new(Certs::default()),
+    ))
+}
+
+/// Starts a server on a free local port which answers a single request,
+/// after naming its certificate if given one. Returns the port and the
+/// request line it got.
+fn server(cert: Option<&'static str>, reply: &'static str) -> (u16, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        if let Some(cert) = cert {
+            writeln!(stream, "{cert}").unwrap();
+        }
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let _ = tx.send(line);
+        // We may well have hung up already
+        let _ = stream.write_all(reply.as_bytes());
+    });
+
+    (port, rx)
+}
+
+fn get(fetcher: &Mutex<Fetcher>, url: &str, input: &str) -> FetchResponse {
+    run_fetch(
+        fetcher,
+        FetchActions::Get {
+            url: url.to_string(),
+            input: input.to_string(),
+        },
+    )
+}
+
+fn fetched(resp: FetchResponse) -> Page {
+    match resp {
+        FetchResponse::Page(page) => page,
+        resp => panic!("Expected a page, got {resp:?}"),
+    }
+}
+
+fn text(s: &str) -> Line {
+    Line::Text(s.to_string())
+}
+
+fn link(target: &str, label: &str) -> Line {
+    Line::Link {
+        target: target.to_string(),
+        label: label.to_string(),
+    }
+}
+
+#[test]
+fn resolving() {
+    let base = "gemini://example.org/docs/intro.gmi?q";
+    assert_eq!(
+        resolve(base, "next.gmi"),
+        "gemini://example.org/docs/next.gmi"
+    );
+    assert_eq!(
+        resolve(base, "../index.gmi"),
+        "gemini://example.org/index.gmi"
+    );
+    assert_eq!(resolve(base, "./"), "gemini://example.org/docs/");
+    assert_eq!(resolve(base, ".."), "gemini://example.org/");
+    assert_eq!(resolve(base, "/about"), "gemini://example.org/about");
+    assert_eq!(resolve(base, "?2"), "gemini://example.org/docs/intro.gmi?2");
+    assert_eq!(resolve(base, "//other.org/"), "gemini://other.org/");
+    assert_eq!(resolve(base, "https://example.com"), "https://example.com");
+    assert_eq!(
+        resolve("gemini://example.org:1966", "a"),
+        "gemini://example.org:1966/a"
+    );
+}
+
+#[test]
+fn gemtext() {
+    let lines = parse_gemtext(
+        "gemini://example.org/blog/",
+        "# Blog\r\n\
+         Welcome\n\
+         => post.gmi  First post\n\
+         =>/\n\
+         =>\n\
+         ## Lists\n\
+         * one\n\
+         > quoted\n\
+         ```ascii art\n\
+         # not a heading\n\
+         ```\n\
+         ### Done",
+    );
+
+    assert_eq!(
+        lines,
+        [
+            Line::Heading {
+                level: 1,
+                text: "Blog".to_string()
+            },
+            text("Welcome"),
+            link("gemini://example.org/blog/post.gmi", "First post"),
+            link("gemini://example.org/", "/"),
+            Line::Heading {
+                level: 2,
+                text: "Lists".to_string()
+            },
+            Line::ListItem("one".to_string()),
+            Line::Quote("quoted".to_string()),
+            Line::Preformatted("# not a heading".to_string()),
+            Line::Heading {
+                level: 3,
+                text: "Done".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn gophermap() {
+    let lines = parse_gophermap(
+        "iWelcome\t\terror.host\t1\r\n\
+         1Games\t/games\tgopher.example\t70\r\n\
+         0About me\t/about.txt\tgopher.example\t7070\r\n\
+         7Search\t/search\tgopher.example\t70\r\n\
+         hWeb\tURL:https://example.com\tgopher.example\t70\r\n\
+         9Tarball\t/a b.tgz\tgopher.example\t70\r\n\
+         .\r\n\
+         iignored\t\terror.host\t1\r\n",
+    );
+
+    assert_eq!(
+        lines,
+        [
+            text("Welcome"),
+            link("gopher://gopher.example/1/games", "Games"),
+            link("gopher://gopher.example:7070/0/about.txt", "About me"),
+            Line::Search {
+                target: "gopher://gopher.example/7/search".to_string(),
+                label: "Search".to_string(),
+            },
+            link("https://example.com", "Web"),
+            link("gopher://gopher.example/9/a%20b.tgz", "Tarball"),
+        ]
+    );
+}
+
+#[test]
+fn gemini_page() {
+    let (port, request) = server(
+        Some("cert"),
+        "20 text/gemini; charset=utf-8\r\n# Hello\r\n=> /next Next\r\n",
+    );
+    let url = format!("gemini://127.0.0.1:{port}/");
+    let page = fetched(get(&fetcher(), &url, ""));
+
+    assert_eq!(request.recv().unwrap(), format!("{url}\r\n"));
+    assert!(!page.truncated);
+    assert_eq!(
+        page.lines,
+        [
+            Line::Heading {
+                level: 1,
+                text: "Hello".to_string()
+            },
+            link(&format!("gemini://127.0.0.1:{port}/next"), "Next"),
+        ]
+    );
+}
+
+#[test]
+fn plain_text_and_binaries() {
+    let fetcher = fetcher();
+
+    let (port, _) = server(Some("cert"), "20 text/plain\r\n# not a heading\n");
+    let page = fetched(get(
+        &fetcher,
+        &format!("gemini://127.0.0.1:{port}/a.txt"),
+        "",
+    ));
+    assert_eq!(page.lines, [text("# not a heading")]);
+
+    let (port, _) = server(Some("cert"), "20 image/png\r\n\u{89}PNG");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/a.png"), "");
+    assert!(matches!(resp, FetchResponse::Error(ERR_NOT_TEXT)));
+}
+
+#[test]
+fn input_and_status_codes() {
+    let fetcher = fetcher();
+
+    let (port, _) = server(Some("cert"), "11 Password\r\n");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/login"), "");
+    let FetchResponse::Input { prompt, sensitive } = resp else {
+        panic!("Expected a prompt, got {resp:?}");
+    };
+    assert_eq!(prompt, "Password");
+    assert!(sensitive);
+
+    // The answer replaces any query, encoded
+    let (port, request) = server(Some("cert"), "20\r\nWelcome\n");
+    let url = format!("gemini://127.0.0.1:{port}/login?old");
+    let page = fetched(get(&fetcher, &url, "a b&c"));
+    assert_eq!(page.lines, [text("Welcome")]);
+    assert_eq!(
+        request.recv().unwrap(),
+        format!("gemini://127.0.0.1:{port}/login?a%20b%26c\r\n")
+    );
+
+    let (port, _) = server(Some("cert"), "31 ../moved\r\n");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/a/b"), "");
+    let FetchResponse::Redirect(target) = resp else {
+        panic!("Expected a redirect, got {resp:?}");
+    };
+    assert_eq!(target, format!("gemini://127.0.0.1:{port}/moved"));
+
+    let (port, _) = server(Some("cert"), "51 Not found\r\n");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/gone"), "");
+    let FetchResponse::Failed { status, meta } = resp else {
+        panic!("Expected a failure, got {resp:?}");
+    };
+    assert_eq!((status, meta.as_str()), (51, "Not found"));
+
+    let (port, _) = server(Some("cert"), "HTTP/1.1 200 OK\r\n");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/"), "");
+    assert!(matches!(resp, FetchResponse::Error(_)));
+}
+
+#[test]
+fn trusts_the_first_certificate() {
+    let fetcher = fetcher();
+
+    let (port, _) = server(Some("first"), "20\r\nhi\n");
+    fetched(get(&fetcher, &format!("gemini://127.0.0.1:{port}/"), ""));
+
+    // Hosts are trusted whatever port they are on
+    let (port, request) = server(Some("second"), "20\r\nhi\n");
+    let resp = get(&fetcher, &format!("gemini://127.0.0.1:{port}/"), "");
+    let FetchResponse::CertChanged(host) = resp else {
+        panic!("Expected the change to be caught, got {resp:?}");
+    };
+    assert_eq!(host, "127.0.0.1");
+    // Nothing is sent to a host we do not trust
+    assert_eq!(request.recv().unwrap(), "");
+
+    let resp = run_fetch(&fetcher, FetchActions::Forget("127.0.0.1".to_string()));
+    assert!(matches!(resp, FetchResponse::Forgotten));
+    let resp = run_fetch(&fetcher, FetchActions::Forget("127.0.0.1".to_string()));
+    assert!(matches!(resp, FetchResponse::Error(ERR_NOT_TRUSTED)));
+    let (port, _) = server(Some("second"), "20\r\nhi\n");
+    fetched(get(&fetcher, &format!("gemini://127.0.0.1:{port}/"), ""));
+}
+
+#[test]
+fn truncates_long_pages() {
+    let long: &'static str = "x\n".repeat(5000).leak();
+    let (port, _) = server(None, long);
+    let page = fetched(get(
+        &fetcher(),
+        &format!("gopher://127.0.0.1:{port}/0/long"),
+        "",
+    ));
+
+    assert!(page.truncated);
+    assert_eq!(page.lines.len(), 4096);
+}
+
+#[test]
+fn gopher_menus_and_files() {
+    let fetcher = fetcher();
+
+    let (port, request) = server(None, "1Docs\t/docs\t127.0.0.1\t70\r\n.\r\n");
+    let page = fetched(get(&fetcher, &format!("gopher://127.0.0.1:{port}"), ""));
+    assert_eq!(request.recv().unwrap(), "\r\n");
+    assert_eq!(page.lines, [link("gopher://127.0.0.1/1/docs", "Docs")]);
+
+    let (port, request) = server(None, "Hello\r\nthere\r\n.\r\n");
+    let url = format!("gopher://127.0.0.1:{port}/0/hello%20world.txt");
+    let page = fetched(get(&fetcher, &url, ""));
+    assert_eq!(request.recv().unwrap(), "/hello world.txt\r\n");
+    assert_eq!(page.lines, [text("Hello"), text("there")]);
+
+    // Searches send the query after a tab
+    let (port, request) = server(None, "iNo results\t\terror.host\t1\r\n.\r\n");
+    let url = format!("gopher://127.0.0.1:{port}/7/search");
+    fetched(get(&fetcher, &url, "calculators"));
+    assert_eq!(request.recv().unwrap(), "/search\tcalculators\r\n");
+
+    let resp = get(&fetcher, &format!("gopher://127.0.0.1:{port}/9/a.tgz"), "");
+    assert!(matches!(resp, FetchResponse::Error(ERR_NOT_TEXT)));
+}
+
+#[test]
+fn bad_urls() {
+    let fetcher = fetcher();
+
+    for url in [
+        "https://example.com",
+        "example.com",
+        "gemini://",
+        "gopher://a:b/",
+    ] {
+        let resp = get(&fetcher, url, "");
+        assert!(matches!(resp, FetchResponse::Error(ERR_BAD_URL)), "{url}");
+    }
+
+    let long = format!("gemini://example.com/{}", "a".repeat(1024));
+    let resp = get(&fetcher, &long, "");
+    assert!(matches!(resp, FetchResponse::Error(ERR_BAD_URL)));
+}
+
+#[test]
+fn without_tls() {
+    let fetcher = Mutex::new(Fetcher::new(TIMEOUT, None, Box::new(Certs::default())));
+
+    let resp = get(&fetcher, "gemini://example.org/", "");
+    assert!(matches!(resp, FetchResponse::Error(ERR_NO_TLS)));
+}
@@ -0,0 +1,112 @@
+use middlesp_sockets::HtmlText;
+
+const BASE: &str = "http://example.com/docs/index.html";
+
+fn convert(html: &str) -> (String, Vec<String>) {
+    let mut text = HtmlText::new(BASE, 4096);
+    text.feed(html.as_bytes());
+    text.finish()
+}
+
+#[test]
+fn drops_scripts_and_styles() {
+    let (text, _) = convert(
+        "<html><head><title>Page</title><style>p { color: red }</style></head>\
+         <body><script>if (a < b) { document.write('<p>hi</p>') }</script>\
+         <p>Hello</p><!-- <p>hidden</p> --><SCRIPT type=\"x\">x</SCRIPT></body></html>",
+    );
+
+    assert_eq!(text, "Hello");
+}
+
+#[test]
+fn keeps_blocks_on_their_own_lines() {
+    let (text, _) = convert(
+        "<h1>Title</h1><p>First   paragraph\n spread over lines.</p>\
+         <p>Second<br>line</p><h4>Small</h4><div>a</div><div>b</div>",
+    );
+
+    assert_eq!(
+        text,
+        "# Title\n\nFirst paragraph spread over lines.\n\nSecond\nline\n\n### Small\n\na\nb"
+    );
+}
+
+#[test]
+fn lists() {
+    let (text, _) = convert(
+        "<ul><li>one</li><li>two<ol start=\"3\"><li>three</li><li>four</li></ol></li></ul>\
+         <p>after</p>",
+    );
+
+    assert_eq!(text, "* one\n* two\n  3. three\n  4. four\n\nafter");
+}
+
+#[test]
+fn numbers_links() {
+    let (text, links) = convert(
+        "<p>See <a href=\"guide.html\">the guide</a>, <a href='/'>home</a> and \
+         <a href=\"https://other.org/?a=1&amp;b=2\">elsewhere</a>. \
+         <a href=\"#top\">Top</a> <a name=\"x\">anchor</a></p>",
+    );
+
+    assert_eq!(
+        text,
+        "See the guide [1], home [2] and elsewhere [3]. Top anchor"
+    );
+    assert_eq!(
+        links,
+        [
+            "http://example.com/docs/guide.html",
+            "http://example.com/",
+            "https://other.org/?a=1&b=2",
+        ]
+    );
+}
+
+#[test]
+fn decodes_entities() {
+    let (text, _) = convert("<p>1 &lt; 2 &amp;&amp; 3&nbsp;&gt; 2 &#169; &#x41; &bogus; & x</p>");
+
+    assert_eq!(text, "1 < 2 && 3 > 2 © A &bogus; & x");
+}
+
+#[test]
+fn keeps_preformatted_whitespace() {
+    let (text, _) = convert("<p>a</p><pre>x  = 1\n  y = 2</pre><p>b   c</p>");
+
+    assert_eq!(text, "a\n\nx  = 1\n  y = 2\n\nb c");
+}
+
+#[test]
+fn handles_pages_arriving_in_pieces() {
+    let html = "<h2 class=\"x\">Caf\u{e9}</h2><p>Fish &amp; chips <a href=\"/menu\">menu</a>\
+                </p><script>let s = '</scr' + 'ipt>';</script><p>end</p>";
+    let whole = convert(html);
+
+    // Every way of splitting it in two, and a byte at a time
+    for at in 1..html.len() {
+        let mut text = HtmlText::new(BASE, 4096);
+        text.feed(&html.as_bytes()[..at]);
+        text.feed(&html.as_bytes()[at..]);
+        assert_eq!(text.finish(), whole, "split at {at}");
+    }
+
+    let mut text = HtmlText::new(BASE, 4096);
+    for b in html.as_bytes() {
+        text.feed(&[*b]);
+    }
+    assert_eq!(text.finish(), whole);
+    assert_eq!(whole.0, "## Café\n\nFish & chips menu [1]\n\nend");
+}
+
+#[test]
+fn stops_once_full() {
+    let mut text = HtmlText::new(BASE, 10);
+    text.feed(b"<p>abcdef</p><p><a href=\"/x\">ghijkl</a></p>");
+    assert!(text.is_full());
+
+    let (text, links) = text.finish();
+    assert_eq!(text, "abcdef\n\ngh");
+    assert!(links.is_empty());
+}
@@ -0,0 +1,177 @@
+use middlesp_proto::http::{JsonValue, Number};
+use middlesp_sockets::JsonSelect;
+
+const WEATHER: &str = r#"{
+    "place": {"name": "Café \"Nord\"", "tags": ["a", "b"]},
+    "current": {"temp": -3.25, "wind": 12, "raining": false, "note": null},
+    "hourly": {"temp": [1.5, 2, -0.5e1, 3E2], "mixed": [1, "x"]},
+    "a/b": {"~c": 7},
+    "list": [{"id": 10}, {"id": 20, "name": "second"}]
+}"#;
+
+fn select(json: &str, selectors: &[&str]) -> Vec<JsonValue> {
+    let selectors: Vec<_> = selectors.iter().map(|s| s.to_string()).collect();
+    let mut select = JsonSelect::new(&selectors).unwrap();
+    select.feed(json.as_bytes());
+    select.finish()
+}
+
+fn num(mantissa: i32, exponent: i8) -> Number {
+    Number { mantissa, exponent }
+}
+
+#[test]
+fn json_pointers() {
+    assert_eq!(
+        select(
+            WEATHER,
+            &[
+                "/current/temp",
+                "/current/raining",
+                "/current/note",
+                "/place/name",
+                "/list/1/name",
+                "/a~1b/~0c",
+                "/current/missing",
+            ]
+        ),
+        [
+            JsonValue::Number(num(-325, -2)),
+            JsonValue::Bool(false),
+            JsonValue::Null,
+            JsonValue::String("Café \"Nord\"".to_string()),
+            JsonValue::String("second".to_string()),
+            JsonValue::Number(num(7, 0)),
+            JsonValue::Missing,
+        ]
+    );
+}
+
+#[test]
+fn json_paths() {
+    assert_eq!(
+        select(
+            WEATHER,
+            &[
+                "$.current.wind",
+                "$['hourly'][\"temp\"][2]",
+                "$.list[0].id",
+                "$.list[0]"
+            ]
+        ),
+        [
+            JsonValue::Number(num(12, 0)),
+            JsonValue::Number(num(-5, 0)),
+            JsonValue::Number(num(1, 1)),
+            JsonValue::Other,
+        ]
+    );
+}
+
+#[test]
+fn arrays_of_numbers() {
+    assert_eq!(
+        select(
+            WEATHER,
+            &[
+                "/hourly/temp",
+                "$.hourly.mixed",
+                "/place/tags",
+                "$.hourly.temp"
+            ]
+        ),
+        [
+            JsonValue::Numbers(vec![num(15, -1), num(2, 0), num(-5, 0), num(3, 2)]),
+            JsonValue::Other,
+            JsonValue::Other,
+            JsonValue::Numbers(vec![num(15, -1), num(2, 0), num(-5, 0), num(3, 2)]),
+        ]
+    );
+}
+
+#[test]
+fn rejects_bad_selectors() {
+    for bad in ["current", "$.", "$[1", "$['a]", "$[x]", "$..a"] {
+        assert!(JsonSelect::new(&[bad.to_string()]).is_none(), "{bad}");
+    }
+    assert!(JsonSelect::new(&[]).is_none());
+}
+
+#[test]
+fn numbers() {
+    let cases = [
+        ("0", num(0, 0)),
+        ("-0.0", num(0, 0)),
+        ("100", num(1, 2)),
+        ("0.001", num(1, -3)),
+        ("123456789012", num(123456789, 3)),
+        ("0.1234567895", num(12345679, -8)),
+        ("999999999.9", num(1, 9)),
+        ("6.02214076e23", num(602214076, 15)),
+        ("1e-200", num(0, 0)),
+    ];
+
+    for (json, want) in cases {
+        assert_eq!(select(json, &[""]), [JsonValue::Number(want)], "{json}");
+    }
+    assert_eq!(select("1e200", &[""]), [JsonValue::Other]);
+}
+
+#[test]
+fn formats_numbers() {
+    let cases = [
+        (num(0, 0), "0"),
+        (num(-325, -2), "-3.25"),
+        (num(5, -3), "0.005"),
+        (num(-5, -3), "-0.005"),
+        (num(12, 3), "12000"),
+        (num(15, -20), "15E-20"),
+        (num(7, 30), "7E30"),
+    ];
+
+    for (n, want) in cases {
+        assert_eq!(n.to_string(), want);
+    }
+}
+
+#[test]
+fn handles_documents_arriving_in_pieces() {
+    let selectors = ["/place/name", "/hourly/temp", "/list/1/id"].map(String::from);
+    let whole = select(WEATHER, &selectors.each_ref().map(|s| s.as_str()));
+
+    for at in 1..WEATHER.len() {
+        let mut select = JsonSelect::new(&selectors).unwrap();
+        select.feed(&WEATHER.as_bytes()[..at]);
+        select.feed(&WEATHER.as_bytes()[at..]);
+        assert_eq!(select.finish(), whole, "split at {at}");
+    }
+}
+
+#[test]
+fn stops_once_everything_is_found() {
+    let mut select = JsonSelect::new(&["/first".to_string()]).unwrap();
+    select.feed(br#"{"first": true, "rest": ["#);
+    assert!(select.is_done());
+
+    // An array is only done once it ends
+    let mut select = JsonSelect::new(&["/a".to_string()]).unwrap();
+    select.feed(br#"{"a": [1, 2"#);
+    assert!(!select.is_done());
+    select.feed(b"]");
+    assert!(select.is_done());
+    assert_eq!(
+        select.finish(),
+        [JsonValue::Numbers(vec![num(1, 0), num(2, 0)])]
+    );
+}
+
+#[test]
+fn first_match_wins_and_long_strings_are_cut_off() {
+    let long = "x".repeat(5000);
+    let json = format!(r#"{{"{long}": 1, "s": "{long}", "a": 1, "a": 2}}"#);
+    let values = select(&json, &["/a", "/s", &format!("/{}", &long[..1024])]);
+
+    assert_eq!(values[0], JsonValue::Number(num(1, 0)));
+    assert_eq!(values[1], JsonValue::String("x".repeat(1024)));
+    assert_eq!(values[2], JsonValue::Missing);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use middlesp_proto::tcp::{Handle, TcpActions, TcpResponse};
+use middlesp_sockets::{run_tcp, Sockets};
+
+use common::{sockets, ID, TIMEOUT};
+
+/// Listens on any free port, returning the handle and port
+fn listen(sockets: &Mutex<Sockets>) -> (Handle, u16) {
+    match run_tcp(sockets, ID, TcpActions::Listen(0)) {
+        TcpResponse::Listening { handle, port } => (handle, port),
+        resp => panic!("Failed to listen: {resp:?}"),
+    }
+}
+
+/// Polls for accepted connections the way the main loop does, until there is
+/// at least one or a second has gone by
+fn accept(sockets: &Mutex<Sockets>) -> Vec<(u8, TcpResponse)> {
+    let start = Instant::now();
+    loop {
+        let accepted = sockets.lock().unwrap().accept();
+        if !accepted.is_empty() || start.elapsed() > Duration::from_secs(1) {
+            return accepted;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn accepts_and_talks() {
+    let sockets = sockets(4);
+    let (listener, port) = listen(&sockets);
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let accepted = accept(&sockets);
+
+    let [(
+        id,
+        TcpResponse::Accepted {
+            listener: from,
+            handle,
+            addr,
+            port: client_port,
+        },
+    )] = accepted.as_slice()
+    else {
+        panic!("Expected one connection, got {accepted:?}");
+    };
+    assert_eq!(*id, ID);
+    assert_eq!(*from, listener);
+    assert_ne!(*handle, listener);
+    assert_eq!(*addr, [127, 0, 0, 1]);
+    assert_eq!(*client_port, client.local_addr().unwrap().port());
+
+    client.write_all(b"ping").unwrap();
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(*handle, 100));
+    assert!(matches!(resp, TcpResponse::Data(data) if data == b"ping"));
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Write(*handle, b"pong".to_vec()));
+    assert!(matches!(resp, TcpResponse::Written));
+    let mut buf = [0; 4];
+    client.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[test]
+fn nothing_waiting() {
+    let sockets = sockets(4);
+    listen(&sockets);
+
+    assert!(sockets.lock().unwrap().accept().is_empty());
+}
+
+#[test]
+fn turns_away_past_the_limit() {
+    let sockets = sockets(2);
+    let (_, port) = listen(&sockets);
+
+    let _first = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    assert_eq!(accept(&sockets).len(), 1);
+    assert_eq!(sockets.lock().unwrap().len(), 2);
+
+    let mut second = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    second.set_read_timeout(Some(TIMEOUT)).unwrap();
+    assert!(accept(&sockets).is_empty());
+    assert_eq!(sockets.lock().unwrap().len(), 2);
+
+    // The module hangs up on it rather than leaving it waiting
+    let mut buf = [0; 1];
+    match second.read(&mut buf) {
+        Ok(0) => {}
+        Err(e) if e.kind() == ErrorKind::ConnectionReset => {}
+        res => panic!("The connection was not closed: {res:?}"),
+    }
+}
+
+#[test]
+fn closing_the_listener_keeps_connections() {
+    let sockets = sockets(4);
+    let (listener, port) = listen(&sockets);
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let accepted = accept(&sockets);
+    let [(_, TcpResponse::Accepted { handle, .. })] = accepted.as_slice() else {
+        panic!("Expected one connection, got {accepted:?}");
+    };
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Close(listener));
+    assert!(matches!(resp, TcpResponse::Closed));
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+
+    client.write_all(b"still here").unwrap();
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(*handle, 100));
+    assert!(matches!(resp, TcpResponse::Data(data) if data == b"still here"));
+}
+
+#[test]
+fn listener_is_not_a_stream() {
+    let sockets = sockets(4);
+    let (listener, _) = listen(&sockets);
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(listener, 100));
+    assert!(matches!(resp, TcpResponse::Error(_)));
+}
@@ -0,0 +1,310 @@
+mod common;
+
+use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use middlesp_proto::{
+    mqtt::{MqttActions, MqttConfig, MqttMessage, MqttResponse, Qos, ERR_NOT_SUBSCRIBED},
+    tcp::{Handle, ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+};
+use middlesp_sockets::{run_mqtt, topic_matches, Connectors, MqttClient, Sockets, Subscriptions};
+
+use common::{sockets_with, TIMEOUT};
+
+/// Stands in for a broker with only us connected, so whatever we publish
+/// comes straight back to our own subscriptions. Publishing to `bye` hangs
+/// up.
+struct Loopback(Arc<Subscriptions>);
+
+impl MqttClient for Loopback {
+    fn publish(&mut self, topic: &str, payload: &[u8], _: Qos, _: bool) -> io::Result<()> {
+        if topic == "bye" {
+            self.0.close();
+        } else {
+            self.0.push(MqttMessage {
+                topic: topic.to_string(),
+                payload: payload.to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, filter: &str, _: Qos) -> io::Result<()> {
+        if filter.is_empty() {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, _: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn connect_loopback(
+    config: &MqttConfig,
+    _: Duration,
+    subscriptions: Arc<Subscriptions>,
+) -> io::Result<Box<dyn MqttClient>> {
+    if !config.url.starts_with("mqtt://") || config.password != "secret" {
+        return Err(ErrorKind::ConnectionRefused.into());
+    }
+
+    Ok(Box::new(Loopback(subscriptions)))
+}
+
+fn sockets(max: usize) -> Mutex<Sockets> {
+    sockets_with(
+        max,
+        Connectors {
+            mqtt: Some(connect_loopback),
+            ..Default::default()
+        },
+    )
+}
+
+fn config(url: &str) -> MqttConfig {
+    MqttConfig {
+        url: url.to_string(),
+        client_id: String::new(),
+        username: "calc".to_string(),
+        password: "secret".to_string(),
+    }
+}
+
+fn connect(sockets: &Mutex<Sockets>) -> Handle {
+    match run_mqtt(sockets, MqttActions::Connect(config("mqtt://broker"))) {
+        MqttResponse::Connected(handle) => handle,
+        resp => panic!("Failed to connect: {resp:?}"),
+    }
+}
+
+fn subscribe(sockets: &Mutex<Sockets>, handle: Handle, filter: &str) -> MqttResponse {
+    run_mqtt(
+        sockets,
+        MqttActions::Subscribe {
+            handle,
+            filter: filter.to_string(),
+            qos: Qos::AtLeastOnce,
+        },
+    )
+}
+
+fn publish(sockets: &Mutex<Sockets>, handle: Handle, topic: &str, payload: &str) {
+    let resp = run_mqtt(
+        sockets,
+        MqttActions::Publish {
+            handle,
+            topic: topic.to_string(),
+            payload: payload.as_bytes().to_vec(),
+            qos: Qos::AtMostOnce,
+            retain: false,
+        },
+    );
+    assert!(matches!(resp, MqttResponse::Published));
+}
+
+fn recv(sockets: &Mutex<Sockets>, handle: Handle, filter: &str) -> MqttResponse {
+    run_mqtt(
+        sockets,
+        MqttActions::Recv {
+            handle,
+            filter: filter.to_string(),
+        },
+    )
+}
+
+/// The payload of the next message for `filter`
+fn payload(sockets: &Mutex<Sockets>, handle: Handle, filter: &str) -> String {
+    match recv(sockets, handle, filter) {
+        MqttResponse::Message(msg) => String::from_utf8(msg.payload).unwrap(),
+        resp => panic!("Expected a message, got {resp:?}"),
+    }
+}
+
+#[test]
+fn filters() {
+    assert!(topic_matches("lab/1/temp", "lab/1/temp"));
+    assert!(!topic_matches("lab/1/temp", "lab/2/temp"));
+    assert!(topic_matches("lab/+/temp", "lab/2/temp"));
+    assert!(!topic_matches("lab/+/temp", "lab/2/humidity"));
+    assert!(!topic_matches("lab/+", "lab/2/temp"));
+    assert!(topic_matches("lab/#", "lab/2/temp"));
+    assert!(topic_matches("lab/#", "lab"));
+    assert!(topic_matches("#", "lab/2/temp"));
+    assert!(!topic_matches("#", "$SYS/uptime"));
+    assert!(topic_matches("$SYS/#", "$SYS/uptime"));
+    assert!(!topic_matches("lab/1", "lab/1/temp"));
+}
+
+#[test]
+fn queues_per_subscription() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+
+    assert!(matches!(
+        subscribe(&sockets, handle, "lab/+/temp"),
+        MqttResponse::Subscribed
+    ));
+    assert!(matches!(
+        subscribe(&sockets, handle, "lab/1/#"),
+        MqttResponse::Subscribed
+    ));
+
+    publish(&sockets, handle, "lab/1/temp", "21.5");
+    publish(&sockets, handle, "lab/2/temp", "19.0");
+    publish(&sockets, handle, "lab/1/humidity", "40");
+    publish(&sockets, handle, "office/temp", "23.0");
+
+    // Each subscription gets its own copy of what matches it
+    assert_eq!(payload(&sockets, handle, "lab/+/temp"), "21.5");
+    assert_eq!(payload(&sockets, handle, "lab/+/temp"), "19.0");
+    assert!(matches!(
+        recv(&sockets, handle, "lab/+/temp"),
+        MqttResponse::Empty
+    ));
+
+    let MqttResponse::Message(msg) = recv(&sockets, handle, "lab/1/#") else {
+        panic!("Nothing was queued");
+    };
+    assert_eq!(msg.topic, "lab/1/temp");
+    assert_eq!(payload(&sockets, handle, "lab/1/#"), "40");
+}
+
+#[test]
+fn drops_the_oldest_when_full() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+    subscribe(&sockets, handle, "count");
+
+    for i in 0..20 {
+        publish(&sockets, handle, "count", &i.to_string());
+    }
+
+    // 16 are kept
+    assert_eq!(payload(&sockets, handle, "count"), "4");
+}
+
+#[test]
+fn waits_when_empty() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+    subscribe(&sockets, handle, "quiet");
+
+    let start = Instant::now();
+    assert!(matches!(
+        recv(&sockets, handle, "quiet"),
+        MqttResponse::Empty
+    ));
+    assert!(start.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn unsubscribing() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+    subscribe(&sockets, handle, "a");
+    publish(&sockets, handle, "a", "kept until unsubscribed");
+
+    let resp = run_mqtt(
+        &sockets,
+        MqttActions::Unsubscribe {
+            handle,
+            filter: "a".to_string(),
+        },
+    );
+    assert!(matches!(resp, MqttResponse::Unsubscribed));
+
+    let resp = recv(&sockets, handle, "a");
+    assert!(matches!(resp, MqttResponse::Error(ERR_NOT_SUBSCRIBED)));
+    let resp = run_mqtt(
+        &sockets,
+        MqttActions::Unsubscribe {
+            handle,
+            filter: "a".to_string(),
+        },
+    );
+    assert!(matches!(resp, MqttResponse::Error(ERR_NOT_SUBSCRIBED)));
+}
+
+#[test]
+fn limits_subscriptions() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+
+    for i in 0..8 {
+        let resp = subscribe(&sockets, handle, &format!("topic/{i}"));
+        assert!(matches!(resp, MqttResponse::Subscribed));
+    }
+
+    // Subscribing again to the same filter is fine, a ninth is not
+    let resp = subscribe(&sockets, handle, "topic/0");
+    assert!(matches!(resp, MqttResponse::Subscribed));
+    let resp = subscribe(&sockets, handle, "topic/8");
+    assert!(matches!(resp, MqttResponse::Error(ERR_TOO_MANY)));
+}
+
+#[test]
+fn failed_subscribe_is_forgotten() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+
+    assert!(matches!(
+        subscribe(&sockets, handle, ""),
+        MqttResponse::Error(_)
+    ));
+    let resp = recv(&sockets, handle, "");
+    assert!(matches!(resp, MqttResponse::Error(ERR_NOT_SUBSCRIBED)));
+}
+
+#[test]
+fn keeps_messages_after_the_broker_disconnects() {
+    let sockets = sockets(4);
+    let handle = connect(&sockets);
+    subscribe(&sockets, handle, "#");
+
+    publish(&sockets, handle, "last", "words");
+    publish(&sockets, handle, "bye", "");
+
+    assert_eq!(payload(&sockets, handle, "#"), "words");
+    assert!(matches!(recv(&sockets, handle, "#"), MqttResponse::Closed));
+    assert!(sockets.lock().unwrap().is_empty());
+
+    let resp = recv(&sockets, handle, "#");
+    assert!(matches!(resp, MqttResponse::Error(ERR_NO_SOCKET)));
+}
+
+#[test]
+fn failed_connect() {
+    let sockets = sockets(4);
+
+    let mut wrong = config("mqtt://broker");
+    wrong.password = "guess".to_string();
+    let resp = run_mqtt(&sockets, MqttActions::Connect(wrong));
+    assert!(matches!(resp, MqttResponse::Error(_)));
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn closing() {
+    let sockets = sockets(1);
+    let handle = connect(&sockets);
+
+    let resp = run_mqtt(&sockets, MqttActions::Connect(config("mqtt://broker")));
+    assert!(matches!(resp, MqttResponse::Error(ERR_TOO_MANY)));
+
+    let resp = run_mqtt(&sockets, MqttActions::Close(handle));
+    assert!(matches!(resp, MqttResponse::Closed));
+    connect(&sockets);
+}
+
+#[test]
+fn without_mqtt() {
+    let sockets = common::sockets(4);
+
+    let resp = run_mqtt(&sockets, MqttActions::Connect(config("mqtt://broker")));
+    assert!(matches!(resp, MqttResponse::Error(ERR_OTHER)));
+}
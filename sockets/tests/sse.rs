@@ -0,0 +1,296 @@
+mod common;
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use middlesp_proto::{
+    http::Headers,
+    sse::{SseActions, SseEvent, SseResponse},
+    tcp::{Handle, ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+};
+use middlesp_sockets::{run_sse, Connectors, EventStream, Sockets, SseParser};
+
+use common::sockets_with;
+
+/// What a test server does with each connection, given the `Last-Event-ID`
+/// it was sent (empty if there was none)
+type Script = Box<dyn FnOnce(TcpStream, &str) + Send>;
+
+/// Stands in for an http client, connecting to `sse://127.0.0.1:port` and
+/// sending the last id as the only line of the request
+fn connect_plain(
+    url: &str,
+    _: &Headers,
+    last_id: Option<&str>,
+    timeout: Duration,
+) -> io::Result<EventStream> {
+    let addr = url
+        .strip_prefix("sse://")
+        .ok_or(io::Error::from(ErrorKind::InvalidInput))?;
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(timeout))?;
+    writeln!(stream, "{}", last_id.unwrap_or_default())?;
+
+    Ok(Box::new(stream))
+}
+
+/// Starts a server on a free local port which runs each script on a
+/// connection in turn, refusing any more once they have all run. Returns the
+/// url and the ids each connection sent.
+fn server(scripts: Vec<Script>) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("sse://{}", listener.local_addr().unwrap());
+    let (ids, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for script in scripts {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+
+            let id = line.trim_end().to_string();
+            let _ = ids.send(id.clone());
+            script(stream, &id);
+        }
+    });
+
+    (url, rx)
+}
+
+/// Keeps the connection open until the other end closes it
+fn wait_for_close(mut stream: TcpStream) {
+    let mut buf = [0; 16];
+    while !matches!(stream.read(&mut buf), Ok(0) | Err(_)) {}
+}
+
+fn sockets(max: usize) -> Mutex<Sockets> {
+    sockets_with(
+        max,
+        Connectors {
+            sse: Some(connect_plain),
+            ..Default::default()
+        },
+    )
+}
+
+fn subscribe(sockets: &Mutex<Sockets>, url: &str) -> SseResponse {
+    run_sse(
+        sockets,
+        SseActions::Subscribe {
+            url: url.to_string(),
+            headers: Vec::new(),
+        },
+    )
+}
+
+fn subscribed(sockets: &Mutex<Sockets>, url: &str) -> Handle {
+    match subscribe(sockets, url) {
+        SseResponse::Subscribed(handle) => handle,
+        resp => panic!("Failed to subscribe: {resp:?}"),
+    }
+}
+
+/// The next event, waiting through a few empty reads for it
+fn event(sockets: &Mutex<Sockets>, handle: Handle) -> SseEvent {
+    for _ in 0..10 {
+        match run_sse(sockets, SseActions::Recv(handle)) {
+            SseResponse::Event(event) => return event,
+            SseResponse::Empty => {}
+            resp => panic!("Expected an event, got {resp:?}"),
+        }
+    }
+
+    panic!("No event arrived");
+}
+
+fn message(data: &str, id: &str) -> SseEvent {
+    SseEvent {
+        event: "message".to_string(),
+        data: data.to_string(),
+        id: id.to_string(),
+    }
+}
+
+#[test]
+fn parsing() {
+    let mut parser = SseParser::default();
+    let events = parser.feed(
+        b": keep alive\n\
+          data: first\n\
+          data:second\n\
+          \n\
+          event: score\n\
+          id: 42\n\
+          data\n\
+          \n\
+          retry: 1500\n\
+          data: no blank line yet\n",
+    );
+
+    assert_eq!(
+        events,
+        [
+            message("first\nsecond", ""),
+            SseEvent {
+                event: "score".to_string(),
+                data: String::new(),
+                id: "42".to_string(),
+            },
+        ]
+    );
+    assert_eq!(parser.retry(), Some(Duration::from_millis(1500)));
+    assert_eq!(parser.feed(b"\n"), [message("no blank line yet", "42")]);
+
+    // Events without data are not sent on, but their id is kept
+    assert!(parser.feed(b"id: 43\n\n").is_empty());
+    assert_eq!(parser.last_id(), "43");
+
+    // Nor are ids with a NUL in them, or retries which are not a number
+    assert!(parser.feed(b"id: 4\0\nretry: 1s\n\n").is_empty());
+    assert_eq!(parser.last_id(), "43");
+    assert_eq!(parser.retry(), Some(Duration::from_millis(1500)));
+}
+
+#[test]
+fn line_endings() {
+    let mut parser = SseParser::default();
+
+    // CRLF split across reads still ends just the one line
+    assert!(parser.feed(b"data: a\r").is_empty());
+    assert!(parser.feed(b"\ndata: b\r").is_empty());
+    assert_eq!(parser.feed(b"\r"), [message("a\nb", "")]);
+    assert_eq!(parser.feed(b"data: c\n\r\n"), [message("c", "")]);
+}
+
+#[test]
+fn discards_half_sent_events() {
+    let mut parser = SseParser::default();
+    assert!(parser.feed(b"event: cut\ndata: half\ndata: li").is_empty());
+
+    parser.discard_pending();
+    assert_eq!(parser.feed(b"data: whole\n\n"), [message("whole", "")]);
+}
+
+#[test]
+fn cuts_off_long_data() {
+    let mut parser = SseParser::default();
+    let long = "x".repeat(5000);
+    let events = parser.feed(format!("data: {long}\ndata: {long}\n\n").as_bytes());
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].data.len(), 4096);
+
+    // Short lines which never end the event stop being kept too
+    for _ in 0..10_000 {
+        assert!(parser.feed(b"data: endless\n").is_empty());
+    }
+    assert_eq!(parser.feed(b"\n")[0].data.len(), 4096);
+}
+
+#[test]
+fn receives() {
+    let (url, _) = server(vec![Box::new(|mut stream, _| {
+        stream
+            .write_all(b"event: goal\ndata: 1-0\n\ndata: 2-0\n\n")
+            .unwrap();
+        wait_for_close(stream);
+    })]);
+    let sockets = sockets(4);
+    let handle = subscribed(&sockets, &url);
+
+    let goal = event(&sockets, handle);
+    assert_eq!(goal.event, "goal");
+    assert_eq!(goal.data, "1-0");
+    assert_eq!(event(&sockets, handle), message("2-0", ""));
+
+    let resp = run_sse(&sockets, SseActions::Recv(handle));
+    assert!(matches!(resp, SseResponse::Empty));
+}
+
+#[test]
+fn reconnects_with_the_last_id() {
+    let (url, ids) = server(vec![
+        Box::new(|mut stream, _| {
+            // Dropped part way through the second event
+            stream
+                .write_all(b"retry: 10\nid: 5\ndata: one\n\nid: 6\ndata: lost")
+                .unwrap();
+        }),
+        Box::new(|mut stream, id| {
+            write!(stream, "data: after {id}\n\n").unwrap();
+            wait_for_close(stream);
+        }),
+    ]);
+    let sockets = sockets(4);
+    let handle = subscribed(&sockets, &url);
+
+    assert_eq!(event(&sockets, handle), message("one", "5"));
+    assert_eq!(event(&sockets, handle), message("after 5", "5"));
+    assert_eq!(ids.iter().take(2).collect::<Vec<_>>(), ["", "5"]);
+}
+
+#[test]
+fn gives_up_reconnecting() {
+    let (url, _) = server(vec![Box::new(|mut stream, _| {
+        stream.write_all(b"retry: 10\ndata: last\n\n").unwrap();
+    })]);
+    let sockets = sockets(4);
+    let handle = subscribed(&sockets, &url);
+
+    // What arrived is kept until taken, then the stream is closed
+    assert_eq!(event(&sockets, handle), message("last", ""));
+    let resp = run_sse(&sockets, SseActions::Recv(handle));
+    assert!(matches!(resp, SseResponse::Closed));
+    assert!(sockets.lock().unwrap().is_empty());
+
+    let resp = run_sse(&sockets, SseActions::Recv(handle));
+    assert!(matches!(resp, SseResponse::Error(ERR_NO_SOCKET)));
+}
+
+#[test]
+fn closing_hangs_up() {
+    let (hung_up, rx) = mpsc::channel();
+    let (url, _) = server(vec![Box::new(move |stream, _| {
+        wait_for_close(stream);
+        hung_up.send(()).unwrap();
+    })]);
+    let sockets = sockets(1);
+    let handle = subscribed(&sockets, &url);
+
+    let resp = subscribe(&sockets, &url);
+    assert!(matches!(resp, SseResponse::Error(ERR_TOO_MANY)));
+
+    let resp = run_sse(&sockets, SseActions::Close(handle));
+    assert!(matches!(resp, SseResponse::Closed));
+    assert!(sockets.lock().unwrap().is_empty());
+    rx.recv_timeout(Duration::from_secs(2))
+        .expect("The stream was left open");
+
+    let resp = run_sse(&sockets, SseActions::Close(handle));
+    assert!(matches!(resp, SseResponse::Error(ERR_NO_SOCKET)));
+}
+
+#[test]
+fn failed_subscribe() {
+    // Nothing is listening once the server has gone
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("sse://{}", listener.local_addr().unwrap())
+    };
+    let sockets = sockets(4);
+
+    let resp = subscribe(&sockets, &url);
+    assert!(matches!(resp, SseResponse::Error(_)));
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn without_sse() {
+    let sockets = common::sockets(4);
+
+    let resp = subscribe(&sockets, "sse://127.0.0.1:1");
+    assert!(matches!(resp, SseResponse::Error(ERR_OTHER)));
+}
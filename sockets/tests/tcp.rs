@@ -0,0 +1,195 @@
+mod common;
+
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use middlesp_proto::tcp::{TcpActions, TcpResponse, ERR_NO_SOCKET, ERR_NO_TLS, ERR_TOO_MANY};
+use middlesp_sockets::{run_tcp, Sockets};
+
+use common::{echo_server, sockets, ID};
+
+fn open(sockets: &Mutex<Sockets>, port: u16) -> TcpResponse {
+    run_tcp(
+        sockets,
+        ID,
+        TcpActions::Open {
+            host: "localhost".to_string(),
+            port,
+            tls: false,
+        },
+    )
+}
+
+#[test]
+fn echoes() {
+    let port = echo_server();
+    let sockets = sockets(4);
+
+    let TcpResponse::Opened(handle) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Write(handle, b"hello\n".to_vec()));
+    assert!(matches!(resp, TcpResponse::Written), "{resp:?}");
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(handle, 100));
+    let TcpResponse::Data(data) = resp else {
+        panic!("Expected data, got {resp:?}");
+    };
+    assert_eq!(data, b"hello\n");
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Close(handle));
+    assert!(matches!(resp, TcpResponse::Closed), "{resp:?}");
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn reads_at_most_max() {
+    let port = echo_server();
+    let sockets = sockets(4);
+
+    let TcpResponse::Opened(handle) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+    run_tcp(&sockets, ID, TcpActions::Write(handle, b"abcdef".to_vec()));
+    // Let all of it come back before reading
+    thread::sleep(Duration::from_millis(50));
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(handle, 4));
+    assert!(
+        matches!(&resp, TcpResponse::Data(d) if d == b"abcd"),
+        "{resp:?}"
+    );
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(handle, 4));
+    assert!(
+        matches!(&resp, TcpResponse::Data(d) if d == b"ef"),
+        "{resp:?}"
+    );
+}
+
+#[test]
+fn read_times_out_with_no_data() {
+    let port = echo_server();
+    let sockets = sockets(4);
+
+    let TcpResponse::Opened(handle) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(handle, 100));
+    assert!(
+        matches!(&resp, TcpResponse::Data(d) if d.is_empty()),
+        "{resp:?}"
+    );
+}
+
+#[test]
+fn other_end_closing() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    // Accepts one connection and closes it straight away
+    thread::spawn(move || drop(listener.accept()));
+
+    let sockets = sockets(4);
+    let TcpResponse::Opened(handle) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Read(handle, 100));
+    assert!(matches!(resp, TcpResponse::Closed), "{resp:?}");
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn limits_open_sockets() {
+    let port = echo_server();
+    let sockets = sockets(2);
+
+    assert!(matches!(open(&sockets, port), TcpResponse::Opened(_)));
+    let TcpResponse::Opened(handle) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+
+    let resp = open(&sockets, port);
+    assert!(matches!(resp, TcpResponse::Error(ERR_TOO_MANY)), "{resp:?}");
+
+    // Closing one makes room again
+    run_tcp(&sockets, ID, TcpActions::Close(handle));
+    assert!(matches!(open(&sockets, port), TcpResponse::Opened(_)));
+}
+
+#[test]
+fn handles_are_not_reused_straight_away() {
+    let port = echo_server();
+    let sockets = sockets(4);
+
+    let TcpResponse::Opened(first) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+    run_tcp(&sockets, ID, TcpActions::Close(first));
+
+    let TcpResponse::Opened(second) = open(&sockets, port) else {
+        panic!("Failed to open a socket");
+    };
+    assert_ne!(first, second);
+
+    let resp = run_tcp(&sockets, ID, TcpActions::Write(first, b"stale".to_vec()));
+    assert!(
+        matches!(resp, TcpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+}
+
+#[test]
+fn unknown_handle() {
+    let sockets = sockets(4);
+
+    for action in [
+        TcpActions::Write(7, b"x".to_vec()),
+        TcpActions::Read(7, 1),
+        TcpActions::Close(7),
+    ] {
+        let resp = run_tcp(&sockets, ID, action);
+        assert!(
+            matches!(resp, TcpResponse::Error(ERR_NO_SOCKET)),
+            "{resp:?}"
+        );
+    }
+}
+
+#[test]
+fn connection_refused() {
+    // Bind and drop to find a port nothing is listening on
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let sockets = sockets(4);
+
+    let resp = open(&sockets, port);
+    assert!(
+        matches!(resp, TcpResponse::Error(code) if code > 0),
+        "{resp:?}"
+    );
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn tls_needs_a_connector() {
+    let port = echo_server();
+    let sockets = sockets(4);
+
+    let resp = run_tcp(
+        &sockets,
+        ID,
+        TcpActions::Open {
+            host: "localhost".to_string(),
+            port,
+            tls: true,
+        },
+    );
+    assert!(matches!(resp, TcpResponse::Error(ERR_NO_TLS)), "{resp:?}");
+}
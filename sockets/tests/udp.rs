@@ -0,0 +1,158 @@
+mod common;
+
+use std::net::{TcpListener, UdpSocket};
+use std::sync::Mutex;
+
+use middlesp_proto::{
+    tcp::{TcpActions, TcpResponse, ERR_NO_SOCKET, ERR_TOO_MANY},
+    udp::{UdpActions, UdpResponse},
+};
+use middlesp_sockets::{run_tcp, run_udp, Sockets};
+
+use common::{sockets, udp_echo_server, ID};
+
+/// Binds to any free port, returning the handle and port
+fn bind(sockets: &Mutex<Sockets>) -> (u8, u16) {
+    match run_udp(sockets, UdpActions::Bind(0)) {
+        UdpResponse::Bound { handle, port } => (handle, port),
+        resp => panic!("Failed to bind: {resp:?}"),
+    }
+}
+
+fn send(sockets: &Mutex<Sockets>, handle: u8, port: u16, data: &[u8]) -> UdpResponse {
+    run_udp(
+        sockets,
+        UdpActions::Send {
+            handle,
+            host: "127.0.0.1".to_string(),
+            port,
+            data: data.to_vec(),
+        },
+    )
+}
+
+#[test]
+fn echoes() {
+    let echo = udp_echo_server();
+    let sockets = sockets(4);
+    let (handle, port) = bind(&sockets);
+    assert_ne!(port, 0);
+
+    let resp = send(&sockets, handle, echo, b"ping");
+    assert!(matches!(resp, UdpResponse::Sent), "{resp:?}");
+
+    let resp = run_udp(&sockets, UdpActions::Recv(handle));
+    let UdpResponse::Received(datagram) = resp else {
+        panic!("Expected a datagram, got {resp:?}");
+    };
+    assert_eq!(datagram.addr, [127, 0, 0, 1]);
+    assert_eq!(datagram.port, echo);
+    assert_eq!(datagram.data, b"ping");
+
+    let resp = run_udp(&sockets, UdpActions::Close(handle));
+    assert!(matches!(resp, UdpResponse::Closed), "{resp:?}");
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn queues_datagrams_in_order() {
+    let sockets = sockets(4);
+    let (handle, port) = bind(&sockets);
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    for data in [&b"one"[..], b"two", b""] {
+        sender.send_to(data, ("127.0.0.1", port)).unwrap();
+    }
+
+    for expected in [&b"one"[..], b"two", b""] {
+        let resp = run_udp(&sockets, UdpActions::Recv(handle));
+        assert!(
+            matches!(&resp, UdpResponse::Received(d) if d.data == expected),
+            "{resp:?}"
+        );
+    }
+
+    let resp = run_udp(&sockets, UdpActions::Recv(handle));
+    assert!(matches!(resp, UdpResponse::Empty), "{resp:?}");
+}
+
+#[test]
+fn binds_the_port_asked_for() {
+    let free = UdpSocket::bind("0.0.0.0:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let sockets = sockets(4);
+
+    let resp = run_udp(&sockets, UdpActions::Bind(free));
+    assert!(
+        matches!(resp, UdpResponse::Bound { port, .. } if port == free),
+        "{resp:?}"
+    );
+
+    // Which is then taken
+    let resp = run_udp(&sockets, UdpActions::Bind(free));
+    assert!(
+        matches!(resp, UdpResponse::Error(code) if code > 0),
+        "{resp:?}"
+    );
+}
+
+#[test]
+fn shares_handles_and_the_limit_with_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tcp_port = listener.local_addr().unwrap().port();
+    let sockets = sockets(2);
+
+    let (udp, _) = bind(&sockets);
+    let TcpResponse::Opened(tcp) = run_tcp(
+        &sockets,
+        ID,
+        TcpActions::Open {
+            host: "127.0.0.1".to_string(),
+            port: tcp_port,
+            tls: false,
+        },
+    ) else {
+        panic!("Failed to open a tcp socket");
+    };
+    assert_ne!(udp, tcp);
+
+    let resp = run_udp(&sockets, UdpActions::Bind(0));
+    assert!(matches!(resp, UdpResponse::Error(ERR_TOO_MANY)), "{resp:?}");
+
+    // Handles only work with the kind of socket they are for
+    let resp = run_udp(&sockets, UdpActions::Recv(tcp));
+    assert!(
+        matches!(resp, UdpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+    let resp = run_udp(&sockets, UdpActions::Close(tcp));
+    assert!(
+        matches!(resp, UdpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+    let resp = run_tcp(&sockets, ID, TcpActions::Close(udp));
+    assert!(
+        matches!(resp, TcpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+    assert_eq!(sockets.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn unknown_handle() {
+    let sockets = sockets(4);
+
+    let resp = send(&sockets, 7, 1, b"x");
+    assert!(
+        matches!(resp, UdpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+    let resp = run_udp(&sockets, UdpActions::Recv(7));
+    assert!(
+        matches!(resp, UdpResponse::Error(ERR_NO_SOCKET)),
+        "{resp:?}"
+    );
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use middlesp_proto::{
+    http::Headers,
+    tcp::{ERR_NO_SOCKET, ERR_OTHER, ERR_TOO_MANY},
+    udp::{UdpActions, UdpResponse},
+    ws::{WsActions, WsMessage, WsResponse, ERR_TOO_BIG},
+};
+use middlesp_sockets::{run_udp, run_ws, Connectors, Inbox, Opcode, Sockets, WsSender};
+
+use common::{sockets_with, TIMEOUT};
+
+/// Stands in for a server which sends back whatever it gets, and hangs up
+/// when sent `bye`
+struct Echo(Arc<Inbox>);
+
+impl WsSender for Echo {
+    fn send(&mut self, msg: &WsMessage) -> io::Result<()> {
+        if *msg == WsMessage::Text("bye".to_string()) {
+            self.0.close();
+        } else {
+            self.0.push(msg.clone());
+        }
+
+        Ok(())
+    }
+}
+
+fn connect_echo(
+    url: &str,
+    headers: &Headers,
+    _: Duration,
+    inbox: Arc<Inbox>,
+) -> io::Result<Box<dyn WsSender>> {
+    if !url.starts_with("ws://") {
+        return Err(ErrorKind::ConnectionRefused.into());
+    }
+
+    // Greets with the headers it was sent, so the tests can check them
+    for (name, value) in headers {
+        inbox.push(WsMessage::Text(format!("{name}: {value}")));
+    }
+
+    if url == "ws://pieces" {
+        greet_in_pieces(&inbox);
+    }
+
+    Ok(Box::new(Echo(inbox)))
+}
+
+/// Greets in pieces, the way the firmware's client hands messages over
+fn greet_in_pieces(inbox: &Inbox) {
+    // Too long to keep
+    let long = vec![0; 20 * 1024];
+    for (i, piece) in long.chunks(4096).enumerate() {
+        inbox.push_piece(Opcode::Binary, true, long.len(), i * 4096, piece);
+    }
+
+    // One frame in three pieces, the first cut in the middle of the é
+    let text = "héllo wörld".as_bytes();
+    inbox.push_piece(Opcode::Text, true, text.len(), 0, &text[..2]);
+    inbox.push_piece(Opcode::Text, true, text.len(), 2, &text[2..7]);
+    inbox.push_piece(Opcode::Text, true, text.len(), 7, &text[7..]);
+
+    // One message in two frames
+    inbox.push_piece(Opcode::Binary, false, 2, 0, &[1, 2]);
+    inbox.push_piece(Opcode::Continuation, true, 1, 0, &[3]);
+
+    // Text which is not UTF-8
+    inbox.push_piece(Opcode::Text, true, 1, 0, &[0xff]);
+}
+
+fn sockets(max: usize) -> Mutex<Sockets> {
+    sockets_with(
+        max,
+        Connectors {
+            ws: Some(connect_echo),
+            ..Default::default()
+        },
+    )
+}
+
+fn connect(sockets: &Mutex<Sockets>, url: &str, headers: Headers) -> WsResponse {
+    run_ws(
+        sockets,
+        WsActions::Connect {
+            url: url.to_string(),
+            headers,
+        },
+    )
+}
+
+fn open(sockets: &Mutex<Sockets>) -> u8 {
+    match connect(sockets, "ws://echo", Vec::new()) {
+        WsResponse::Connected(handle) => handle,
+        resp => panic!("Failed to connect: {resp:?}"),
+    }
+}
+
+fn recv(sockets: &Mutex<Sockets>, handle: u8) -> WsResponse {
+    run_ws(sockets, WsActions::Recv(handle))
+}
+
+#[test]
+fn echoes() {
+    let sockets = sockets(4);
+    let handle = open(&sockets);
+
+    let text = WsMessage::Text("hello".to_string());
+    let binary = WsMessage::Binary(vec![0, 1, 2]);
+    for msg in [&text, &binary] {
+        let resp = run_ws(&sockets, WsActions::Send(handle, msg.clone()));
+        assert!(matches!(resp, WsResponse::Sent));
+    }
+
+    assert!(matches!(recv(&sockets, handle), WsResponse::Message(m) if m == text));
+    assert!(matches!(recv(&sockets, handle), WsResponse::Message(m) if m == binary));
+
+    let resp = run_ws(&sockets, WsActions::Close(handle));
+    assert!(matches!(resp, WsResponse::Closed));
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn sends_headers() {
+    let sockets = sockets(4);
+    let headers = vec![("Authorization".to_string(), "Bearer abc".to_string())];
+    let WsResponse::Connected(handle) = connect(&sockets, "ws://echo", headers) else {
+        panic!("Failed to connect");
+    };
+
+    let resp = recv(&sockets, handle);
+    assert!(
+        matches!(resp, WsResponse::Message(WsMessage::Text(t)) if t == "Authorization: Bearer abc")
+    );
+}
+
+#[test]
+fn waits_when_empty() {
+    let sockets = sockets(4);
+    let handle = open(&sockets);
+
+    let start = Instant::now();
+    assert!(matches!(recv(&sockets, handle), WsResponse::Empty));
+    assert!(start.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn keeps_messages_after_the_server_closes() {
+    let sockets = sockets(4);
+    let handle = open(&sockets);
+
+    run_ws(
+        &sockets,
+        WsActions::Send(handle, WsMessage::Text("last".to_string())),
+    );
+    run_ws(
+        &sockets,
+        WsActions::Send(handle, WsMessage::Text("bye".to_string())),
+    );
+
+    let resp = recv(&sockets, handle);
+    assert!(matches!(resp, WsResponse::Message(WsMessage::Text(t)) if t == "last"));
+
+    // Closed straight away rather than after waiting, and forgotten
+    let start = Instant::now();
+    assert!(matches!(recv(&sockets, handle), WsResponse::Closed));
+    assert!(start.elapsed() < TIMEOUT);
+    assert!(sockets.lock().unwrap().is_empty());
+
+    let resp = recv(&sockets, handle);
+    assert!(matches!(resp, WsResponse::Error(ERR_NO_SOCKET)));
+}
+
+#[test]
+fn drops_the_oldest_when_full() {
+    let sockets = sockets(4);
+    let handle = open(&sockets);
+
+    for i in 0..20u8 {
+        let msg = WsMessage::Binary(vec![i; 1024]);
+        run_ws(&sockets, WsActions::Send(handle, msg));
+    }
+
+    // 16 KiB are kept, so the first 4 are gone
+    let WsResponse::Message(WsMessage::Binary(first)) = recv(&sockets, handle) else {
+        panic!("Nothing was kept");
+    };
+    assert_eq!(first[0], 4);
+}
+
+#[test]
+fn puts_pieces_back_together() {
+    let sockets = sockets(4);
+    let WsResponse::Connected(handle) = connect(&sockets, "ws://pieces", Vec::new()) else {
+        panic!("Failed to connect");
+    };
+
+    assert!(matches!(
+        recv(&sockets, handle),
+        WsResponse::Error(ERR_TOO_BIG)
+    ));
+
+    let expected = [
+        WsMessage::Text("héllo wörld".to_string()),
+        WsMessage::Binary(vec![1, 2, 3]),
+        WsMessage::Binary(vec![0xff]),
+    ];
+    for msg in expected {
+        assert!(matches!(recv(&sockets, handle), WsResponse::Message(m) if m == msg));
+    }
+    assert!(matches!(recv(&sockets, handle), WsResponse::Empty));
+}
+
+#[test]
+fn failed_connect() {
+    let sockets = sockets(4);
+
+    let resp = connect(&sockets, "http://echo", Vec::new());
+    assert!(matches!(resp, WsResponse::Error(_)));
+    assert!(sockets.lock().unwrap().is_empty());
+}
+
+#[test]
+fn shares_handles_with_udp() {
+    let sockets = sockets(2);
+    let ws = open(&sockets);
+    let UdpResponse::Bound { handle: udp, .. } = run_udp(&sockets, UdpActions::Bind(0)) else {
+        panic!("Failed to bind");
+    };
+    assert_ne!(ws, udp);
+
+    let resp = connect(&sockets, "ws://echo", Vec::new());
+    assert!(matches!(resp, WsResponse::Error(ERR_TOO_MANY)));
+
+    let resp = run_ws(&sockets, WsActions::Close(udp));
+    assert!(matches!(resp, WsResponse::Error(ERR_NO_SOCKET)));
+}
+
+#[test]
+fn without_websockets() {
+    let sockets = common::sockets(4);
+
+    let resp = connect(&sockets, "ws://echo", Vec::new());
+    assert!(matches!(resp, WsResponse::Error(ERR_OTHER)));
+}
@@ -0,0 +1,21 @@
+use std::thread;
+
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use middlesp_sockets::STACK_SIZE;
+
+/// Runs `f` on its own thread so it does not hold up the main loop, the
+/// returned future resolves to `None` if the thread panicked.
+pub fn spawn<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> std::io::Result<BoxFuture<'static, Option<T>>> {
+    let (tx, rx) = oneshot::channel();
+
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            // If nobody is waiting any more there is nothing to do
+            let _ = tx.send(f());
+        })?;
+
+    Ok(rx.map(Result::ok).boxed())
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+/// Limits the module runs with
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Most requests which may be running at once
+    pub max_in_flight: usize,
+    /// Most requests which may be waiting to run, anything past this is
+    /// answered with [middlesp_proto::CalcResponse::Busy]
+    pub max_queued: usize,
+    /// How long the calculator may stay silent before we assume it has gone
+    /// (e.g. been turned off) and tear everything down, `None` never does
+    pub idle_timeout: Option<Duration>,
+    /// Whether to also stop wifi once the calculator has gone quiet. It is
+    /// started (and connected, if it was) again once the calculator is back
+    pub idle_stop_wifi: bool,
+    /// How long the calculator may stay silent while we are on non default
+    /// uart settings before we go back to the defaults, in case it has lost
+    /// track of what they are. `None` never does.
+    pub uart_fallback: Option<Duration>,
+    /// Whether to record the uart from boot, rather than only once the
+    /// calculator asks, see [middlesp_proto::trace]
+    pub trace: bool,
+    /// Most sockets the calculator may have open at once, counting TCP, UDP,
+    /// WebSocket, MQTT and event stream ones together
+    pub max_sockets: usize,
+    /// How long opening a socket, or a single read or write on one, may take
+    pub socket_timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 3,
+            max_queued: 8,
+            idle_timeout: Some(Duration::from_secs(5 * 60)),
+            idle_stop_wifi: false,
+            uart_fallback: Some(Duration::from_secs(60)),
+            trace: false,
+            max_sockets: 4,
+            socket_timeout: Duration::from_secs(5),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+use esp_idf_svc::{
+    nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault},
+    sys::{
+        esp_tls_get_ssl_context, mbedtls_sha256, mbedtls_ssl_context, mbedtls_ssl_get_peer_cert,
+        EspError,
+    },
+    tls::{Config, EspTls, InternalSocket},
+};
+use middlesp_sockets::{CertStore, Fingerprint, Stream};
+
+use crate::tcp::{io_error, Tls};
+
+/// NVS namespace the trusted certificates are kept in, apart from our
+/// settings so they can be cleared on their own
+const NVS_NAMESPACE: &str = "gemini_certs";
+/// Longest host name DNS allows
+const MAX_HOST: usize = 253;
+
+/// Opens a TLS connection to a Gemini server. No CA is given, so esp-tls
+/// skips checking the certificate (see `sdkconfig.defaults`) and the caller
+/// checks its fingerprint against the one trusted for the host instead.
+pub fn connect_gemini(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+) -> io::Result<(Box<dyn Stream>, Fingerprint)> {
+    let mut tls = EspTls::new().map_err(io_error)?;
+    tls.connect(
+        host,
+        port,
+        &Config {
+            common_name: Some(host),
+            timeout_ms: timeout.as_millis() as u32,
+            ..Config::new()
+        },
+    )
+    .map_err(io_error)?;
+
+    let fingerprint = fingerprint(&tls)?;
+    Ok((Box::new(Tls(tls)), fingerprint))
+}
+
+/// SHA-256 of the certificate the server showed
+fn fingerprint(tls: &EspTls<InternalSocket>) -> io::Result<Fingerprint> {
+    // SAFETY: the ssl context lives as long as the connection, and holds on
+    // to the peer's certificate until it is closed
+    unsafe {
+        let ssl = esp_tls_get_ssl_context(tls.context_handle()) as *const mbedtls_ssl_context;
+        if ssl.is_null() {
+            return Err(io::Error::new(ErrorKind::Other, "No ssl context"));
+        }
+        let cert = mbedtls_ssl_get_peer_cert(ssl);
+        if cert.is_null() {
+            return Err(io::Error::new(
+                ErrorKind::Other,
+                "Server showed no certificate",
+            ));
+        }
+
+        let raw = &(*cert).raw;
+        let mut hash = [0; 32];
+        match mbedtls_sha256(raw.p, raw.len as _, hash.as_mut_ptr(), 0) {
+            0 => Ok(hash),
+            code => Err(io::Error::new(
+                ErrorKind::Other,
+                format!("Hashing failed: {code}"),
+            )),
+        }
+    }
+}
+
+/// The certificates trusted for each Gemini host, kept in NVS. Keys can only
+/// be 15 characters so each is a hash of the host, with the host stored
+/// after the fingerprint to tell apart any which collide.
+pub struct NvsCerts(EspNvs<NvsDefault>);
+
+impl NvsCerts {
+    pub fn open(partition: EspDefaultNvsPartition) -> Result<Self, EspError> {
+        Ok(Self(EspNvs::new(partition, NVS_NAMESPACE, true)?))
+    }
+}
+
+impl CertStore for NvsCerts {
+    fn get(&self, host: &str) -> Option<Fingerprint> {
+        let mut buf = [0; 32 + MAX_HOST];
+        match self.0.get_raw(&key(host), &mut buf) {
+            Ok(Some(raw)) if raw.len() > 32 && &raw[32..] == host.as_bytes() => {
+                raw[..32].try_into().ok()
+            }
+            Ok(_) => None,
+            Err(e) => {
+                println!("Failed to read the certificate trusted for {host}: {e:?}");
+                None
+            }
+        }
+    }
+
+    fn set(&mut self, host: &str, fingerprint: Fingerprint) {
+        let mut raw = fingerprint.to_vec();
+        raw.extend_from_slice(host.as_bytes());
+
+        if let Err(e) = self.0.set_raw(&key(host), &raw) {
+            println!("Failed to store the certificate trusted for {host}: {e:?}");
+        }
+    }
+
+    fn forget(&mut self, host: &str) -> bool {
+        self.0.remove(&key(host)).unwrap_or_else(|e| {
+            println!("Failed to forget the certificate trusted for {host}: {e:?}");
+            false
+        })
+    }
+}
+
+/// FNV-1a of the host, which is plenty to spread hosts over keys
+fn key(host: &str) -> String {
+    let hash = host.bytes().fold(0x811c_9dc5_u32, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+
+    format!("h{hash:08x}")
+}
@@ -0,0 +1,226 @@
+use std::sync::Mutex;
+
+use esp_idf_svc::{
+    http::{
+        client::{EspHttpConnection, Response},
+        Method,
+    },
+    io::{utils::try_read_full, EspIOError, Read},
+    sys::{EspError, ESP_ERR_INVALID_ARG},
+};
+use middlesp_proto::http::{Convert, Headers, HttpReq, HttpResp, MethodWithArgs};
+use middlesp_sockets::{HtmlText, JsonSelect};
+
+use crate::http_pool::{HttpClient, HttpPool, Origin};
+
+/// Largest response body we send back to the calculator
+const BODY_SIZE: usize = 4096;
+/// Most HTML read for a [Convert::Text] body, far more than we send back as
+/// markup and scripts take up most of a page
+const HTML_SIZE: usize = 64 * 1024;
+/// Most of a JSON document read for [Convert::Json], only the values picked
+/// out of it are kept so this just stops an endless stream tying us up
+const JSON_SIZE: usize = 1024 * 1024;
+
+pub trait HeadersTrait {
+    fn as_full_ref(&self) -> Vec<(&str, &str)>;
+}
+
+impl HeadersTrait for Headers {
+    fn as_full_ref(&self) -> Vec<(&str, &str)> {
+        self.iter()
+            .map(|v| (v.0.as_str(), v.1.as_str()))
+            .collect::<Vec<_>>()
+    }
+}
+
+pub trait MethodWithArgsTrait {
+    /// Also returns whether the connection can be reused, which needs the
+    /// whole body to have been read and the server to keep it open
+    fn request<'a>(
+        self,
+        client: &'a mut HttpClient,
+        uri: &'a str,
+        close: bool,
+        convert: Convert,
+    ) -> Result<(HttpResp, bool), EspIOError>;
+}
+
+impl MethodWithArgsTrait for MethodWithArgs {
+    fn request<'a>(
+        self,
+        client: &'a mut HttpClient,
+        uri: &'a str,
+        close: bool,
+        convert: Convert,
+    ) -> Result<(HttpResp, bool), EspIOError> {
+        // Checked before anything is sent, so a bad selector costs no request
+        let json = match &convert {
+            Convert::Json(selectors) => Some(JsonSelect::new(selectors).ok_or_else(|| {
+                println!("Bad JSON selectors {selectors:?}");
+                EspIOError::from(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())
+            })?),
+            _ => None,
+        };
+
+        // Bit confusing but we need to get the lifetimes correct
+        let headers = headers(&self, close);
+
+        let request = match &self {
+            Self::Delete => {
+                println!("-> DELETE {uri}");
+                client.request(Method::Delete, uri, &headers)
+            }
+            Self::Get => {
+                println!("-> GET {uri}");
+                client.request(Method::Get, uri, &headers)
+            }
+            Self::Head(_) => {
+                println!("-> HEAD {uri}");
+                client.request(Method::Head, uri, &headers)
+            }
+            Self::Post(_, payload) => {
+                println!("-> POST {uri}");
+                client.post(uri, &headers).and_then(|mut req| {
+                    req.write(payload.as_bytes())?;
+
+                    Ok(req)
+                })
+            }
+            Self::Put(_) => {
+                println!("-> PUT {uri}");
+                client.put(uri, &headers)
+            }
+        }?;
+
+        let mut response = request.submit()?;
+        // Only a connection the server keeps open is worth putting back, an
+        // HTTP/1.0 server may still close it without saying so
+        let keep_alive = !response
+            .header("Connection")
+            .is_some_and(|c| c.eq_ignore_ascii_case("close"));
+
+        let (resp, finished) = match (convert, json) {
+            (_, Some(json)) => read_json(&mut response, json)?,
+            (Convert::Text, _) => read_text(&mut response, uri)?,
+            _ => {
+                let mut buf = [0u8; BODY_SIZE];
+                let bytes_read = try_read_full(&mut response, &mut buf).map_err(|e| e.0)?;
+
+                (
+                    HttpResp::new(Vec::from_iter(buf.into_iter().take(bytes_read))),
+                    bytes_read < BODY_SIZE,
+                )
+            }
+        };
+
+        Ok((resp, finished && keep_alive))
+    }
+}
+
+/// Turns the HTML into text as it arrives, stopping once we have as much
+/// text as we send back
+fn read_text(
+    response: &mut Response<&mut EspHttpConnection>,
+    uri: &str,
+) -> Result<(HttpResp, bool), EspIOError> {
+    let mut html = HtmlText::new(uri, BODY_SIZE);
+    let mut buf = [0u8; 1024];
+    let mut total = 0;
+
+    let finished = loop {
+        let size = response.read(&mut buf)?;
+        if size == 0 {
+            break true;
+        }
+
+        html.feed(&buf[..size]);
+        total += size;
+        if html.is_full() || total >= HTML_SIZE {
+            break false;
+        }
+    };
+
+    let (text, links) = html.finish();
+    Ok((HttpResp::text(text, links), finished))
+}
+
+/// Picks the values out of the JSON as it arrives, stopping once every
+/// selector has one
+fn read_json(
+    response: &mut Response<&mut EspHttpConnection>,
+    mut json: JsonSelect,
+) -> Result<(HttpResp, bool), EspIOError> {
+    let mut buf = [0u8; 1024];
+    let mut total = 0;
+
+    let finished = loop {
+        let size = response.read(&mut buf)?;
+        if size == 0 {
+            break true;
+        }
+
+        json.feed(&buf[..size]);
+        total += size;
+        if json.is_done() || total >= JSON_SIZE {
+            break false;
+        }
+    };
+
+    Ok((HttpResp::json(json.finish()), finished))
+}
+
+fn headers(method: &MethodWithArgs, close: bool) -> Vec<(&str, &str)> {
+    let mut headers = match method {
+        MethodWithArgs::Delete | MethodWithArgs::Get => Vec::new(),
+        MethodWithArgs::Put(h) | MethodWithArgs::Head(h) | MethodWithArgs::Post(h, _) => {
+            h.as_full_ref()
+        }
+    };
+
+    if close {
+        headers.push(("Connection", "close"));
+    }
+
+    headers
+}
+
+pub trait HttpReqTrait {
+    /// NOTE: this blocks until the whole response has been read, the pool is
+    /// only locked while taking and returning the connection
+    fn send(self, pool: &Mutex<HttpPool>) -> Result<HttpResp, EspIOError>;
+}
+
+impl HttpReqTrait for HttpReq {
+    fn send(self, pool: &Mutex<HttpPool>) -> Result<HttpResp, EspIOError> {
+        let Some(origin) = Origin::parse(&self.url) else {
+            println!("Could not get origin of {}", self.url);
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>().into());
+        };
+
+        let (mut client, pooled) = pool.lock().unwrap().take(&origin)?;
+        let res =
+            self.extra
+                .clone()
+                .request(&mut client, &self.url, self.close, self.convert.clone());
+        let (resp, reusable) = match res {
+            // HTTP/1.0 servers close connections without saying so, which we
+            // only find out once the next request fails on it
+            Err(e) if pooled => {
+                println!("Pooled connection failed with {e:?}, retrying on a new one");
+                client = HttpPool::open(&origin)?;
+                self.extra
+                    .request(&mut client, &self.url, self.close, self.convert)?
+            }
+            res => res?,
+        };
+
+        // Anything past the body we read is still waiting on the connection,
+        // so only reuse it if we know we have read everything
+        if !self.close && reusable {
+            pool.lock().unwrap().put(origin, client);
+        }
+
+        Ok(resp)
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::{Duration, Instant};
+
+use embedded_svc::http::client::Client;
+use esp_idf_svc::{
+    handle::RawHandle,
+    http::client::{Configuration as HttpConfiguration, EspHttpConnection},
+    sys::{esp, esp_http_client_set_header, EspError},
+};
+
+pub type HttpClient = Client<EspHttpConnection>;
+
+/// Most connections we keep open at once, each TLS connection holds on to a
+/// fair chunk of heap so keep this small
+const MAX_CONNECTIONS: usize = 3;
+/// Connections unused for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The scheme, host and port a connection is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    tls: bool,
+    host: String,
+    port: u16,
+}
+
+impl Origin {
+    /// Gets the origin of an absolute `http://` or `https://` uri
+    pub fn parse(uri: &str) -> Option<Self> {
+        let (scheme, rest) = uri.split_once("://")?;
+        let tls = match scheme.to_ascii_lowercase().as_str() {
+            "http" => false,
+            "https" => true,
+            _ => return None,
+        };
+
+        let authority = rest.split(['/', '?', '#']).next()?;
+        // Strip any `user:pass@`
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (host, port.parse().ok()?),
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            tls,
+            host: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+}
+
+struct Pooled {
+    origin: Origin,
+    client: HttpClient,
+    last_used: Instant,
+}
+
+/// Keeps connections alive between requests so repeated calls to the same
+/// server skip the TCP (and more importantly TLS) handshake.
+///
+/// Connections are taken out with [HttpPool::take] for the duration of a
+/// request and handed back with [HttpPool::put] once it is safe to reuse them.
+#[derive(Default)]
+pub struct HttpPool {
+    idle: Vec<Pooled>,
+}
+
+impl HttpPool {
+    /// Takes an idle connection to `origin`, opening a new one if there is
+    /// none. Also returns whether the connection was idle, as the server may
+    /// have closed it since
+    pub fn take(&mut self, origin: &Origin) -> Result<(HttpClient, bool), EspError> {
+        if let Some(i) = self.idle.iter().position(|p| &p.origin == origin) {
+            println!("Reusing connection to {}:{}", origin.host, origin.port);
+            return Ok((self.idle.swap_remove(i).client, true));
+        }
+
+        Ok((Self::open(origin)?, false))
+    }
+
+    /// Opens a new connection to `origin`, without pooling it
+    pub fn open(origin: &Origin) -> Result<HttpClient, EspError> {
+        println!("Opening connection to {}:{}", origin.host, origin.port);
+        // There are no keep-alive or TLS session options here, the TLS session
+        // lasts as long as the connection does so keeping that open is what
+        // saves the handshake
+        let config = HttpConfiguration {
+            crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
+            ..Default::default()
+        };
+        let connection = EspHttpConnection::new(&config)?;
+
+        // Headers set on the client are sent with every request, so even
+        // HTTP/1.0 servers are asked to keep the connection open
+        esp!(unsafe {
+            esp_http_client_set_header(
+                connection.handle(),
+                c"Connection".as_ptr(),
+                c"keep-alive".as_ptr(),
+            )
+        })?;
+
+        Ok(HttpClient::wrap(connection))
+    }
+
+    /// Returns a connection to the pool, closing the least recently used one
+    /// if we are over [MAX_CONNECTIONS]
+    pub fn put(&mut self, origin: Origin, client: HttpClient) {
+        if self.idle.len() >= MAX_CONNECTIONS {
+            if let Some(i) = self
+                .idle
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, p)| p.last_used)
+                .map(|(i, _)| i)
+            {
+                let old = self.idle.swap_remove(i);
+                println!("Closing connection to {}:{}", old.origin.host, old.origin.port);
+            }
+        }
+
+        self.idle.push(Pooled {
+            origin,
+            client,
+            last_used: Instant::now(),
+        });
+    }
+
+    /// Closes every connection
+    pub fn clear(&mut self) {
+        self.idle.clear();
+    }
+
+    /// Closes every connection which has been idle for over [IDLE_TIMEOUT]
+    pub fn evict_idle(&mut self) {
+        self.idle.retain(|p| {
+            let keep = p.last_used.elapsed() < IDLE_TIMEOUT;
+            if !keep {
+                println!("Closing idle connection to {}:{}", p.origin.host, p.origin.port);
+            }
+
+            keep
+        });
+    }
+}
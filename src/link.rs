@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use esp_idf_svc::hal::{delay::NON_BLOCK, uart::UartDriver};
+use middlesp_proto::{
+    frame::{self, encode, Kind, Parsed, Seen, CRC_SIZE, HEADER_SIZE, MAX_PAYLOAD, START},
+    trace::Direction,
+    Mode,
+};
+
+use crate::trace::Trace;
+
+/// How long a half received frame may sit around before we give up on it
+const RX_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long we wait for an ack before resending a frame
+const ACK_TIMEOUT: Duration = Duration::from_millis(500);
+/// How many times a frame is resent before we give up on it
+const MAX_RETRIES: u8 = 5;
+/// Most frames waiting on an ack at once
+const MAX_PENDING: usize = 16;
+/// Most frames waiting to be sent, past this new responses are dropped
+/// (unless nothing else is waiting)
+const MAX_OUTBOX: usize = 256;
+/// Smallest max frame size the calculator may ask for
+pub const MIN_FRAME: usize = 32;
+/// Longest line we accept in text mode
+const MAX_LINE: usize = 1024;
+
+/// A data frame we have sent but which has not been acked yet
+struct Pending {
+    seq: u8,
+    frame: Vec<u8>,
+    sent_at: Instant,
+    retries: u8,
+}
+
+/// Splits the uart byte stream up into frames (see [frame]) and makes sure
+/// they arrive. Data frames which are not acked within [ACK_TIMEOUT] are
+/// resent.
+///
+/// Once the calculator has set a max frame size only one data frame is sent
+/// at a time, the next goes out once the last has been acked.
+///
+/// In [Mode::Text] there are no frames, just `\r` and/or `\n` terminated
+/// lines, see [middlesp_proto::at].
+#[derive(Default)]
+pub struct Link {
+    rx: Vec<u8>,
+    last_rx: Option<Instant>,
+    next_seq: u8,
+    /// Encoded frames waiting for room in `pending`
+    outbox: VecDeque<(u8, Vec<u8>)>,
+    pending: VecDeque<Pending>,
+    seen: Seen,
+    /// Largest frame (header and crc included) the calculator can take
+    max_frame: Option<usize>,
+    trace: Trace,
+}
+
+impl Link {
+    /// Reads whatever is waiting on the uart (without blocking) and returns
+    /// the payload of the next new data frame, if there is one
+    pub fn poll_frame(&mut self, uart: &mut UartDriver<'_>) -> Option<Vec<u8>> {
+        self.read_available(uart);
+
+        loop {
+            match self.take_frame()? {
+                Parsed::Frame(Kind::Data, seq, payload) => {
+                    write_raw(uart, &mut self.trace, &encode(Kind::Ack, seq, &[]));
+
+                    if !self.seen.is_new(seq) {
+                        println!("Dropping resent frame {seq}");
+                        continue;
+                    }
+
+                    return Some(payload);
+                }
+                Parsed::Frame(Kind::Reset, seq, _) => {
+                    println!("Calculator started a new session");
+                    write_raw(uart, &mut self.trace, &encode(Kind::Ack, seq, &[]));
+                    self.seen.clear();
+                }
+                Parsed::Frame(Kind::Ack, seq, _) => {
+                    self.pending.retain(|p| p.seq != seq);
+                    self.flush(uart);
+                }
+                Parsed::Frame(Kind::Nak, seq, _) => {
+                    if let Some(p) = self.pending.iter_mut().find(|p| p.seq == seq) {
+                        println!("Frame {seq} was nak'd, resending");
+                        write_raw(uart, &mut self.trace, &p.frame);
+                        p.sent_at = Instant::now();
+                    }
+                }
+                Parsed::Corrupt(seq) => {
+                    println!("Received corrupt frame {seq}");
+                    write_raw(uart, &mut self.trace, &encode(Kind::Nak, seq, &[]));
+                }
+            }
+        }
+    }
+
+    /// Works out which mode the calculator is talking in from the first byte
+    /// it sends, skipping any line noise before it
+    pub fn detect_mode(&mut self, uart: &mut UartDriver<'_>) -> Option<Mode> {
+        self.read_available(uart);
+
+        while let Some(&b) = self.rx.first() {
+            match b {
+                START => return Some(Mode::Binary),
+                b'A' | b'a' => return Some(Mode::Text),
+                _ => {
+                    self.rx.remove(0);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Reads whatever is waiting on the uart (without blocking) and returns
+    /// the next complete line, if there is one
+    pub fn poll_line(&mut self, uart: &mut UartDriver<'_>) -> Option<String> {
+        self.read_available(uart);
+
+        loop {
+            let Some(end) = self.rx.iter().position(|b| matches!(b, b'\r' | b'\n')) else {
+                if self.rx.len() > MAX_LINE {
+                    println!("Dropping line of over {MAX_LINE} bytes");
+                    self.rx.clear();
+                }
+
+                return None;
+            };
+
+            let line = String::from_utf8_lossy(&self.rx[..end]).into_owned();
+            self.rx.drain(..=end);
+
+            // `\r\n` leaves an empty line behind
+            if !line.trim().is_empty() {
+                return Some(line);
+            }
+        }
+    }
+
+    pub fn send_line(&mut self, uart: &mut UartDriver<'_>, line: &str) {
+        write_raw(uart, &mut self.trace, line.as_bytes());
+        write_raw(uart, &mut self.trace, b"\r\n");
+    }
+
+    /// Forgets anything still waiting to be sent or acked and which frames
+    /// have been seen, for when the calculator stops talking in frames or
+    /// goes away
+    pub fn reset(&mut self) {
+        self.outbox.clear();
+        self.pending.clear();
+        self.seen.clear();
+    }
+
+    fn read_available(&mut self, uart: &mut UartDriver<'_>) {
+        let mut buf = [0u8; 256];
+        loop {
+            match uart.read(&mut buf, NON_BLOCK) {
+                Ok(0) => break,
+                Ok(size) => {
+                    self.rx.extend_from_slice(&buf[..size]);
+                    self.trace.record(Direction::In, &buf[..size]);
+                    self.last_rx = Some(Instant::now());
+                }
+                Err(e) => {
+                    println!("Error when reading from stream: {e:?}");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn take_frame(&mut self) -> Option<Parsed> {
+        let stale = self.last_rx.is_some_and(|t| t.elapsed() > RX_TIMEOUT);
+
+        frame::take_frame(&mut self.rx, stale)
+    }
+
+    pub fn trace(&mut self) -> &mut Trace {
+        &mut self.trace
+    }
+
+    /// Whether everything we have sent has been acked
+    pub fn is_idle(&self) -> bool {
+        self.outbox.is_empty() && self.pending.is_empty()
+    }
+
+    /// Sets the largest frame the calculator can take, `None` for no limit.
+    /// Returns the size actually used, which is at least [MIN_FRAME].
+    pub fn set_max_frame(&mut self, max_frame: Option<usize>) -> Option<usize> {
+        self.max_frame =
+            max_frame.map(|m| m.clamp(MIN_FRAME, HEADER_SIZE + MAX_PAYLOAD + CRC_SIZE));
+        self.max_frame
+    }
+
+    /// Largest payload which fits in a single frame, anything longer would
+    /// be taken as corrupt on the other side
+    pub fn max_payload(&self) -> usize {
+        self.max_frame
+            .map_or(MAX_PAYLOAD, |m| m - HEADER_SIZE - CRC_SIZE)
+    }
+
+    /// Queues `payloads` to be sent in data frames, which are each kept until
+    /// they are acked. They are queued all together or not at all, so the
+    /// fragments of a response never go out with one missing. Returns false
+    /// if they were dropped.
+    pub fn send(&mut self, uart: &mut UartDriver<'_>, payloads: &[Vec<u8>]) -> bool {
+        if let Some(payload) = payloads.iter().find(|p| p.len() > self.max_payload()) {
+            println!("Dropping frame of {} bytes, too big to send", payload.len());
+            return false;
+        }
+
+        // A response longer than the limit still goes out once it is alone
+        if !self.outbox.is_empty() && self.outbox.len() + payloads.len() > MAX_OUTBOX {
+            println!(
+                "Too many frames waiting to be sent, dropping {} more",
+                payloads.len()
+            );
+            return false;
+        }
+
+        for payload in payloads {
+            let seq = self.next_seq;
+            self.next_seq = self.next_seq.wrapping_add(1);
+
+            self.outbox
+                .push_back((seq, encode(Kind::Data, seq, payload)));
+        }
+        self.flush(uart);
+
+        true
+    }
+
+    /// Sends as many frames from the outbox as there is room for
+    fn flush(&mut self, uart: &mut UartDriver<'_>) {
+        // Small receive buffers can only take one frame at a time
+        let window = if self.max_frame.is_some() {
+            1
+        } else {
+            MAX_PENDING
+        };
+
+        while self.pending.len() < window {
+            let Some((seq, frame)) = self.outbox.pop_front() else {
+                return;
+            };
+
+            write_raw(uart, &mut self.trace, &frame);
+            self.pending.push_back(Pending {
+                seq,
+                frame,
+                sent_at: Instant::now(),
+                retries: 0,
+            });
+        }
+    }
+
+    /// Resends any data frames which have not been acked in time
+    pub fn resend_unacked(&mut self, uart: &mut UartDriver<'_>) {
+        self.pending.retain_mut(|p| {
+            if p.sent_at.elapsed() < ACK_TIMEOUT {
+                return true;
+            }
+
+            if p.retries >= MAX_RETRIES {
+                println!("Frame {} was never acked, giving up", p.seq);
+                return false;
+            }
+
+            println!("Frame {} was not acked, resending", p.seq);
+            write_raw(uart, &mut self.trace, &p.frame);
+            p.sent_at = Instant::now();
+            p.retries += 1;
+
+            true
+        });
+
+        self.flush(uart);
+    }
+}
+
+fn write_raw(uart: &mut UartDriver<'_>, trace: &mut Trace, buf: &[u8]) {
+    trace.record(Direction::Out, buf);
+
+    match uart.write(buf) {
+        Ok(size) if size != buf.len() => {
+            println!("Only write {size} bytes when expected {}", buf.len());
+        }
+        Err(e) => {
+            println!("Error when writing to stream: {e:?}")
+        }
+        _ => {} // Everything is fine with the world
+    }
+}
@@ -1,11 +1,27 @@
 use anyhow::Result;
+use config::Config;
 use esp_idf_svc::hal::delay;
-use spec::{wifi::WifiActions, CalcRequest};
-use state::State;
+use middlesp_proto::{
+    wifi::{WifiActions, WifiConfig},
+    CalcRequest,
+};
+use state::{State, BOOT_ID};
 
-mod safe_read;
-pub mod spec;
+mod blocking;
+pub mod config;
+mod gemini;
+mod http;
+mod http_pool;
+mod link;
+mod mdns;
+mod mqtt;
+mod sse;
 pub mod state;
+mod tcp;
+mod trace;
+mod uart;
+mod wifi;
+mod ws;
 
 /// NOTE: It seems we can actually use two threads (and make this a bunch
 /// nicer with having one thread on reading incoming and the other just waiting
@@ -20,22 +36,33 @@ fn main() -> Result<()> {
     // Bind the log crate to the ESP Logging facilities
     esp_idf_svc::log::EspLogger::initialize_default();
 
-    let mut state = State::new()?;
-    state.push_incoming(CalcRequest::Wifi(WifiActions::SetConfig(
-        esp_idf_svc::wifi::ClientConfiguration::default(),
-    )));
-    state.push_incoming(CalcRequest::Wifi(WifiActions::Start));
+    let mut state = State::new(Config::default())?;
+    state.push_incoming(
+        BOOT_ID,
+        CalcRequest::Wifi(WifiActions::SetConfig(WifiConfig::default())),
+    );
+    state.push_incoming(BOOT_ID, CalcRequest::Wifi(WifiActions::Start));
 
-    while state.is_processing() {
+    // Reads never block now, so keep serving the calculator for as long as
+    // we are powered
+    loop {
         state.read_incoming();
 
         state.try_process_incoming();
 
         state.try_send_processing();
-        // let res = state.poll_processing();
-        // println!("Response: {:?}", res);
+
+        state.accept_connections();
+
+        state.resend_unacked();
+
+        state.evict_idle_connections();
+
+        state.check_idle();
+
+        state.check_uart();
+
+        state.check_mode();
         delay::Ets::delay_ms(100);
     }
-
-    Ok(())
 }
@@ -0,0 +1,85 @@
+use std::{net::IpAddr, time::Duration};
+
+use esp_idf_svc::mdns::{EspMdns, QueryResult};
+use middlesp_proto::mdns::{MdnsActions, MdnsResponse, ServiceInstance};
+
+/// How long a browse waits for answers before returning what it found
+const BROWSE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Upper bound on the number of instances returned by a single browse
+const MAX_RESULTS: usize = 16;
+
+/// The hostname the module advertises unless told otherwise, e.g.
+/// `calc-a0b1c2d3e4f5` (which resolves as `calc-a0b1c2d3e4f5.local`)
+pub fn default_hostname(mac: [u8; 6]) -> String {
+    let mut name = String::from("calc-");
+    for b in mac {
+        name.push_str(&format!("{b:02x}"));
+    }
+
+    name
+}
+
+pub trait MdnsActionsTrait {
+    fn run_on(self, mdns: &mut EspMdns, default_hostname: &str) -> MdnsResponse;
+}
+
+impl MdnsActionsTrait for MdnsActions {
+    /// NOTE: mDNS queries block until [BROWSE_TIMEOUT] has passed
+    fn run_on(self, mdns: &mut EspMdns, default_hostname: &str) -> MdnsResponse {
+        match self {
+            Self::SetHostname(name) => {
+                let name = if name.is_empty() {
+                    default_hostname.to_string()
+                } else {
+                    name
+                };
+
+                match mdns
+                    .set_hostname(&name)
+                    .and_then(|_| mdns.set_instance_name(&name))
+                {
+                    Ok(_) => MdnsResponse::Hostname(name),
+                    Err(e) => MdnsResponse::Error(e.code()),
+                }
+            }
+            Self::Browse(service) => {
+                // `_http._tcp` -> (`_http`, `_tcp`), defaulting to tcp
+                let (service_type, proto) = service
+                    .rsplit_once('.')
+                    .unwrap_or((service.as_str(), "_tcp"));
+                println!("-> mDNS browse {service_type}.{proto}");
+
+                let mut results: [QueryResult; MAX_RESULTS] = Default::default();
+                match mdns.query_ptr(service_type, proto, BROWSE_TIMEOUT, MAX_RESULTS, &mut results) {
+                    Ok(found) => MdnsResponse::Services(
+                        results
+                            .into_iter()
+                            .take(found)
+                            .map(service_instance)
+                            .collect(),
+                    ),
+                    Err(e) => MdnsResponse::Error(e.code()),
+                }
+            }
+        }
+    }
+}
+
+fn service_instance(res: QueryResult) -> ServiceInstance {
+    let addr = res
+        .addr
+        .iter()
+        .find_map(|addr| match addr {
+            IpAddr::V4(v4) => Some(v4.octets()),
+            IpAddr::V6(_) => None,
+        })
+        .unwrap_or_default();
+
+    ServiceInstance {
+        instance: res.instance_name.unwrap_or_default(),
+        host: res.hostname.unwrap_or_default(),
+        addr,
+        port: res.port,
+        txt: res.txt,
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{self, ErrorKind};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use embedded_svc::mqtt::client::{Details, QoS as EspQoS};
+use esp_idf_svc::{
+    mqtt::client::{EspMqttClient, EventPayload, MqttClientConfiguration, MqttProtocolVersion},
+    sys::EspError,
+};
+use middlesp_proto::mqtt::{MqttConfig, MqttMessage, Qos};
+use middlesp_sockets::{MqttClient, Subscriptions};
+
+/// Size of the client's receive buffer, longer messages are dropped
+const BUFFER_SIZE: usize = 4096;
+
+/// An open connection to a broker, closed when dropped
+struct Client(EspMqttClient<'static>);
+
+// SAFETY: the client is not tied to the thread which opened it, and the
+// socket table only ever lets one thread at a time use it
+unsafe impl Send for Client {}
+
+impl MqttClient for Client {
+    fn publish(&mut self, topic: &str, payload: &[u8], qos: Qos, retain: bool) -> io::Result<()> {
+        self.0
+            .publish(topic, esp_qos(qos), retain, payload)
+            .map(|_| ())
+            .map_err(io_error)
+    }
+
+    fn subscribe(&mut self, filter: &str, qos: Qos) -> io::Result<()> {
+        self.0
+            .subscribe(filter, esp_qos(qos))
+            .map(|_| ())
+            .map_err(io_error)
+    }
+
+    fn unsubscribe(&mut self, filter: &str) -> io::Result<()> {
+        self.0.unsubscribe(filter).map(|_| ()).map_err(io_error)
+    }
+}
+
+/// Connects to an `mqtt://` or `mqtts://` broker, checking certificates
+/// against the bundle. The client runs a task of its own which queues
+/// messages as they arrive, so this only waits for the broker to accept us.
+pub fn connect_mqtt(
+    config: &MqttConfig,
+    timeout: Duration,
+    subscriptions: Arc<Subscriptions>,
+) -> io::Result<Box<dyn MqttClient>> {
+    let non_empty = |s: &String| (!s.is_empty()).then_some(s.as_str());
+    let conf = MqttClientConfiguration {
+        protocol_version: Some(MqttProtocolVersion::V3_1_1),
+        // esp-mqtt makes one up from the MAC address if there is none
+        client_id: non_empty(&config.client_id),
+        username: non_empty(&config.username),
+        password: non_empty(&config.password),
+        network_timeout: timeout,
+        buffer_size: BUFFER_SIZE,
+        // No timeout sets esp-mqtt's disable_auto_reconnect. A dropped
+        // connection is reported as closed, and the calculator connects
+        // again if it wants to
+        reconnect_timeout: None,
+        crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
+        ..Default::default()
+    };
+
+    let (ready, connected) = mpsc::sync_channel(1);
+    let client = EspMqttClient::new_cb(&config.url, &conf, move |event| match event.payload() {
+        EventPayload::Connected(_) => {
+            let _ = ready.try_send(true);
+        }
+        EventPayload::Received {
+            topic: Some(topic),
+            data,
+            details: Details::Complete,
+            ..
+        } => subscriptions.push(MqttMessage {
+            topic: topic.to_string(),
+            payload: data.to_vec(),
+        }),
+        EventPayload::Received { .. } => {
+            println!("Dropping an mqtt message too big for the buffer");
+        }
+        EventPayload::Disconnected => {
+            subscriptions.close();
+            let _ = ready.try_send(false);
+        }
+        EventPayload::Error(e) => println!("Mqtt error: {e:?}"),
+        _ => {}
+    })
+    .map_err(io_error)?;
+
+    match connected.recv_timeout(timeout) {
+        Ok(true) => Ok(Box::new(Client(client))),
+        Ok(false) => Err(ErrorKind::ConnectionRefused.into()),
+        Err(_) => Err(ErrorKind::TimedOut.into()),
+    }
+}
+
+fn esp_qos(qos: Qos) -> EspQoS {
+    match qos {
+        Qos::AtMostOnce => EspQoS::AtMostOnce,
+        Qos::AtLeastOnce => EspQoS::AtLeastOnce,
+        Qos::ExactlyOnce => EspQoS::ExactlyOnce,
+    }
+}
+
+fn io_error(e: EspError) -> io::Error {
+    io::Error::new(ErrorKind::Other, e)
+}
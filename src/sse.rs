@@ -0,0 +1,76 @@
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+use embedded_svc::io::Read;
+use esp_idf_svc::{
+    http::{
+        client::{Configuration as HttpConfiguration, EspHttpConnection},
+        Method,
+    },
+    sys::{EspError, ESP_ERR_HTTP_EAGAIN},
+};
+use middlesp_proto::http::Headers;
+use middlesp_sockets::EventStream;
+
+use crate::http::HeadersTrait;
+
+/// The body of an event stream, which is closed when dropped
+struct Body(EspHttpConnection);
+
+// SAFETY: the connection is not tied to the thread which opened it, and only
+// the thread reading the stream ever uses it
+unsafe impl Send for Body {}
+
+impl io::Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).map_err(|e| {
+            // Nothing arrived before the timeout, the stream is still open
+            if e.code() == ESP_ERR_HTTP_EAGAIN as i32 {
+                ErrorKind::TimedOut.into()
+            } else {
+                io_error(e)
+            }
+        })
+    }
+}
+
+/// Opens an event stream over `http://` or `https://`, checking certificates
+/// against the bundle. These connections stay open for as long as the
+/// calculator wants them, so they do not come from the http pool.
+pub fn connect_sse(
+    url: &str,
+    headers: &Headers,
+    last_id: Option<&str>,
+    timeout: Duration,
+) -> io::Result<EventStream> {
+    let config = HttpConfiguration {
+        timeout: Some(timeout),
+        crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
+        ..Default::default()
+    };
+    let mut conn = EspHttpConnection::new(&config).map_err(io_error)?;
+
+    let mut headers = headers.as_full_ref();
+    headers.push(("Accept", "text/event-stream"));
+    headers.push(("Cache-Control", "no-cache"));
+    if let Some(id) = last_id {
+        headers.push(("Last-Event-ID", id));
+    }
+
+    println!("-> GET {url}");
+    conn.initiate_request(Method::Get, url, &headers)
+        .map_err(io_error)?;
+    conn.initiate_response().map_err(io_error)?;
+
+    match conn.status() {
+        200 => Ok(Box::new(Body(conn))),
+        status => {
+            println!("Event stream {url} answered with {status}");
+            Err(ErrorKind::ConnectionRefused.into())
+        }
+    }
+}
+
+fn io_error(e: EspError) -> io::Error {
+    io::Error::new(ErrorKind::Other, e)
+}
@@ -1,61 +1,148 @@
 use std::future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{collections::VecDeque, future::poll_fn, task::Poll};
 
 use anyhow::Result;
-use embedded_svc::http::client::Client as HttpClient;
-use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
 // use embassy_net::{
 //     dns::DnsSocket,
 //     tcp::client::{TcpClient, TcpClientState},
 // };
 use esp_idf_svc::{
     eventloop::EspSystemEventLoop,
-    hal::{
-        gpio,
-        prelude::Peripherals,
-        uart::{config, UartDriver},
-        units::Hertz,
-    },
-    nvs::EspDefaultNvsPartition,
+    hal::{delay::TickType, prelude::Peripherals, reset, uart::UartDriver},
+    mdns::EspMdns,
+    nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault},
+    sys::ESP_FAIL,
     timer::EspTaskTimerService,
-    wifi::{AsyncWifi, EspWifi},
+    wifi::{AsyncWifi, EspWifi, WifiDeviceId},
 };
 use futures::{executor, future::BoxFuture, FutureExt};
+use middlesp_proto::{
+    at,
+    fetch::FetchResponse,
+    mdns::MdnsResponse,
+    mqtt::{MqttActions, MqttResponse},
+    sse::{SseActions, SseResponse},
+    tcp::{TcpActions, TcpResponse, ERR_OTHER},
+    uart::UartSettings,
+    udp::{UdpActions, UdpResponse},
+    ws::{WsActions, WsResponse},
+    CalcRequest, CalcResponse, Deserialise, Mode, SafeRead, Serialise, Varint,
+};
+use middlesp_sockets::{
+    run_fetch, run_mqtt, run_sse, run_tcp, run_udp, run_ws, Connectors, Fetcher, Sockets,
+};
 // use reqwless::client::{HttpClient, TlsConfig};
 
-use crate::spec::{CalcRequest, CalcResponse, Deserialise, Serialise};
+use crate::blocking;
+use crate::config::Config;
+use crate::gemini;
+use crate::http::HttpReqTrait;
+use crate::http_pool::HttpPool;
+use crate::link::Link;
+use crate::mdns::{self, MdnsActionsTrait};
+use crate::mqtt;
+use crate::sse;
+use crate::tcp;
+use crate::uart;
+use crate::wifi::WifiActionsTrait;
+use crate::ws;
+
+/// Id the start up requests are sent with, their responses only go out to
+/// a calculator which is already talking in binary mode
+pub const BOOT_ID: u8 = 0;
+
+/// NVS namespace our settings are kept in
+const NVS_NAMESPACE: &str = "middlesp";
+/// How long the calculator has to talk to us after switching uart settings
+/// before we decide it cannot and switch back
+const UART_SWITCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most bytes a [CalcResponse::Fragment] adds around its data: the request
+/// id, response id, then index, count and length as (at most 5 byte) varints
+const FRAGMENT_OVERHEAD: usize = 1 + 1 + 5 + 5 + 5;
+
+/// A request which has been started but not yet answered
+struct InFlight {
+    id: u8,
+    /// Whether this holds on to the wifi driver
+    wifi: bool,
+    future: BoxFuture<'static, CalcResponse>,
+}
+
+/// A change of uart settings the calculator asked for
+enum UartSwitch {
+    /// Waiting for our answer (at the old settings) to be acked
+    Pending(UartSettings),
+    /// Switched, waiting to hear from the calculator with the new settings
+    Trial {
+        previous: UartSettings,
+        since: Instant,
+    },
+}
 
 pub struct State {
+    config: Config,
     wifi: *mut AsyncWifi<EspWifi<'static>>,
     uart: *mut UartDriver<'static>,
-    http: *mut HttpClient<EspHttpConnection>,
-    processing: Option<BoxFuture<'static, CalcResponse>>,
-    incoming: VecDeque<CalcRequest>,
+    uart_settings: UartSettings,
+    uart_switch: Option<UartSwitch>,
+    /// `None` until we have worked out what the calculator is talking in
+    mode: Option<Mode>,
+    mode_switch: Option<Mode>,
+    /// Text mode has no ids, so we make them up (never [BOOT_ID])
+    next_text_id: u8,
+    link: Link,
+    nvs: EspNvs<NvsDefault>,
+    http: Arc<Mutex<HttpPool>>,
+    /// TCP, UDP, WebSocket, MQTT and event stream sockets, sharing one limit
+    sockets: Arc<Mutex<Sockets>>,
+    fetcher: Arc<Mutex<Fetcher>>,
+    mdns: Arc<Mutex<EspMdns>>,
+    hostname: String,
+    in_flight: Vec<InFlight>,
+    /// Requests which stopped being wanted while running on another thread.
+    /// They are still polled, so any socket one opens can be closed again
+    /// instead of taking up a slot nobody knows about
+    orphans: Vec<InFlight>,
+    incoming: VecDeque<(u8, CalcRequest)>,
+    /// When we last got a frame from the calculator
+    last_heard: Instant,
+    /// Whether we have already torn everything down for being idle
+    idle: bool,
+    /// Set when going idle stopped wifi, to whether it was connected then
+    wifi_stopped: Option<bool>,
 }
 
 impl State {
-    pub fn new() -> Result<Self> {
+    pub fn new(config: Config) -> Result<Self> {
         let peripherals = Peripherals::take().unwrap();
         let sysloop = EspSystemEventLoop::take()?;
         let nvs = EspDefaultNvsPartition::take()?;
+        let store = EspNvs::new(nvs.clone(), NVS_NAMESPACE, true)?;
+        let certs = gemini::NvsCerts::open(nvs.clone())?;
 
         let wifi = EspWifi::new(peripherals.modem, sysloop.clone(), Some(nvs))?;
         let timer_service = EspTaskTimerService::new()?;
 
-        // Create uart (Serial interaction)
-        let tx = peripherals.pins.gpio5;
-        let rx = peripherals.pins.gpio6;
-
-        let config = config::Config::new().baudrate(Hertz(115_200));
-        let uart = UartDriver::new(
-            peripherals.uart1,
-            tx,
-            rx,
-            Option::<gpio::Gpio0>::None,
-            Option::<gpio::Gpio1>::None,
-            &config,
-        )
-        .unwrap();
+        // Advertise ourselves as `calc-<mac>.local` from the start
+        let hostname = mdns::default_hostname(wifi.get_mac(WifiDeviceId::Sta)?);
+        let mut mdns = EspMdns::take()?;
+        mdns.set_hostname(&hostname)?;
+        mdns.set_instance_name(&hostname)?;
+
+        // Create uart (Serial interaction), with whatever settings last
+        // worked falling back on the defaults
+        let mut uart_settings = uart::load(&store);
+        let uart = match uart::open(&uart_settings) {
+            Ok(uart) => uart,
+            Err(e) => {
+                println!("Failed to open uart with {uart_settings:?}: {e:?}");
+                uart_settings = UartSettings::default();
+                uart::open(&uart_settings)?
+            }
+        };
 
         // Create http client
         // TODO: following: https://esp32.implrust.com/wifi/embassy/http-request.html
@@ -75,19 +162,49 @@ impl State {
         //     reqwless::client::TlsVerify::None,
         // );
 
-        let config = &HttpConfiguration {
-            crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
-            ..Default::default()
-        };
+        let mut link = Link::default();
+        if config.trace {
+            link.trace().start();
+        }
 
-        let client = HttpClient::wrap(EspHttpConnection::new(&config)?);
+        let sockets = Sockets::new(
+            config.max_sockets,
+            config.socket_timeout,
+            Connectors {
+                tls: Some(tcp::connect_tls),
+                ws: Some(ws::connect_ws),
+                mqtt: Some(mqtt::connect_mqtt),
+                sse: Some(sse::connect_sse),
+            },
+        );
+        let fetcher = Fetcher::new(
+            config.socket_timeout,
+            Some(gemini::connect_gemini),
+            Box::new(certs),
+        );
 
         Ok(Self {
+            config,
             uart: Box::into_raw(Box::new(uart)),
+            uart_settings,
+            uart_switch: None,
+            mode: None,
+            mode_switch: None,
+            next_text_id: BOOT_ID.wrapping_add(1),
+            link,
+            nvs: store,
             // Drop is implemented in and so this is safe :)
             wifi: Box::into_raw(Box::new(AsyncWifi::wrap(wifi, sysloop, timer_service)?)),
-            http: Box::into_raw(Box::new(client)),
-            processing: None,
+            http: Arc::default(),
+            sockets: Arc::new(Mutex::new(sockets)),
+            fetcher: Arc::new(Mutex::new(fetcher)),
+            mdns: Arc::new(Mutex::new(mdns)),
+            hostname,
+            in_flight: Vec::new(),
+            orphans: Vec::new(),
+            last_heard: Instant::now(),
+            idle: false,
+            wifi_stopped: None,
             incoming: VecDeque::new(),
         })
     }
@@ -100,93 +217,644 @@ impl State {
         unsafe { self.wifi.as_mut().unwrap() }
     }
 
-    pub fn http(&mut self) -> &mut HttpClient<EspHttpConnection> {
-        unsafe { self.http.as_mut().unwrap() }
+    pub fn http(&self) -> &Mutex<HttpPool> {
+        &self.http
     }
 
+    /// Reads every complete request waiting on the uart, any which do not
+    /// fit in the queue are answered with [CalcResponse::Busy]
     pub fn read_incoming(&mut self) {
-        match CalcRequest::from_bytes(self.uart()) {
-            Ok(res) => {
-                println!("Receieved: {res:?}");
-                self.push_incoming(res);
+        if self.mode.is_none() {
+            self.mode = self.link.detect_mode(unsafe { &mut *self.uart });
+            if let Some(mode) = self.mode {
+                println!("Calculator is talking in {mode:?} mode");
             }
-            Err(e) => println!("Failed to get buf: {e:?}"),
+        }
+
+        match self.mode {
+            Some(Mode::Binary) => {
+                while let Some(frame) = self.link.poll_frame(unsafe { &mut *self.uart }) {
+                    self.heard();
+
+                    match decode_request(&frame) {
+                        Ok((id, req)) => self.handle(id, req),
+                        Err(e) => println!("Failed to decode request: {e:?}"),
+                    }
+                }
+            }
+            // Answers are only told apart by their order, so the next line
+            // waits until everything before it has been answered
+            Some(Mode::Text) => {
+                while !self.is_processing() {
+                    let Some(line) = self.link.poll_line(unsafe { &mut *self.uart }) else {
+                        break;
+                    };
+                    self.heard();
+
+                    match at::parse(&line) {
+                        Ok(reqs) => {
+                            for req in reqs {
+                                let id = self.text_id();
+                                self.handle(id, req);
+                            }
+                        }
+                        Err(e) => {
+                            println!("Failed to parse {line:?}: {e:?}");
+                            self.link.send_line(unsafe { &mut *self.uart }, "ERROR");
+                        }
+                    }
+
+                    // Anything after a switch is in the other mode
+                    if self.mode_switch.is_some() {
+                        break;
+                    }
+                }
+            }
+            None => {}
         }
     }
 
-    pub fn push_incoming(&mut self, req: CalcRequest) {
-        self.incoming.push_back(req);
+    /// Makes up an id for a request in text mode
+    fn text_id(&mut self) -> u8 {
+        let id = self.next_text_id;
+        self.next_text_id = self.next_text_id.wrapping_add(1);
+        if self.next_text_id == BOOT_ID {
+            self.next_text_id = self.next_text_id.wrapping_add(1);
+        }
+
+        id
     }
 
-    /// Processes the next incoming request
-    pub fn try_process_incoming(&mut self) {
-        if self.processing.is_some() {
-            return;
+    /// Called whenever we get something from the calculator
+    fn heard(&mut self) {
+        self.last_heard = Instant::now();
+        self.idle = false;
+
+        if let Some(connected) = self.wifi_stopped.take() {
+            self.restart_wifi(connected);
         }
 
-        if let Some(next) = self.incoming.pop_front() {
-            let wifi = self.wifi;
-            let resp = match next {
-                CalcRequest::Wifi(action) => action
-                    .run_on(unsafe { wifi.as_mut().unwrap() })
-                    .map(CalcResponse::Wifi)
-                    .boxed(),
-                // Sadly it seems we must block on the request which is just
-                // kinda sad I will admit
-                CalcRequest::Http(req) => {
-                    future::ready(CalcResponse::Http(req.send(self.http()))).boxed()
+        if let Some(UartSwitch::Trial { .. }) = self.uart_switch {
+            println!("Calculator is talking with {:?}", self.uart_settings);
+            self.uart_switch = None;
+            uart::store(&mut self.nvs, &self.uart_settings);
+        }
+    }
+
+    fn handle(&mut self, id: u8, req: CalcRequest) {
+        match req {
+            // Cancels should not have to wait behind what they cancel
+            CalcRequest::Cancel(target) => {
+                println!("Receieved {id}: cancel {target}");
+                let found = self.cancel(target);
+                self.send(id, CalcResponse::Cancel(found));
+            }
+            CalcRequest::Ping => self.send(id, CalcResponse::Pong),
+            CalcRequest::SetMaxFrame(size) => {
+                let resp = self.set_max_frame(size);
+                self.send(id, resp);
+            }
+            CalcRequest::SetUart(settings) => {
+                let resp = self.set_uart(settings);
+                self.send(id, resp);
+            }
+            CalcRequest::SetMode(mode) => {
+                let resp = self.set_mode(mode);
+                self.send(id, resp);
+            }
+            CalcRequest::Trace(action) => {
+                let resp = CalcResponse::Trace(self.link.trace().run(action));
+                self.send(id, resp);
+            }
+            req => {
+                println!("Receieved {id}: {req:?}");
+                if !self.push_incoming(id, req) {
+                    println!("Queue is full, rejecting {id}");
+                    self.send(id, CalcResponse::Busy);
                 }
-            };
+            }
+        }
+    }
+
+    /// Switches mode once our answer has gone out in the current one
+    fn set_mode(&mut self, mode: Mode) -> CalcResponse {
+        self.mode_switch = Some(mode);
 
-            self.processing = Some(resp);
+        CalcResponse::Mode(mode)
+    }
+
+    /// Carries out a mode switch once its answer has gone out (and been acked
+    /// if we are in binary mode)
+    pub fn check_mode(&mut self) {
+        if let Some(mode) = self.mode_switch {
+            if self.link.is_idle() {
+                println!("Switching to {mode:?} mode");
+                self.mode = Some(mode);
+                self.mode_switch = None;
+                self.link.reset();
+            }
+        }
+    }
+
+    /// Tears everything down once the calculator has been silent for longer
+    /// than [Config::idle_timeout], as nobody is left to read the responses
+    pub fn check_idle(&mut self) {
+        let Some(timeout) = self.config.idle_timeout else {
+            return;
+        };
+
+        if self.idle || self.last_heard.elapsed() < timeout {
+            return;
+        }
+
+        println!("Calculator has been silent for {timeout:?}, going idle");
+        self.idle = true;
+
+        self.incoming.clear();
+        for f in std::mem::take(&mut self.in_flight) {
+            self.abandon(f);
+        }
+        // Whoever talks next starts a new session
+        self.link.reset();
+        self.http.lock().unwrap().clear();
+        self.sockets.lock().unwrap().clear();
+
+        if self.config.idle_stop_wifi {
+            let connected = self.wifi().is_connected().unwrap_or(false);
+            match executor::block_on(self.wifi().stop()) {
+                Ok(()) => self.wifi_stopped = Some(connected),
+                Err(e) => println!("Failed to stop wifi: {e:?}"),
+            }
         }
     }
 
-    pub fn poll_processing(&mut self) -> Option<CalcResponse> {
-        if let Some(processing) = &mut self.processing {
-            let res = executor::block_on(poll_fn(|ctx| {
-                Poll::Ready(match processing.poll_unpin(ctx) {
-                    Poll::Ready(v) => Some(v),
-                    Poll::Pending => None,
-                })
-            }));
+    /// Starts wifi again after going idle stopped it, connecting again if it
+    /// was connected.
+    ///
+    /// NOTE: this blocks until connected, so whatever the calculator woke us
+    /// up with does not fail for want of wifi
+    fn restart_wifi(&mut self, connect: bool) {
+        println!("Calculator is back, starting wifi again");
+        if let Err(e) = executor::block_on(self.wifi().start()) {
+            println!("Failed to start wifi: {e:?}");
+            return;
+        }
 
-            if res.is_some() {
-                self.processing = None;
+        if connect {
+            if let Err(e) = executor::block_on(self.wifi().connect()) {
+                println!("Failed to connect wifi again: {e:?}");
             }
+        }
+    }
+
+    /// Closes any http connections which have not been used in a while
+    pub fn evict_idle_connections(&mut self) {
+        self.http.lock().unwrap().evict_idle();
+    }
 
-            res
+    /// Queues a request, returning false if the queue is already full. Only
+    /// for requests which [Self::handle] would queue too
+    pub fn push_incoming(&mut self, id: u8, req: CalcRequest) -> bool {
+        if self.incoming.len() >= self.config.max_queued {
+            return false;
+        }
+
+        self.incoming.push_back((id, req));
+        true
+    }
+
+    /// Starts as many queued requests as we have room for. In text mode
+    /// only one runs at a time, so the answers go out in order.
+    pub fn try_process_incoming(&mut self) {
+        let max_in_flight = match self.mode {
+            Some(Mode::Text) => 1,
+            _ => self.config.max_in_flight,
+        };
+
+        while self.in_flight.len() < max_in_flight {
+            // Wifi requests all borrow the driver so only one may run at once,
+            // anything else queued behind them is still free to start
+            let wifi_busy = self.in_flight.iter().any(|f| f.wifi);
+            let Some(i) = self
+                .incoming
+                .iter()
+                .position(|(_, req)| !(wifi_busy && matches!(req, CalcRequest::Wifi(_))))
+            else {
+                return;
+            };
+
+            let (id, next) = self.incoming.remove(i).unwrap();
+            let wifi = matches!(next, CalcRequest::Wifi(_));
+            let future = self.start(id, next);
+
+            self.in_flight.push(InFlight { id, wifi, future });
+        }
+    }
+
+    fn start(&mut self, id: u8, req: CalcRequest) -> BoxFuture<'static, CalcResponse> {
+        let wifi = self.wifi;
+        match req {
+            CalcRequest::Wifi(action) => action
+                .run_on(unsafe { wifi.as_mut().unwrap() })
+                .map(CalcResponse::Wifi)
+                .boxed(),
+            // The http client blocks, so give it a thread of its own
+            CalcRequest::Http(req) => {
+                let pool = self.http.clone();
+                on_thread(
+                    "http",
+                    move || req.send(&pool).map_err(|e| e.0.code()),
+                    CalcResponse::Http,
+                    Err(ESP_FAIL),
+                )
+            }
+            // Sockets block while connecting and waiting for data
+            CalcRequest::Tcp(action) => {
+                let sockets = self.sockets.clone();
+                on_thread(
+                    "tcp",
+                    move || run_tcp(&sockets, id, action),
+                    CalcResponse::Tcp,
+                    TcpResponse::Error(ERR_OTHER),
+                )
+            }
+            CalcRequest::Udp(action) => {
+                let sockets = self.sockets.clone();
+                on_thread(
+                    "udp",
+                    move || run_udp(&sockets, action),
+                    CalcResponse::Udp,
+                    UdpResponse::Error(ERR_OTHER),
+                )
+            }
+            CalcRequest::Ws(action) => {
+                let sockets = self.sockets.clone();
+                on_thread(
+                    "websocket",
+                    move || run_ws(&sockets, action),
+                    CalcResponse::Ws,
+                    WsResponse::Error(ERR_OTHER),
+                )
+            }
+            CalcRequest::Mqtt(action) => {
+                let sockets = self.sockets.clone();
+                on_thread(
+                    "mqtt",
+                    move || run_mqtt(&sockets, action),
+                    CalcResponse::Mqtt,
+                    MqttResponse::Error(ERR_OTHER),
+                )
+            }
+            CalcRequest::Sse(action) => {
+                let sockets = self.sockets.clone();
+                on_thread(
+                    "sse",
+                    move || run_sse(&sockets, action),
+                    CalcResponse::Sse,
+                    SseResponse::Error(ERR_OTHER),
+                )
+            }
+            // Gemini and Gopher block while connecting and reading the page
+            CalcRequest::Fetch(action) => {
+                let fetcher = self.fetcher.clone();
+                on_thread(
+                    "fetch",
+                    move || run_fetch(&fetcher, action),
+                    CalcResponse::Fetch,
+                    FetchResponse::Error(ERR_OTHER),
+                )
+            }
+            // mDNS queries block for their whole timeout
+            CalcRequest::Mdns(action) => {
+                let mdns = self.mdns.clone();
+                let hostname = self.hostname.clone();
+                on_thread(
+                    "mdns",
+                    move || action.run_on(&mut mdns.lock().unwrap(), &hostname),
+                    CalcResponse::Mdns,
+                    MdnsResponse::Error(ESP_FAIL),
+                )
+            }
+            // Answered straight away by [Self::handle], so never queued
+            CalcRequest::Cancel(_)
+            | CalcRequest::Ping
+            | CalcRequest::SetMaxFrame(_)
+            | CalcRequest::SetUart(_)
+            | CalcRequest::SetMode(_)
+            | CalcRequest::Trace(_) => unreachable!("answered in handle"),
+        }
+    }
+
+    /// Drops the queued or running request with the id `target`, answering it
+    /// with [CalcResponse::Cancelled]. Returns false if there was no such
+    /// request.
+    pub fn cancel(&mut self, target: u8) -> bool {
+        let found = if let Some(i) = self.incoming.iter().position(|(id, _)| *id == target) {
+            self.incoming.remove(i);
+            true
+        } else if let Some(i) = self.in_flight.iter().position(|f| f.id == target) {
+            let f = self.in_flight.remove(i);
+            self.abandon(f);
+            true
         } else {
-            None
+            false
+        };
+
+        if found {
+            println!("Cancelled {target}");
+            self.send(target, CalcResponse::Cancelled);
         }
+
+        found
+    }
+
+    /// Stops answering a running request. Dropping the future is enough to
+    /// stop wifi requests, anything else finishes on its own thread and is
+    /// kept until then so [Self::close_orphans] can undo it
+    fn abandon(&mut self, f: InFlight) {
+        if !f.wifi {
+            self.orphans.push(f);
+        }
+    }
+
+    /// Polls every running request once, returning those which have finished
+    pub fn poll_processing(&mut self) -> Vec<(u8, CalcResponse)> {
+        let mut done = Vec::new();
+
+        self.in_flight
+            .retain_mut(|f| match poll_once(&mut f.future) {
+                Some(resp) => {
+                    done.push((f.id, resp));
+                    false
+                }
+                None => true,
+            });
+
+        done
+    }
+
+    /// Closes the sockets opened by abandoned requests which have finished
+    fn close_orphans(&mut self) {
+        let sockets = &self.sockets;
+        self.orphans.retain_mut(|f| {
+            let Some(resp) = poll_once(&mut f.future) else {
+                return true;
+            };
+
+            match resp {
+                CalcResponse::Tcp(
+                    TcpResponse::Opened(handle) | TcpResponse::Listening { handle, .. },
+                ) => {
+                    run_tcp(sockets, f.id, TcpActions::Close(handle));
+                }
+                CalcResponse::Udp(UdpResponse::Bound { handle, .. }) => {
+                    run_udp(sockets, UdpActions::Close(handle));
+                }
+                CalcResponse::Ws(WsResponse::Connected(handle)) => {
+                    run_ws(sockets, WsActions::Close(handle));
+                }
+                CalcResponse::Mqtt(MqttResponse::Connected(handle)) => {
+                    run_mqtt(sockets, MqttActions::Close(handle));
+                }
+                CalcResponse::Sse(SseResponse::Subscribed(handle)) => {
+                    run_sse(sockets, SseActions::Close(handle));
+                }
+                _ => return false,
+            }
+            println!("Closed the socket cancelled request {} opened", f.id);
+
+            false
+        });
     }
 
     pub fn try_send_processing(&mut self) {
-        if let Some(resp) = self.poll_processing() {
-            println!("Sending: {resp:?}");
+        self.close_orphans();
+        for (id, resp) in self.poll_processing() {
+            self.send(id, resp);
+        }
+    }
+
+    /// Tells the calculator about any clients which have connected to its
+    /// listening sockets, using the id it started listening with
+    pub fn accept_connections(&mut self) {
+        let accepted = self.sockets.lock().unwrap().accept();
+        for (id, resp) in accepted {
+            self.send(id, CalcResponse::Tcp(resp));
+        }
+    }
+
+    fn set_max_frame(&mut self, size: u16) -> CalcResponse {
+        let size = self
+            .link
+            .set_max_frame((size != 0).then_some(size as usize));
+        println!("Max frame size is now {size:?}");
+
+        CalcResponse::MaxFrame(size.unwrap_or(0) as u16)
+    }
+
+    fn set_uart(&mut self, settings: UartSettings) -> CalcResponse {
+        if !settings.is_valid() || self.uart_switch.is_some() {
+            println!("Not switching uart to {settings:?}");
+            return CalcResponse::Uart(self.uart_settings.clone());
+        }
+
+        self.uart_switch = Some(UartSwitch::Pending(settings.clone()));
+        CalcResponse::Uart(settings)
+    }
+
+    /// Carries out any uart switch once its answer has gone out, and switches
+    /// back if the calculator does not follow
+    pub fn check_uart(&mut self) {
+        match &self.uart_switch {
+            Some(UartSwitch::Pending(next)) if self.link.is_idle() => {
+                let next = next.clone();
+                self.uart_switch = Some(UartSwitch::Trial {
+                    previous: self.uart_settings.clone(),
+                    since: Instant::now(),
+                });
+                self.reopen_uart(next);
+            }
+            Some(UartSwitch::Trial { previous, since })
+                if since.elapsed() > UART_SWITCH_TIMEOUT =>
+            {
+                println!("Calculator never talked with the new uart settings");
+                let previous = previous.clone();
+                self.uart_switch = None;
+                self.reopen_uart(previous);
+            }
+            None if self.uart_settings != UartSettings::default()
+                && self
+                    .config
+                    .uart_fallback
+                    .is_some_and(|t| self.last_heard.elapsed() > t) =>
+            {
+                println!("Calculator has been silent, going back to default uart settings");
+                self.reopen_uart(UartSettings::default());
+            }
+            _ => {}
+        }
+    }
 
-            let buf = resp.to_bytes();
-            match self.uart().write(&buf) {
-                Ok(size) if size != buf.len() => {
-                    println!("Only write {size} bytes when expected {}", buf.len());
+    fn reopen_uart(&mut self, settings: UartSettings) {
+        println!("Switching uart to {settings:?}");
+
+        // Let the last few bytes (likely acks) go out at the old settings
+        let _ = self.uart().wait_tx_done(TickType::new_millis(100).ticks());
+
+        // SAFETY: the old driver is dropped before the new one takes over its
+        // peripheral, and is replaced before anything else can touch it
+        unsafe { std::ptr::drop_in_place(self.uart) };
+        let uart = match uart::open(&settings) {
+            Ok(uart) => {
+                self.uart_settings = settings;
+                uart
+            }
+            Err(e) => {
+                println!("Failed to open uart with {settings:?}: {e:?}");
+                match uart::open(&self.uart_settings) {
+                    Ok(uart) => uart,
+                    // Without a uart there is nothing we can do, so start over
+                    Err(_) => reset::restart(),
                 }
+            }
+        };
+        unsafe { std::ptr::write(self.uart, uart) };
+    }
+
+    /// Resends any responses the calculator has not acked yet
+    pub fn resend_unacked(&mut self) {
+        self.link.resend_unacked(unsafe { &mut *self.uart });
+    }
+
+    /// Sends a response, split into [CalcResponse::Fragment]s if it is too
+    /// big for the calculator to take in one frame
+    fn send(&mut self, id: u8, resp: CalcResponse) {
+        println!("Sending {id}: {resp:?}");
+
+        match self.mode {
+            // Nobody has asked for anything yet, so these are answers to the
+            // start up requests and we do not know how to frame them
+            None => {
+                println!("Calculator has not said anything yet, dropping {id}");
+                return;
+            }
+            // A line nobody asked for would be taken as the answer to the
+            // next command
+            Some(Mode::Text) if id == BOOT_ID => {
+                println!("Not sending {id} in text mode, it was never asked for");
+                return;
+            }
+            Some(Mode::Text) => {
+                let lines = at::format(resp);
+                // Only the rest of this command can be queued, and it must
+                // not add a second answer once part of it has failed
+                if at::is_error(&lines) && !self.incoming.is_empty() {
+                    println!("Dropping the rest of the command after {id} failed");
+                    self.incoming.clear();
+                }
+
+                for line in lines {
+                    self.link.send_line(unsafe { &mut *self.uart }, &line);
+                }
+
+                return;
+            }
+            Some(Mode::Binary) => {}
+        }
+
+        let bytes = match resp.to_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                println!("Failed to encode response to {id}: {e:?}");
+                return;
+            }
+        };
+        let max = self.link.max_payload();
+
+        if bytes.len() < max {
+            let mut buf = vec![id];
+            buf.extend(bytes);
+
+            if !self.link.send(unsafe { &mut *self.uart }, &[buf]) {
+                println!("Dropped response to {id}");
+            }
+            return;
+        }
+
+        let chunks = bytes.chunks(max - FRAGMENT_OVERHEAD);
+        let count = chunks.len() as u32;
+        println!("Splitting response to {id} into {count} fragments");
+
+        // Every fragment is encoded before any are queued, so they all go
+        // out or none do
+        let mut frames = Vec::with_capacity(chunks.len());
+        for (index, chunk) in (0..).zip(chunks) {
+            let fragment = CalcResponse::Fragment {
+                index: Varint(index),
+                count: Varint(count),
+                data: chunk.to_vec(),
+            };
+
+            let mut buf = vec![id];
+            match fragment.to_bytes() {
+                Ok(bytes) => buf.extend(bytes),
                 Err(e) => {
-                    println!("Error when writing to stream: {e:?}")
+                    println!("Failed to encode fragment {index} of {id}: {e:?}");
+                    return;
                 }
-                _ => {} // Everything is fine with the world
             }
+            frames.push(buf);
+        }
+
+        if !self.link.send(unsafe { &mut *self.uart }, &frames) {
+            println!("Dropped all {count} fragments of the response to {id}");
         }
     }
 
     pub fn is_processing(&self) -> bool {
-        self.processing.is_some() || !self.incoming.is_empty()
+        !self.in_flight.is_empty() || !self.incoming.is_empty()
     }
 }
 
+/// Splits a frame into the id the calculator gave the request and the request
+fn decode_request(mut frame: &[u8]) -> Result<(u8, CalcRequest)> {
+    let id = frame.try_next()?;
+
+    Ok((id, CalcRequest::from_bytes(&mut frame)?))
+}
+
 impl Drop for State {
     fn drop(&mut self) {
+        // Running requests may still be borrowing the wifi driver
+        self.in_flight.clear();
         let _box = unsafe { Box::from_raw(self.wifi) };
         let _box = unsafe { Box::from_raw(self.uart) };
-        // let _box = unsafe { Box::from_raw(self.http) };
     }
 }
+
+/// Runs a blocking request on a thread of its own, answering with `failed`
+/// if the thread could not be started or panicked
+fn on_thread<T: Send + 'static>(
+    name: &str,
+    f: impl FnOnce() -> T + Send + 'static,
+    wrap: fn(T) -> CalcResponse,
+    failed: T,
+) -> BoxFuture<'static, CalcResponse> {
+    match blocking::spawn(f) {
+        Ok(fut) => fut.map(move |res| wrap(res.unwrap_or(failed))).boxed(),
+        Err(e) => {
+            println!("Failed to spawn {name} thread: {e:?}");
+            future::ready(wrap(failed)).boxed()
+        }
+    }
+}
+
+/// Polls a future once, without waiting for it
+fn poll_once(future: &mut BoxFuture<'static, CalcResponse>) -> Option<CalcResponse> {
+    executor::block_on(poll_fn(|ctx| {
+        Poll::Ready(match future.poll_unpin(ctx) {
+            Poll::Ready(v) => Some(v),
+            Poll::Pending => None,
+        })
+    }))
+}
@@ -0,0 +1,58 @@
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+use esp_idf_svc::{
+    sys::{EspError, MBEDTLS_ERR_SSL_TIMEOUT, MBEDTLS_ERR_SSL_WANT_READ},
+    tls::{Config, EspTls, InternalSocket},
+};
+use middlesp_sockets::Stream;
+
+/// A TLS connection, made to look like a std stream for the socket table (and
+/// Gemini)
+pub struct Tls(pub EspTls<InternalSocket>);
+
+// SAFETY: the connection is not tied to the thread which opened it, and the
+// socket table only ever lets one thread at a time use it
+unsafe impl Send for Tls {}
+
+impl io::Read for Tls {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).map_err(io_error)
+    }
+}
+
+impl io::Write for Tls {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf).map_err(io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Opens a TLS connection checked against the certificate bundle, esp-tls
+/// uses the same timeout for connecting and for each read or write
+pub fn connect_tls(host: &str, port: u16, timeout: Duration) -> io::Result<Box<dyn Stream>> {
+    let mut tls = EspTls::new().map_err(io_error)?;
+    tls.connect(
+        host,
+        port,
+        &Config {
+            common_name: Some(host),
+            timeout_ms: timeout.as_millis() as u32,
+            use_crt_bundle_attach: true,
+            ..Config::new()
+        },
+    )
+    .map_err(io_error)?;
+
+    Ok(Box::new(Tls(tls)))
+}
+
+pub fn io_error(e: EspError) -> io::Error {
+    match e.code() {
+        MBEDTLS_ERR_SSL_WANT_READ | MBEDTLS_ERR_SSL_TIMEOUT => ErrorKind::TimedOut.into(),
+        _ => io::Error::new(ErrorKind::Other, e),
+    }
+}
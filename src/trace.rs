@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use middlesp_proto::trace::{Direction, TraceActions, TraceRecord, TraceResponse};
+
+/// Most bytes kept, past this the oldest records are dropped
+const TRACE_SIZE: usize = 16 * 1024;
+
+/// A ring buffer of everything read from and written to the uart, only
+/// filled in while recording
+pub struct Trace {
+    records: VecDeque<TraceRecord>,
+    /// Bytes held across all of `records`
+    size: usize,
+    recording: bool,
+    boot: Instant,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self {
+            records: VecDeque::new(),
+            size: 0,
+            recording: false,
+            boot: Instant::now(),
+        }
+    }
+}
+
+impl Trace {
+    pub fn record(&mut self, dir: Direction, bytes: &[u8]) {
+        if !self.recording || bytes.is_empty() {
+            return;
+        }
+
+        self.records.push_back(TraceRecord {
+            at_ms: self.boot.elapsed().as_millis() as u32,
+            dir,
+            bytes: bytes.to_vec(),
+        });
+        self.size += bytes.len();
+
+        while self.size > TRACE_SIZE {
+            let Some(old) = self.records.pop_front() else {
+                break;
+            };
+            self.size -= old.bytes.len();
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.take();
+        self.recording = true;
+    }
+
+    pub fn run(&mut self, action: TraceActions) -> TraceResponse {
+        match action {
+            TraceActions::Start => {
+                self.start();
+                TraceResponse::Started
+            }
+            TraceActions::Stop => {
+                self.recording = false;
+                TraceResponse::Stopped
+            }
+            TraceActions::Take => TraceResponse::Records(self.take()),
+        }
+    }
+
+    fn take(&mut self) -> Vec<TraceRecord> {
+        self.size = 0;
+        self.records.drain(..).collect()
+    }
+}
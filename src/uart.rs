@@ -0,0 +1,109 @@
+use esp_idf_svc::{
+    hal::{
+        gpio::AnyIOPin,
+        uart::{
+            config::{self, FlowControl, StopBits},
+            UartDriver, UART1,
+        },
+        units::Hertz,
+    },
+    nvs::{EspNvs, NvsDefault},
+    sys::EspError,
+};
+
+use middlesp_proto::{uart::UartSettings, Deserialise, Serialise};
+
+const NVS_KEY: &str = "uart";
+
+/// Opens the uart to the calculator with the given settings.
+///
+/// NOTE: any previous driver must have been dropped first, as this takes
+/// UART1 and the pins again
+pub fn open(settings: &UartSettings) -> Result<UartDriver<'static>, EspError> {
+    let config = config::Config::new()
+        .baudrate(Hertz(settings.baud))
+        .stop_bits(stop_bits(settings))
+        .flow_control(flow_control(settings));
+    let config = match settings.parity {
+        1 => config.parity_even(),
+        2 => config.parity_odd(),
+        _ => config.parity_none(),
+    };
+
+    // SAFETY: we are the only ones using UART1 and the calculator's pins, and
+    // the old driver using them has been dropped
+    unsafe {
+        UartDriver::new(
+            UART1::new(),
+            AnyIOPin::new(settings.tx as i32),
+            AnyIOPin::new(settings.rx as i32),
+            settings.cts.map(|p| AnyIOPin::new(p as i32)),
+            settings.rts.map(|p| AnyIOPin::new(p as i32)),
+            &config,
+        )
+    }
+}
+
+fn stop_bits(settings: &UartSettings) -> StopBits {
+    match settings.stop_bits {
+        2 => StopBits::STOP2,
+        _ => StopBits::STOP1,
+    }
+}
+
+fn flow_control(settings: &UartSettings) -> FlowControl {
+    match (settings.rts, settings.cts) {
+        (Some(_), Some(_)) => FlowControl::CTSRTS,
+        (Some(_), None) => FlowControl::RTS,
+        (None, Some(_)) => FlowControl::CTS,
+        (None, None) => FlowControl::None,
+    }
+}
+
+/// The settings we last confirmed worked, or the defaults if there are none
+pub fn load(nvs: &EspNvs<NvsDefault>) -> UartSettings {
+    if let Some(pin) = UartSettings::default().flash_pin() {
+        println!("WARNING: the default uart settings use GPIO{pin}, which is wired to the flash");
+    }
+
+    let mut buf = [0u8; 16];
+    match nvs.get_raw(NVS_KEY, &mut buf) {
+        Ok(Some(mut raw)) => match UartSettings::from_bytes(&mut raw) {
+            Ok(settings) if settings.is_valid() => settings,
+            Ok(settings) => {
+                match settings.flash_pin() {
+                    // Likely stored before GPIO6 stopped being the default rx,
+                    // and the calculator stays silent until it is rewired
+                    Some(pin) => println!(
+                        "WARNING: ignoring the stored uart settings, GPIO{pin} is wired to \
+                         the flash. The calculator has to be moved to the default pins, rx \
+                         is GPIO{} rather than GPIO6: {settings:?}",
+                        UartSettings::default().rx
+                    ),
+                    None => println!("Ignoring invalid stored uart settings: {settings:?}"),
+                }
+                UartSettings::default()
+            }
+            Err(e) => {
+                println!("Failed to decode stored uart settings: {e:?}");
+                UartSettings::default()
+            }
+        },
+        Ok(None) => UartSettings::default(),
+        Err(e) => {
+            println!("Failed to read stored uart settings: {e:?}");
+            UartSettings::default()
+        }
+    }
+}
+
+pub fn store(nvs: &mut EspNvs<NvsDefault>, settings: &UartSettings) {
+    let res = settings
+        .clone()
+        .to_bytes()
+        .and_then(|raw| Ok(nvs.set_raw(NVS_KEY, &raw)?));
+
+    if let Err(e) = res {
+        println!("Failed to store uart settings: {e:?}");
+    }
+}
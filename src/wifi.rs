@@ -0,0 +1,117 @@
+use std::future::{self, Future};
+
+use embedded_svc::wifi::{self, ClientConfiguration};
+use esp_idf_svc::{
+    sys::EspError,
+    wifi::{AccessPointInfo, AsyncWifi, EspWifi},
+};
+use futures::{future::BoxFuture, FutureExt};
+use middlesp_proto::wifi::{AccessPoint, AuthMethod, WifiActions, WifiConfig, WifiResponse};
+
+pub trait WifiActionsTrait {
+    fn run_on<'a>(self, wifi: &'a mut AsyncWifi<EspWifi<'_>>) -> BoxFuture<'a, WifiResponse>;
+}
+
+impl WifiActionsTrait for WifiActions {
+    fn run_on<'a>(self, wifi: &'a mut AsyncWifi<EspWifi<'_>>) -> BoxFuture<'a, WifiResponse> {
+        match self {
+            Self::IsStarted => {
+                future::ready(wifi.is_started().into_resp(WifiResponse::IsStarted)).boxed()
+            }
+            Self::Scan => wifi
+                .scan()
+                .into_resp(|points| {
+                    WifiResponse::AccessPoints(points.into_iter().map(access_point).collect())
+                })
+                .boxed(),
+            Self::IsConnected => {
+                future::ready(wifi.is_connected().into_resp(WifiResponse::IsConnected)).boxed()
+            }
+            Self::GetCapabilities => future::ready(
+                wifi.get_capabilities()
+                    .into_resp(|caps| WifiResponse::Capabilities(caps.as_u8())),
+            )
+            .boxed(),
+            Self::Start => wifi.start().into_resp_or(WifiResponse::Started).boxed(),
+            Self::Stop => wifi.stop().into_resp_or(WifiResponse::Stopped).boxed(),
+            Self::Connect => wifi.connect().into_resp_or(WifiResponse::Connected).boxed(),
+            Self::Disconnect => wifi
+                .disconnect()
+                .into_resp_or(WifiResponse::Disconnected)
+                .boxed(),
+            Self::SetConfig(config) => future::ready(
+                wifi.set_configuration(&wifi::Configuration::Client(client_config(config)))
+                    .into_resp_or(WifiResponse::Configured),
+            )
+            .boxed(),
+        }
+    }
+}
+
+fn client_config(config: WifiConfig) -> ClientConfiguration {
+    ClientConfiguration {
+        ssid: config.ssid,
+        password: config.password,
+        auth_method: match config.auth_method {
+            AuthMethod::None => wifi::AuthMethod::None,
+            AuthMethod::WEP => wifi::AuthMethod::WEP,
+            AuthMethod::WPA => wifi::AuthMethod::WPA,
+            AuthMethod::WPA2Personal => wifi::AuthMethod::WPA2Personal,
+            AuthMethod::WPAWPA2Personal => wifi::AuthMethod::WPAWPA2Personal,
+            AuthMethod::WPA2Enterprise => wifi::AuthMethod::WPA2Enterprise,
+            AuthMethod::WPA3Personal => wifi::AuthMethod::WPA3Personal,
+            AuthMethod::WPA2WPA3Personal => wifi::AuthMethod::WPA2WPA3Personal,
+            AuthMethod::WAPIPersonal => wifi::AuthMethod::WAPIPersonal,
+        },
+        ..Default::default()
+    }
+}
+
+fn access_point(info: AccessPointInfo) -> AccessPoint {
+    AccessPoint {
+        ssid: info.ssid.to_string(),
+        bssid: info.bssid,
+        channel: info.channel,
+        signal_strength: info.signal_strength,
+    }
+}
+
+pub trait ConvertToWifiResponse<T> {
+    fn into_resp(self, f: impl Fn(T) -> WifiResponse) -> WifiResponse;
+    fn into_resp_or(self, or: WifiResponse) -> WifiResponse;
+}
+
+impl<T> ConvertToWifiResponse<T> for Result<T, EspError> {
+    #[inline]
+    fn into_resp(self, f: impl Fn(T) -> WifiResponse) -> WifiResponse {
+        match self {
+            Ok(r) => f(r),
+            Err(e) => WifiResponse::Error(e.code()),
+        }
+    }
+
+    #[inline]
+    fn into_resp_or(self, or: WifiResponse) -> WifiResponse {
+        match self {
+            Ok(_) => or,
+            Err(e) => WifiResponse::Error(e.code()),
+        }
+    }
+}
+
+pub trait AsyncConvertToWifiResponse<T> {
+    fn into_resp(self, f: impl Fn(T) -> WifiResponse) -> impl Future<Output = WifiResponse>;
+    fn into_resp_or(self, or: WifiResponse) -> impl Future<Output = WifiResponse>;
+}
+
+impl<T, F: Future<Output = Result<T, EspError>>> AsyncConvertToWifiResponse<T> for F {
+    #[inline]
+    fn into_resp(self, f: impl Fn(T) -> WifiResponse) -> impl Future<Output = WifiResponse> {
+        self.map(|r| r.into_resp(f))
+    }
+
+    #[inline]
+    fn into_resp_or(self, or: WifiResponse) -> impl Future<Output = WifiResponse> {
+        self.map(|r| r.into_resp_or(or))
+    }
+}
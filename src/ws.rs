@@ -0,0 +1,152 @@
+use std::ffi::c_void;
+use std::io::{self, ErrorKind};
+use std::slice;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use embedded_svc::ws::FrameType;
+use esp_idf_svc::{
+    handle::RawHandle,
+    sys::{
+        esp, esp_event_base_t, esp_websocket_event_data_t,
+        esp_websocket_event_id_t_WEBSOCKET_EVENT_DATA, esp_websocket_register_events, EspError,
+    },
+    ws::client::{
+        EspWebSocketClient, EspWebSocketClientConfig, WebSocketEvent, WebSocketEventType,
+    },
+};
+use middlesp_proto::{http::Headers, ws::WsMessage};
+use middlesp_sockets::{Inbox, Opcode, WsSender};
+
+/// Size of the client's receive buffer, longer frames arrive in pieces which
+/// [on_data] puts back together
+const BUFFER_SIZE: usize = 4096;
+
+/// An open WebSocket, closed when dropped
+struct Client {
+    client: EspWebSocketClient<'static>,
+    /// [on_data] is handed a pointer to this, so it has to outlive `client`
+    _inbox: Arc<Inbox>,
+}
+
+// SAFETY: the client is not tied to the thread which opened it, and the
+// socket table only ever lets one thread at a time send on it
+unsafe impl Send for Client {}
+
+impl WsSender for Client {
+    fn send(&mut self, msg: &WsMessage) -> io::Result<()> {
+        let (frame_type, data) = match msg {
+            WsMessage::Text(text) => (FrameType::Text(false), text.as_bytes()),
+            WsMessage::Binary(data) => (FrameType::Binary(false), data.as_slice()),
+        };
+
+        self.client.send(frame_type, data).map_err(io_error)
+    }
+}
+
+/// Connects to a `ws://` or `wss://` url, checking certificates against the
+/// bundle. The client runs a task of its own which fills `inbox` as messages
+/// arrive, so this only waits for the upgrade to be done.
+pub fn connect_ws(
+    url: &str,
+    headers: &Headers,
+    timeout: Duration,
+    inbox: Arc<Inbox>,
+) -> io::Result<Box<dyn WsSender>> {
+    // The client takes the headers already joined up as they are sent
+    let headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}\r\n"))
+        .collect();
+    let config = EspWebSocketClientConfig {
+        headers: (!headers.is_empty()).then_some(headers.as_str()),
+        buffer_size: BUFFER_SIZE,
+        network_timeout_ms: timeout,
+        // A dropped connection is reported as closed, and the calculator
+        // connects again if it wants to
+        disable_auto_reconnect: true,
+        crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
+        ..Default::default()
+    };
+
+    let (ready, connected) = mpsc::sync_channel(1);
+    let events_inbox = inbox.clone();
+    let client = EspWebSocketClient::new(url, &config, timeout, move |event| {
+        // Continuation frames and text cut off mid-character come through
+        // as errors, [on_data] deals with every piece of data
+        let Ok(WebSocketEvent { event_type, .. }) = event else {
+            return;
+        };
+
+        match event_type {
+            WebSocketEventType::Connected => {
+                let _ = ready.try_send(true);
+            }
+            WebSocketEventType::Close(_)
+            | WebSocketEventType::Closed
+            | WebSocketEventType::Disconnected => {
+                events_inbox.close();
+                let _ = ready.try_send(false);
+            }
+            _ => {}
+        }
+    })
+    .map_err(|e| io_error(e.0))?;
+
+    // The client has already started, but the upgrade takes far longer than
+    // this so nothing can have arrived yet
+    esp!(unsafe {
+        esp_websocket_register_events(
+            client.handle(),
+            esp_websocket_event_id_t_WEBSOCKET_EVENT_DATA,
+            Some(on_data),
+            Arc::as_ptr(&inbox) as *mut c_void,
+        )
+    })
+    .map_err(io_error)?;
+
+    match connected.recv_timeout(timeout) {
+        Ok(true) => Ok(Box::new(Client {
+            client,
+            _inbox: inbox,
+        })),
+        Ok(false) => Err(ErrorKind::ConnectionRefused.into()),
+        Err(_) => Err(ErrorKind::TimedOut.into()),
+    }
+}
+
+/// Hands every piece of data to the [Inbox] `arg` points to, with the
+/// offsets the safe callback leaves out
+unsafe extern "C" fn on_data(
+    arg: *mut c_void,
+    _base: esp_event_base_t,
+    _id: i32,
+    data: *mut c_void,
+) {
+    let inbox = &*(arg as *const Inbox);
+    let data = &*(data as *const esp_websocket_event_data_t);
+
+    let opcode = match data.op_code {
+        0 => Opcode::Continuation,
+        1 => Opcode::Text,
+        2 => Opcode::Binary,
+        // Pings, pongs and closes
+        _ => return,
+    };
+    let piece = match usize::try_from(data.data_len) {
+        Ok(len) if len > 0 => slice::from_raw_parts(data.data_ptr as *const u8, len),
+        _ => &[],
+    };
+
+    inbox.push_piece(
+        opcode,
+        data.fin,
+        data.payload_len as usize,
+        data.payload_offset as usize,
+        piece,
+    );
+}
+
+fn io_error(e: EspError) -> io::Error {
+    io::Error::new(ErrorKind::Other, e)
+}